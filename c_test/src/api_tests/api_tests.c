@@ -9,6 +9,9 @@
 #include <stdio.h>
 #include <string.h>
 #include <stdlib.h>
+#include <stdatomic.h>
+#include <unistd.h>
+#include <poll.h>
 
 /* ============================================================================
  * Test Helpers
@@ -133,6 +136,71 @@ int test_sr_health(void) {
     return TEST_PASS;
 }
 
+int test_sr_connect_with_options(void) {
+    sr_surreal_t *db;
+    sr_string_t err;
+    sr_object_t *result;
+    
+    sr_connect_options_t opts = {0};
+    opts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+    opts.strict = true;
+    opts.query_timeout.secs = 5;
+    opts.namespace_ = "test";
+    opts.database = "strict";
+    
+    int res = sr_connect_with_options(&err, &db, "mem://", &opts);
+    ASSERT_GE(res, 0);
+    ASSERT_NOT_NULL(db);
+    
+    /* Strict databases reject tables which have not been defined */
+    sr_object_t content = sr_object_new();
+    sr_object_insert_str(&content, "name", "test_item");
+    res = sr_create(db, &err, &result, "items", &content);
+    ASSERT_TRUE(res < 0);
+    sr_free_string(err);
+    
+    sr_arr_res_t *query_res;
+    res = sr_query(db, &err, &query_res, "DEFINE TABLE items SCHEMALESS", NULL);
+    ASSERT_GE(res, 0);
+    sr_free_arr_res_arr(query_res, res);
+    
+    res = sr_create(db, &err, &result, "items", &content);
+    ASSERT_GE(res, 0);
+    
+    sr_free_object(content);
+    sr_surreal_disconnect(db);
+    
+    /* Unknown versions are rejected */
+    opts.version = 0;
+    res = sr_connect_with_options(&err, &db, "mem://", &opts);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    sr_free_string(err);
+    return TEST_PASS;
+}
+
+int test_sr_surreal_reconnect(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "CREATE reconnect_items:1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    ASSERT_EQ(sr_surreal_reconnect(db, &err), sr_SR_NONE);
+    
+    /* The namespace and database are selected again, the in-memory data is gone */
+    len = sr_query(db, &err, &results, "CREATE reconnect_items:2; SELECT * FROM reconnect_items", NULL);
+    ASSERT_EQ(len, 2);
+    ASSERT_EQ(results[1].err.code, 0);
+    ASSERT_EQ(sr_array_len(&results[1].ok), 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
 /* ============================================================================
  * Authentication Tests
  * ============================================================================ */
@@ -500,6 +568,538 @@ int test_sr_relate(void) {
     return TEST_PASS;
 }
 
+int test_sr_query_response(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_response_t *resp;
+    int count = sr_query_response(db, &err, &resp, "RETURN 1; RETURN [1]; THROW 'oops'", NULL);
+    ASSERT_EQ(count, 3);
+    ASSERT_EQ(sr_response_count(resp), 3);
+    
+    /* Values are returned as they are, without wrapping into arrays */
+    ASSERT_TRUE(sr_response_is_ok(resp, 0));
+    const sr_value_t *val = sr_response_value(resp, 0);
+    ASSERT_NOT_NULL(val);
+    ASSERT_EQ(val->tag, SR_VALUE_NUMBER);
+    ASSERT_EQ(val->sr_value_number.tag, SR_NUMBER_INT);
+    ASSERT_EQ(val->sr_value_number.sr_number_int, 1);
+    
+    val = sr_response_value(resp, 1);
+    ASSERT_NOT_NULL(val);
+    ASSERT_EQ(val->tag, SR_VALUE_ARRAY);
+    ASSERT_TRUE(sr_response_error(resp, 1) == NULL);
+    
+    /* Failed statements carry an error instead of a value */
+    ASSERT_FALSE(sr_response_is_ok(resp, 2));
+    ASSERT_TRUE(sr_response_value(resp, 2) == NULL);
+    const sr_error_t *e = sr_response_error(resp, 2);
+    ASSERT_NOT_NULL(e);
+    ASSERT_EQ(e->kind, SR_ERROR_KIND_THROWN);
+    
+    /* Out of range indexes are handled */
+    ASSERT_FALSE(sr_response_is_ok(resp, 3));
+    ASSERT_TRUE(sr_response_value(resp, -1) == NULL);
+    sr_duration_t time = sr_response_time(resp, 3);
+    ASSERT_EQ(time.secs, 0);
+    ASSERT_EQ(time.nanos, 0);
+    
+    sr_response_free(resp);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_prepare(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_statement_t *stmt;
+    
+    /* Syntax errors are reported when preparing */
+    int res = sr_prepare(db, &err, &stmt, "SELEC * FROM stmt_items");
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    sr_free_string(err);
+    
+    /* sr_parse_check rejects the same queries */
+    sr_diagnostic_t *diags;
+    int diags_len = sr_parse_check(&err, &diags, "SELEC * FROM stmt_items");
+    ASSERT_GE(diags_len, 1);
+    sr_free_diagnostics(diags, diags_len);
+    
+    res = sr_prepare(db, &err, &stmt, "CREATE stmt_items SET n = $n, name = $name");
+    ASSERT_EQ(res, sr_SR_NONE);
+    
+    /* The statement can be executed repeatedly with new bindings */
+    for (int i = 0; i < 3; i++) {
+        ASSERT_EQ(sr_statement_bind_int(stmt, &err, "n", i), sr_SR_NONE);
+        ASSERT_EQ(sr_statement_bind_str(stmt, &err, "$name", "item"), sr_SR_NONE);
+        
+        sr_arr_res_t *results;
+        int len = sr_statement_execute(stmt, &err, &results);
+        ASSERT_EQ(len, 1);
+        sr_free_arr_res_arr(results, len);
+    }
+    sr_statement_free(stmt);
+    
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "SELECT * FROM stmt_items WHERE name = 'item'", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 3);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_parse_check(void) {
+    sr_string_t err;
+    sr_diagnostic_t *diags;
+    
+    /* Valid queries produce no diagnostics */
+    int len = sr_parse_check(&err, &diags, "SELECT * FROM user WHERE age > $age; RETURN 1");
+    ASSERT_EQ(len, 0);
+    sr_free_diagnostics(diags, len);
+    
+    /* Errors point at the offending token */
+    const char *query = "SELECT *\nFROM user\nWHERE age >> 1";
+    len = sr_parse_check(&err, &diags, query);
+    ASSERT_GE(len, 1);
+    ASSERT_EQ(diags[0].line, 3);
+    ASSERT_EQ(diags[0].column, 12);
+    ASSERT_EQ(diags[0].offset, 30);
+    ASSERT_GE(diags[0].length, 1);
+    ASSERT_NOT_NULL(diags[0].message);
+    sr_free_diagnostics(diags, len);
+    
+    /* Every location involved carries its own message */
+    len = sr_parse_check(&err, &diags, "SELECT * FROM [1, 2");
+    ASSERT_EQ(len, 2);
+    for (int i = 0; i < len; i++) {
+        bool opening = diags[i].offset == 14;
+        ASSERT_EQ(strstr(diags[i].message, "expected this delimiter to close") != NULL, opening);
+    }
+    sr_free_diagnostics(diags, len);
+    
+    /* Invalid arguments are reported as errors */
+    ASSERT_EQ(sr_parse_check(&err, &diags, NULL), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    return TEST_PASS;
+}
+
+static int count_cursor_rows(sr_surreal_t *db, const char *query, int batch_size) {
+    sr_string_t err;
+    sr_cursor_t *cursor;
+    if (sr_cursor_open(db, &err, &cursor, query, NULL, batch_size) < 0) {
+        sr_free_string(err);
+        return -1;
+    }
+    int count = 0;
+    sr_value_t *row;
+    int res;
+    while ((res = sr_cursor_next(cursor, &err, &row)) > 0) {
+        sr_value_free(row);
+        count++;
+    }
+    sr_cursor_close(cursor);
+    if (res < 0) {
+        sr_free_string(err);
+        return -1;
+    }
+    return count;
+}
+
+int test_sr_cursor(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "FOR $i IN 0..25 { CREATE cursor_items SET n = $i }", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Rows are returned across several batches, including a final partial one */
+    ASSERT_EQ(count_cursor_rows(db, "SELECT * FROM cursor_items ORDER BY n", 10), 25);
+    ASSERT_EQ(count_cursor_rows(db, "SELECT * FROM cursor_items ORDER BY n;", 5), 25);
+    
+    /* Queries with their own LIMIT are paginated as a subquery */
+    ASSERT_EQ(count_cursor_rows(db, "SELECT * FROM cursor_items ORDER BY n LIMIT 12", 5), 12);
+    ASSERT_EQ(count_cursor_rows(db, "SELECT * FROM cursor_items WHERE n > 100", 0), 0);
+    
+    /* Rows keep the order of the query */
+    sr_cursor_t *cursor;
+    ASSERT_EQ(sr_cursor_open(db, &err, &cursor, "SELECT VALUE n FROM cursor_items ORDER BY n DESC", NULL, 4), sr_SR_NONE);
+    sr_value_t *row;
+    ASSERT_EQ(sr_cursor_next(cursor, &err, &row), 1);
+    ASSERT_EQ(row->tag, SR_VALUE_NUMBER);
+    ASSERT_EQ(row->sr_value_number.sr_number_int, 24);
+    sr_value_free(row);
+    sr_cursor_close(cursor);
+    
+    /* Only single statements are accepted */
+    ASSERT_EQ(sr_cursor_open(db, &err, &cursor, "SELECT * FROM a; SELECT * FROM b", NULL, 0), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+typedef struct {
+    sr_surreal_t *db;
+    int attempts;
+} txn_run_state_t;
+
+static int txn_run_conflicting(sr_transaction_t *txn, void *userdata) {
+    txn_run_state_t *state = userdata;
+    state->attempts++;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_transaction_query(txn, &err, &results, "UPDATE txn_counter:1 SET n += 1", NULL);
+    if (len < 0) {
+        sr_free_string(err);
+        return len;
+    }
+    sr_free_arr_res_arr(results, len);
+    
+    /* Conflict with the transaction on the first attempt only */
+    if (state->attempts == 1) {
+        len = sr_query(state->db, &err, &results, "UPDATE txn_counter:1 SET n += 100", NULL);
+        if (len < 0) {
+            sr_free_string(err);
+            return len;
+        }
+        sr_free_arr_res_arr(results, len);
+    }
+    return state->attempts;
+}
+
+static int txn_run_failing(sr_transaction_t *txn, void *userdata) {
+    (void)userdata;
+    sr_arr_res_t *results;
+    int len = sr_transaction_query(txn, NULL, &results, "CREATE txn_items:failed", NULL);
+    if (len >= 0) sr_free_arr_res_arr(results, len);
+    return -42;
+}
+
+static int txn_run_failing_op(sr_transaction_t *txn, void *userdata) {
+    (void)userdata;
+    sr_arr_res_t *results;
+    int len = sr_transaction_query(txn, NULL, &results, "SELEC * FROM txn_items", NULL);
+    if (len >= 0) sr_free_arr_res_arr(results, len);
+    /* Later calls outside the transaction do not change the error reported for it */
+    sr_clear_error();
+    return -7;
+}
+
+int test_sr_transaction(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_transaction_t *txn;
+    sr_arr_res_t *results;
+    
+    /* Rolled back changes are discarded */
+    ASSERT_EQ(sr_transaction_begin(db, &err, &txn), sr_SR_NONE);
+    int len = sr_transaction_query(txn, &err, &results, "CREATE txn_items:a SET n = 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_transaction_rollback(txn, &err), sr_SR_NONE);
+    
+    len = sr_query(db, &err, &results, "SELECT * FROM txn_items", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 0);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Committed changes span several calls */
+    ASSERT_EQ(sr_transaction_begin(db, &err, &txn), sr_SR_NONE);
+    len = sr_transaction_query(txn, &err, &results, "CREATE txn_items:a SET n = 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_object_t content = sr_object_new();
+    sr_object_insert_int(&content, "n", 2);
+    sr_value_t *vals;
+    len = sr_transaction_upsert(txn, &err, &vals, "txn_items:b", &content);
+    ASSERT_EQ(len, 1);
+    sr_free_arr(vals, len);
+    sr_free_object(content);
+    
+    /* Reads inside the transaction see its own writes */
+    len = sr_transaction_query(txn, &err, &results, "SELECT * FROM txn_items", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 2);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_transaction_commit(txn, &err), sr_SR_NONE);
+    
+    len = sr_query(db, &err, &results, "SELECT * FROM txn_items", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 2);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_transaction_run(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "CREATE txn_counter:1 SET n = 0", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    /* A conflicting commit is retried in a new transaction */
+    txn_run_state_t state = { db, 0 };
+    err = NULL;
+    int res = sr_transaction_run(db, &err, txn_run_conflicting, &state, 3);
+    ASSERT_EQ(res, 2);
+    ASSERT_EQ(state.attempts, 2);
+    ASSERT_TRUE(err == NULL);
+    
+    len = sr_query(db, &err, &results, "RETURN txn_counter:1.n", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *n = sr_array_get(&results[0].ok, 0);
+    ASSERT_EQ(n->sr_value_number.sr_number_int, 101);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Without retries the conflict is returned */
+    state.attempts = 0;
+    res = sr_transaction_run(db, &err, txn_run_conflicting, &state, 0);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_CONFLICT);
+    sr_free_string(err);
+    
+    /* Failing callbacks roll back without retrying */
+    res = sr_transaction_run(db, &err, txn_run_failing, NULL, 3);
+    ASSERT_EQ(res, -42);
+    sr_free_string(err);
+    len = sr_query(db, &err, &results, "SELECT * FROM txn_items", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 0);
+    sr_free_arr_res_arr(results, len);
+    
+    /* The error of the failed operation is reported, whatever the callback did afterwards */
+    res = sr_transaction_run(db, &err, txn_run_failing_op, NULL, 3);
+    ASSERT_EQ(res, -7);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    sr_free_string(err);
+    
+    /* A successful run leaves the last error of the caller untouched */
+    ASSERT_TRUE(sr_query(db, &err, &results, "SELEC 1", NULL) < 0);
+    sr_free_string(err);
+    state.attempts = 0;
+    res = sr_transaction_run(db, &err, txn_run_conflicting, &state, 3);
+    ASSERT_EQ(res, 2);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_batch(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    sr_batch_t *batch = sr_batch_new();
+    
+    sr_object_t tobie = sr_object_new();
+    sr_object_insert_str(&tobie, "name", "tobie");
+    sr_object_t jaime = sr_object_new();
+    sr_object_insert_str(&jaime, "name", "jaime");
+    sr_object_t since = sr_object_new();
+    sr_object_insert_int(&since, "since", 2020);
+    
+    ASSERT_EQ(sr_batch_create(batch, &err, "batch_person:tobie", &tobie), 0);
+    ASSERT_EQ(sr_batch_create(batch, &err, "batch_person:jaime", &jaime), 1);
+    ASSERT_EQ(sr_batch_relate(batch, &err, "batch_person:tobie", "batch_knows", "batch_person:jaime", &since), 2);
+    ASSERT_EQ(sr_batch_merge(batch, &err, "batch_person:tobie", &since), 3);
+    sr_value_t *age = sr_value_int(40);
+    ASSERT_EQ(sr_batch_patch_add(batch, &err, "batch_person:jaime", "/age", age), 4);
+    sr_value_free(age);
+    ASSERT_EQ(sr_batch_run(batch, &err, "string::len", NULL), 5);
+    ASSERT_EQ(sr_batch_len(batch), 6);
+    
+    /* Invalid operations are rejected when added */
+    ASSERT_EQ(sr_batch_run(batch, &err, "string::len(); DELETE batch_person", NULL), sr_SR_ERROR);
+    sr_free_string(err);
+    ASSERT_EQ(sr_batch_create(batch, &err, NULL, &tobie), sr_SR_ERROR);
+    sr_free_string(err);
+    ASSERT_EQ(sr_batch_len(batch), 6);
+    
+    /* Every operation gets its own result, failures do not stop the batch */
+    int len = sr_batch_execute(db, &err, &results, batch, false);
+    ASSERT_EQ(len, 6);
+    for (int i = 0; i < 5; i++) {
+        ASSERT_EQ(results[i].err.code, 0);
+        ASSERT_EQ(sr_array_len(&results[i].ok), 1);
+    }
+    ASSERT_TRUE(results[5].err.code != 0);
+    sr_free_arr_res_arr(results, len);
+    
+    len = sr_query(db, &err, &results, "SELECT VALUE ->batch_knows->batch_person.name FROM ONLY batch_person:tobie", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 1);
+    sr_free_arr_res_arr(results, len);
+    sr_batch_free(batch);
+    
+    /* Atomic batches apply nothing if any operation fails */
+    batch = sr_batch_new();
+    ASSERT_EQ(sr_batch_delete(batch, &err, "batch_person:jaime"), 0);
+    ASSERT_EQ(sr_batch_create(batch, &err, "batch_person:tobie", &tobie), 1);
+    len = sr_batch_execute(db, &err, &results, batch, true);
+    ASSERT_EQ(len, 2);
+    ASSERT_TRUE(results[1].err.code != 0);
+    sr_free_arr_res_arr(results, len);
+    sr_batch_free(batch);
+    
+    len = sr_query(db, &err, &results, "SELECT * FROM batch_person", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 2);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Batched creates address the same records as sr_create */
+    sr_object_t *created;
+    ASSERT_GE(sr_create(db, &err, &created, "batch_person:1", &tobie), 0);
+    batch = sr_batch_new();
+    ASSERT_EQ(sr_batch_create(batch, &err, "batch_person:1", &jaime), 0);
+    len = sr_batch_execute(db, &err, &results, batch, false);
+    ASSERT_EQ(len, 1);
+    ASSERT_TRUE(results[0].err.code != 0);
+    sr_free_arr_res_arr(results, len);
+    sr_batch_free(batch);
+    
+    /* Batched inserts accept tables and record ids like sr_insert */
+    batch = sr_batch_new();
+    ASSERT_EQ(sr_batch_insert(batch, &err, "batch_animal", &tobie), 0);
+    ASSERT_EQ(sr_batch_insert(batch, &err, "batch_animal:jaime", &jaime), 1);
+    len = sr_batch_execute(db, &err, &results, batch, false);
+    ASSERT_EQ(len, 2);
+    ASSERT_EQ(results[0].err.code, 0);
+    ASSERT_EQ(results[1].err.code, 0);
+    sr_free_arr_res_arr(results, len);
+    sr_batch_free(batch);
+    
+    len = sr_query(db, &err, &results, "SELECT VALUE name FROM batch_animal:jaime", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_free_object(tobie);
+    sr_free_object(jaime);
+    sr_free_object(since);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+static int64_t insert_many_field(const sr_value_t *record, const char *key) {
+    if (record->tag != SR_VALUE_OBJECT) return -1;
+    const sr_value_t *val = sr_object_get(&record->sr_value_object, key);
+    if (val == NULL || val->tag != SR_VALUE_NUMBER) return -1;
+    return val->sr_value_number.sr_number_int;
+}
+
+int test_sr_insert_many(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_value_t *results;
+    const char *ids[] = {"a", "b", "c"};
+    sr_object_t rows[3];
+    for (int i = 0; i < 3; i++) {
+        rows[i] = sr_object_new();
+        sr_object_insert_str(&rows[i], "id", ids[i]);
+        sr_object_insert_str(&rows[i], "name", ids[i]);
+        sr_object_insert_int(&rows[i], "n", i);
+    }
+    
+    int len = sr_insert_many(db, &err, &results, "reading", rows, 3, 0);
+    ASSERT_EQ(len, 3);
+    ASSERT_EQ(insert_many_field(&results[2], "n"), 2);
+    sr_free_arr(results, len);
+    
+    /* Existing records fail the insert by default */
+    len = sr_insert_many(db, &err, &results, "reading", rows, 1, 0);
+    ASSERT_TRUE(len < 0);
+    sr_free_string(err);
+    
+    /* IGNORE skips them and only returns the new records */
+    sr_object_t fresh = sr_object_new();
+    sr_object_insert_str(&fresh, "id", "d");
+    sr_object_insert_int(&fresh, "n", 3);
+    sr_object_t mixed[2] = {rows[0], fresh};
+    len = sr_insert_many(db, &err, &results, "reading", mixed, 2, sr_SR_INSERT_IGNORE);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(insert_many_field(&results[0], "n"), 3);
+    sr_free_arr(results, len);
+    sr_free_object(fresh);
+    
+    /* UPDATE overwrites the fields given and keeps the others */
+    sr_object_t change = sr_object_new();
+    sr_object_insert_str(&change, "id", "a");
+    sr_object_insert_int(&change, "n", 100);
+    len = sr_insert_many(db, &err, &results, "reading", &change, 1, sr_SR_INSERT_UPDATE);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(insert_many_field(&results[0], "n"), 100);
+    ASSERT_EQ(results[0].tag, SR_VALUE_OBJECT);
+    ASSERT_NOT_NULL(sr_object_get(&results[0].sr_value_object, "name"));
+    sr_free_arr(results, len);
+    
+    len = sr_insert_many(db, &err, &results, "reading", &change, 1, sr_SR_INSERT_IGNORE | sr_SR_INSERT_UPDATE);
+    ASSERT_TRUE(len < 0);
+    sr_free_string(err);
+    sr_free_object(change);
+    
+    /* DISCARD returns nothing */
+    sr_object_t more[2] = {sr_object_new(), sr_object_new()};
+    sr_object_insert_int(&more[0], "n", 4);
+    sr_object_insert_int(&more[1], "n", 5);
+    len = sr_insert_many(db, &err, NULL, "reading", more, 2, sr_SR_INSERT_DISCARD);
+    ASSERT_EQ(len, 0);
+    sr_free_object(more[0]);
+    sr_free_object(more[1]);
+    
+    /* Array variant */
+    sr_value_t *row = sr_value_object(&rows[0]);
+    sr_array_t *arr = sr_array_push(NULL, row);
+    sr_value_free(row);
+    len = sr_insert_many_arr(db, &err, &results, "reading", arr, sr_SR_INSERT_IGNORE);
+    ASSERT_EQ(len, 0);
+    
+    sr_value_t *not_obj = sr_value_int(1);
+    sr_array_t *bad = sr_array_push(arr, not_obj);
+    sr_value_free(not_obj);
+    len = sr_insert_many_arr(db, &err, &results, "reading", bad, 0);
+    ASSERT_TRUE(len < 0);
+    sr_free_string(err);
+    sr_array_free(bad);
+    sr_array_free(arr);
+    
+    sr_arr_res_t *count;
+    len = sr_query(db, &err, &count, "RETURN count(SELECT * FROM reading)", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *total = sr_array_get(&count[0].ok, 0);
+    ASSERT_NOT_NULL(total);
+    ASSERT_EQ(total->sr_value_number.sr_number_int, 6);
+    sr_free_arr_res_arr(count, len);
+    
+    for (int i = 0; i < 3; i++) {
+        sr_free_object(rows[i]);
+    }
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
 /* ============================================================================
  * Patch Tests
  * ============================================================================ */
@@ -692,6 +1292,177 @@ int test_sr_select_live(void) {
     return TEST_PASS;
 }
 
+int test_sr_live_query(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "DEFINE TABLE orders", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_object_t vars = sr_object_new();
+    sr_object_insert_str(&vars, "s", "open");
+    sr_stream_t *stream;
+    ASSERT_EQ(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM orders WHERE status = $s", &vars), 1);
+    ASSERT_NOT_NULL(stream);
+    
+    /* Only the records matching the filter are notified */
+    len = sr_query(db, &err, &results, "CREATE orders:1 SET status = 'closed'; CREATE orders:2 SET status = 'open'", NULL);
+    ASSERT_EQ(len, 2);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_notification_t not;
+    ASSERT_EQ(sr_stream_next_timeout(stream, &not, 5000), 1);
+    ASSERT_EQ(not.action, SR_ACTION_CREATE);
+    ASSERT_EQ(not.data.tag, SR_VALUE_OBJECT);
+    const sr_value_t *status = sr_object_get(&not.data.sr_value_object, "status");
+    ASSERT_NOT_NULL(status);
+    ASSERT_TRUE(strcmp(status->sr_value_strand, "open") == 0);
+    ASSERT_EQ(sr_stream_next_timeout(stream, &not, 200), sr_SR_TIMEOUT);
+    sr_stream_kill(stream);
+    
+    /* The query must be a single statement */
+    ASSERT_TRUE(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM orders; LIVE SELECT * FROM orders", NULL) < 0);
+    sr_free_string(err);
+    ASSERT_TRUE(sr_live_query(db, &err, &stream, "SELECT * FROM orders", NULL) < 0);
+    sr_free_string(err);
+    
+    /* Rejected queries are not executed */
+    ASSERT_TRUE(sr_live_query(db, &err, &stream, "CREATE orders:3; LIVE SELECT * FROM orders", NULL) < 0);
+    sr_free_string(err);
+    len = sr_query(db, &err, &results, "SELECT * FROM orders:3", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_free_object(vars);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_select_live_diff(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "DEFINE TABLE docs", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_stream_t *stream;
+    ASSERT_EQ(sr_select_live_diff(db, &err, &stream, "docs"), 1);
+    
+    len = sr_query(db, &err, &results,
+                   "CREATE docs:1 SET title = 'hello world', tags = ['a', 'b', 'c', 'd'], meta = { n: 1 };"
+                   "UPDATE docs:1 SET title = 'hello there world', tags = ['a'], meta.n = 2, done = true;", NULL);
+    ASSERT_EQ(len, 2);
+    sr_free_arr_res_arr(results, len);
+    
+    /* A creation replaces the whole document */
+    sr_value_t *cache = sr_value_none();
+    sr_diff_notification_t not;
+    ASSERT_EQ(sr_stream_next_diff_timeout(stream, &not, 5000), 1);
+    ASSERT_EQ(not.action, SR_ACTION_CREATE);
+    ASSERT_EQ(not.ops_len, 1);
+    ASSERT_EQ(not.ops[0].op, SR_DIFF_REPLACE);
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, not.ops, not.ops_len), sr_SR_NONE);
+    sr_free_diff_notification(not);
+    ASSERT_EQ(cache->tag, SR_VALUE_OBJECT);
+    
+    /* An update only holds the changes, strings are changed with a text patch */
+    ASSERT_EQ(sr_stream_next_diff_timeout(stream, &not, 5000), 1);
+    ASSERT_EQ(not.action, SR_ACTION_UPDATE);
+    int changes = 0;
+    for (int i = 0; i < not.ops_len; i++) {
+        if (not.ops[i].op == SR_DIFF_CHANGE) changes++;
+    }
+    ASSERT_EQ(changes, 1);
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, not.ops, not.ops_len), sr_SR_NONE);
+    sr_free_diff_notification(not);
+    
+    len = sr_query(db, &err, &results, "SELECT * FROM ONLY docs:1", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_TRUE(sr_value_eq(cache, sr_array_get(&results[0].ok, 0)));
+    sr_free_arr_res_arr(results, len);
+    
+    /* A failing operation leaves the value unchanged */
+    sr_diff_op_t bad[2] = {0};
+    bad[0].op = SR_DIFF_REMOVE;
+    bad[0].path = "/done";
+    bad[1].op = SR_DIFF_CHANGE;
+    bad[1].path = "/meta/n";
+    bad[1].value.tag = SR_VALUE_STRAND;
+    bad[1].value.sr_value_strand = "@@ -1 +1 @@";
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, bad, 2), sr_SR_ERROR);
+    sr_free_string(err);
+    ASSERT_NOT_NULL(sr_object_get(&cache->sr_value_object, "done"));
+    
+    /* Elements removed from the end of an array are listed in ascending order, as the server does */
+    sr_value_t *list = sr_value_array();
+    sr_diff_op_t ops[4] = {0};
+    const char *paths[4] = {"/0", "/1", "/2", "/3"};
+    for (int i = 0; i < 4; i++) {
+        ops[i].op = SR_DIFF_ADD;
+        ops[i].path = (char *)paths[i];
+        ops[i].value.tag = SR_VALUE_STRAND;
+        ops[i].value.sr_value_strand = (char *)paths[i];
+    }
+    ASSERT_EQ(sr_value_apply_diff(&err, list, ops, 4), sr_SR_NONE);
+    ASSERT_EQ(sr_array_len(list->sr_value_array), 4);
+    for (int i = 0; i < 3; i++) {
+        ops[i].op = SR_DIFF_REMOVE;
+        ops[i].path = (char *)paths[i + 1];
+        ops[i].value.tag = SR_VALUE_NONE;
+    }
+    ASSERT_EQ(sr_value_apply_diff(&err, list, ops, 3), sr_SR_NONE);
+    ASSERT_EQ(sr_array_len(list->sr_value_array), 1);
+    
+    /* Missing values are not silently skipped */
+    ops[0].op = SR_DIFF_REMOVE;
+    ops[0].path = "/1";
+    ASSERT_EQ(sr_value_apply_diff(&err, list, ops, 1), sr_SR_ERROR);
+    sr_free_string(err);
+    ops[0].op = SR_DIFF_ADD;
+    ops[0].path = "/3";
+    ops[0].value.tag = SR_VALUE_BOOL;
+    ops[0].value.sr_value_bool = true;
+    ASSERT_EQ(sr_value_apply_diff(&err, list, ops, 1), sr_SR_ERROR);
+    sr_free_string(err);
+    ops[0].op = SR_DIFF_REMOVE;
+    ops[0].path = "/missing";
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, ops, 1), sr_SR_ERROR);
+    sr_free_string(err);
+    ops[0].op = SR_DIFF_REPLACE;
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, ops, 1), sr_SR_ERROR);
+    sr_free_string(err);
+    ASSERT_EQ(sr_array_len(list->sr_value_array), 1);
+    sr_value_free(list);
+    
+    len = sr_query(db, &err, &results, "DELETE docs:1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_stream_next_diff_timeout(stream, &not, 5000), 1);
+    ASSERT_EQ(not.action, SR_ACTION_DELETE);
+    ASSERT_EQ(sr_value_apply_diff(&err, cache, not.ops, not.ops_len), sr_SR_NONE);
+    sr_free_diff_notification(not);
+    ASSERT_EQ(cache->tag, SR_VALUE_NONE);
+    sr_stream_kill(stream);
+    sr_value_free(cache);
+    
+    /* Notifications of other live queries are not diffs */
+    ASSERT_EQ(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM docs", NULL), 1);
+    len = sr_query(db, &err, &results, "CREATE docs:2", NULL);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_stream_next_diff_timeout(stream, &not, 5000), sr_SR_ERROR);
+    sr_stream_kill(stream);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
 /* ============================================================================
  * Import/Export Tests
  * ============================================================================ */
@@ -1088,6 +1859,194 @@ int test_sr_rpc_stream_free(void) {
     return TEST_SKIP;
 }
 
+int test_sr_stream_try_next(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "DEFINE TABLE ticks", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_stream_t *stream;
+    ASSERT_EQ(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM ticks", NULL), 1);
+    
+    sr_notification_t not;
+    ASSERT_EQ(sr_stream_try_next(stream, &not), sr_SR_NONE);
+    ASSERT_EQ(sr_stream_next_timeout(stream, &not, 10), sr_SR_TIMEOUT);
+    
+    len = sr_query(db, &err, &results, "CREATE ticks:1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Poll from a loop until the notification arrives */
+    int res = sr_SR_NONE;
+    for (int i = 0; i < 5000 && res == sr_SR_NONE; i++) {
+        res = sr_stream_try_next(stream, &not);
+        if (res == sr_SR_NONE) usleep(1000);
+    }
+    ASSERT_EQ(res, 1);
+    ASSERT_EQ(not.action, SR_ACTION_CREATE);
+    ASSERT_EQ(sr_stream_try_next(stream, &not), sr_SR_NONE);
+    
+    sr_stream_kill(stream);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_rpc_stream_try_next(void) {
+    sr_surreal_rpc_t *rpc;
+    sr_string_t err;
+    sr_option_t opts = {0};
+    ASSERT_GE(sr_surreal_rpc_new(&err, &rpc, "memory", opts), 0);
+    
+    sr_RpcStream *stream;
+    ASSERT_EQ(sr_surreal_rpc_notifications(rpc, &err, &stream), 1);
+    
+    uint8_t *res;
+    ASSERT_EQ(sr_rpc_stream_try_next(stream, &res), sr_SR_NONE);
+    ASSERT_EQ(sr_rpc_stream_next_timeout(stream, &res, 10), sr_SR_TIMEOUT);
+    ASSERT_EQ(sr_rpc_stream_next_timeout(stream, &res, 0), sr_SR_TIMEOUT);
+    
+    sr_rpc_stream_free(stream);
+    sr_surreal_rpc_free(rpc);
+    return TEST_PASS;
+}
+
+typedef struct {
+    atomic_int notifications;
+    atomic_int last_code;
+    atomic_int closes;
+    sr_subscription_t *unsubscribe_on_first;
+} subscription_state_t;
+
+static void on_notification(void *userdata, int code, const sr_notification_t *notification, const sr_error_t *err) {
+    subscription_state_t *state = userdata;
+    (void)err;
+    if (code == 1 && notification != NULL) {
+        if (atomic_fetch_add(&state->notifications, 1) == 0 && state->unsubscribe_on_first != NULL) {
+            sr_stream_unsubscribe(state->unsubscribe_on_first);
+        }
+    } else {
+        atomic_fetch_add(&state->closes, 1);
+    }
+    atomic_store(&state->last_code, code);
+}
+
+static int wait_for_count(atomic_int *count, int expected) {
+    for (int i = 0; i < 5000 && atomic_load(count) < expected; i++) {
+        usleep(1000);
+    }
+    return atomic_load(count) == expected;
+}
+
+int test_sr_stream_subscribe(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "DEFINE TABLE events", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    subscription_state_t state = {0};
+    sr_stream_t *stream;
+    sr_subscription_t *sub;
+    ASSERT_EQ(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM events", NULL), 1);
+    ASSERT_EQ(sr_stream_subscribe(stream, &sub, on_notification, &state), sr_SR_NONE);
+    
+    len = sr_query(db, &err, &results, "CREATE events:1; CREATE events:2; CREATE events:3", NULL);
+    ASSERT_EQ(len, 3);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_TRUE(wait_for_count(&state.notifications, 3));
+    ASSERT_EQ(atomic_load(&state.last_code), 1);
+    
+    /* Nothing is dispatched once unsubscribed */
+    sr_stream_unsubscribe(sub);
+    len = sr_query(db, &err, &results, "CREATE events:4", NULL);
+    sr_free_arr_res_arr(results, len);
+    usleep(50000);
+    ASSERT_EQ(atomic_load(&state.notifications), 3);
+    ASSERT_EQ(atomic_load(&state.closes), 0);
+    
+    /* The callback may unsubscribe itself */
+    subscription_state_t self = {0};
+    ASSERT_EQ(sr_live_query(db, &err, &stream, "LIVE SELECT * FROM events", NULL), 1);
+    ASSERT_EQ(sr_stream_subscribe(stream, &self.unsubscribe_on_first, on_notification, &self), sr_SR_NONE);
+    len = sr_query(db, &err, &results, "CREATE events:5; CREATE events:6", NULL);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_TRUE(wait_for_count(&self.notifications, 1));
+    usleep(50000);
+    ASSERT_EQ(atomic_load(&self.notifications), 1);
+    
+    ASSERT_EQ(sr_stream_subscribe(NULL, &sub, on_notification, &state), sr_SR_ERROR);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_stream_set(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "DEFINE TABLE left_t; DEFINE TABLE right_t", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_stream_set_t *set = sr_stream_set_new();
+    ASSERT_NOT_NULL(set);
+    int index = -1;
+    sr_notification_t not;
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, -1), sr_SR_NONE);
+    
+    sr_stream_t *left, *right;
+    ASSERT_EQ(sr_live_query(db, &err, &left, "LIVE SELECT * FROM left_t", NULL), 1);
+    ASSERT_EQ(sr_live_query(db, &err, &right, "LIVE SELECT * FROM right_t", NULL), 1);
+    ASSERT_EQ(sr_stream_set_add(set, left), 0);
+    ASSERT_EQ(sr_stream_set_add(set, right), 1);
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, 0), sr_SR_TIMEOUT);
+    
+    /* The descriptor becomes readable once a stream has a notification */
+    int fd = sr_stream_set_fd(set, &err);
+    ASSERT_GE(fd, 0);
+    len = sr_query(db, &err, &results, "CREATE right_t:1", NULL);
+    sr_free_arr_res_arr(results, len);
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    ASSERT_EQ(poll(&pfd, 1, 5000), 1);
+    char buf[64];
+    while (read(fd, buf, sizeof(buf)) > 0) {}
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, 0), 1);
+    ASSERT_EQ(index, 1);
+    ASSERT_EQ(not.action, SR_ACTION_CREATE);
+    
+    len = sr_query(db, &err, &results, "CREATE left_t:1", NULL);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, 5000), 1);
+    ASSERT_EQ(index, 0);
+    
+    /* Removed streams are killed and no longer reported */
+    sr_stream_set_remove(set, 0);
+    len = sr_query(db, &err, &results, "CREATE left_t:2", NULL);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, 100), sr_SR_TIMEOUT);
+    len = sr_query(db, &err, &results, "CREATE right_t:2", NULL);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, 5000), 1);
+    ASSERT_EQ(index, 1);
+    
+    sr_stream_set_remove(set, 1);
+    ASSERT_EQ(sr_stream_set_next(set, &index, &not, -1), sr_SR_NONE);
+    ASSERT_EQ(sr_stream_set_add(set, NULL), sr_SR_ERROR);
+    
+    sr_stream_set_free(set);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
 /* ============================================================================
  * Utility Tests
  * ============================================================================ */
@@ -1328,3 +2287,924 @@ int test_sr_kill(void) {
     sr_surreal_disconnect(db);
     return TEST_PASS;
 }
+
+/* ============================================================================
+ * Error Tests
+ * ============================================================================ */
+
+int test_sr_error_kind(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_error_t err;
+    sr_object_t *result;
+    sr_object_t content = sr_object_new();
+    sr_object_insert_str(&content, "name", "test_item");
+    
+    int res = sr_create_ex(db, &err, &result, "items:dup", &content);
+    ASSERT_GE(res, 0);
+    
+    /* Creating the same record twice is reported as ALREADY_EXISTS */
+    res = sr_create_ex(db, &err, &result, "items:dup", &content);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(err.code, sr_SR_ERROR);
+    ASSERT_EQ(err.kind, SR_ERROR_KIND_ALREADY_EXISTS);
+    ASSERT_NOT_NULL(err.msg);
+    sr_free_error(err);
+    
+    /* Null arguments are reported as INVALID_ARGUMENT */
+    res = sr_create_ex(db, &err, &result, NULL, &content);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(err.kind, SR_ERROR_KIND_INVALID_ARGUMENT);
+    sr_free_error(err);
+    
+    /* Invalid SurrealQL is reported as PARSE */
+    sr_arr_res_t *query_res;
+    res = sr_query_ex(db, &err, &query_res, "SELEC * FROM items", NULL);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(err.kind, SR_ERROR_KIND_PARSE);
+    sr_free_error(err);
+    
+    sr_free_object(content);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_last_error(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_clear_error();
+    ASSERT_TRUE(sr_last_error() == NULL);
+    ASSERT_EQ(sr_last_error_code(), 0);
+    
+    /* Errors are recorded even when err_ptr is NULL */
+    sr_arr_res_t *query_res;
+    int res = sr_query(db, NULL, &query_res, "SELEC * FROM items", NULL);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_NOT_NULL(sr_last_error());
+    ASSERT_EQ(sr_last_error_code(), sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    
+    /* Successful calls leave the last error in place */
+    res = sr_query(db, NULL, &query_res, "RETURN 1", NULL);
+    ASSERT_GE(res, 0);
+    sr_free_arr_res_arr(query_res, res);
+    ASSERT_EQ(sr_last_error_code(), sr_SR_ERROR);
+    
+    /* Null argument checks are recorded too */
+    res = sr_use_ns(db, NULL, NULL);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_INVALID_ARGUMENT);
+    ASSERT_TRUE(strcmp(sr_last_error(), "ns_name is null") == 0);
+    
+    sr_clear_error();
+    ASSERT_TRUE(sr_last_error() == NULL);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_NONE);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+/* ============================================================================
+ * Async Tests
+ * ============================================================================ */
+
+typedef struct {
+    atomic_int done;
+    int len;
+    int kind;
+} async_result_t;
+
+static void on_values(void *userdata, int len, sr_value_t *values, const sr_error_t *err) {
+    async_result_t *r = userdata;
+    r->len = len;
+    r->kind = err ? err->kind : SR_ERROR_KIND_NONE;
+    if (len >= 0) sr_free_arr(values, len);
+    atomic_store(&r->done, 1);
+}
+
+static void on_object(void *userdata, int code, sr_object_t *obj, const sr_error_t *err) {
+    async_result_t *r = userdata;
+    r->len = code;
+    r->kind = err ? err->kind : SR_ERROR_KIND_NONE;
+    if (code >= 0) sr_free_object(*obj);
+    atomic_store(&r->done, 1);
+}
+
+static void on_query(void *userdata, int len, sr_arr_res_t *results, const sr_error_t *err) {
+    async_result_t *r = userdata;
+    r->len = len;
+    r->kind = err ? err->kind : SR_ERROR_KIND_NONE;
+    if (len >= 0) sr_free_arr_res_arr(results, len);
+    atomic_store(&r->done, 1);
+}
+
+typedef struct {
+    async_result_t result;
+    sr_surreal_t *db;
+    int explained;
+} blocking_call_t;
+
+/* Makes a blocking call from inside the callback, which must fail without poisoning the connection */
+static void on_values_blocking(void *userdata, int len, sr_value_t *values, const sr_error_t *err) {
+    blocking_call_t *b = userdata;
+    if (len >= 0) sr_free_arr(values, len);
+    sr_arr_res_t *query_res;
+    b->result.len = sr_query(b->db, NULL, &query_res, "RETURN 1", NULL);
+    b->result.kind = sr_last_error_kind();
+    b->explained = strstr(sr_last_error(), "completion callback") != NULL;
+    atomic_store(&b->result.done, 1);
+}
+
+/* Wait up to 5 seconds for a callback to fire */
+static int wait_done(async_result_t *r) {
+    for (int i = 0; i < 5000 && !atomic_load(&r->done); i++) {
+        usleep(1000);
+    }
+    return atomic_load(&r->done);
+}
+
+int test_sr_async_callbacks(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_object_t content = sr_object_new();
+    sr_object_insert_str(&content, "name", "async_item");
+    
+    async_result_t created = {0};
+    int res = sr_create_async(db, &err, "async_items:1", &content, on_object, &created);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_TRUE(wait_done(&created));
+    ASSERT_EQ(created.len, 1);
+    
+    async_result_t selected = {0};
+    res = sr_select_async(db, &err, "async_items", on_values, &selected);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_TRUE(wait_done(&selected));
+    ASSERT_EQ(selected.len, 1);
+    
+    /* Errors are delivered to the callback */
+    async_result_t duplicate = {0};
+    res = sr_create_async(db, &err, "async_items:1", &content, on_object, &duplicate);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_TRUE(wait_done(&duplicate));
+    ASSERT_EQ(duplicate.len, sr_SR_ERROR);
+    ASSERT_EQ(duplicate.kind, SR_ERROR_KIND_ALREADY_EXISTS);
+    
+    async_result_t queried = {0};
+    res = sr_query_async(db, &err, "SELECT * FROM async_items; RETURN 1", NULL, on_query, &queried);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_TRUE(wait_done(&queried));
+    ASSERT_EQ(queried.len, 2);
+    
+    /* Invalid arguments are reported immediately */
+    res = sr_select_async(db, &err, "async_items", NULL, NULL);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    sr_free_string(err);
+    
+    /* Blocking calls inside a callback are rejected, and the connection stays usable */
+    blocking_call_t blocking = {.db = db};
+    res = sr_select_async(db, &err, "async_items", on_values_blocking, &blocking);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_TRUE(wait_done(&blocking.result));
+    ASSERT_EQ(blocking.result.len, sr_SR_ERROR);
+    ASSERT_TRUE(blocking.explained);
+    
+    sr_arr_res_t *query_res;
+    res = sr_query(db, &err, &query_res, "RETURN 1", NULL);
+    ASSERT_EQ(res, 1);
+    sr_free_arr_res_arr(query_res, res);
+    
+    sr_free_object(content);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_future(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    int fd = sr_surreal_fd(db, &err);
+    ASSERT_GE(fd, 0);
+    
+    sr_object_t content = sr_object_new();
+    sr_object_insert_str(&content, "name", "future_item");
+    
+    sr_future_t *created;
+    int res = sr_create_future(db, &err, &created, "future_items:1", &content);
+    ASSERT_EQ(res, sr_SR_NONE);
+    
+    /* The descriptor becomes readable once the future completes */
+    struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    ASSERT_EQ(poll(&pfd, 1, 5000), 1);
+    char buf[64];
+    while (read(fd, buf, sizeof(buf)) > 0) {}
+    ASSERT_EQ(sr_future_poll(created), 1);
+    
+    sr_object_t *obj;
+    res = sr_future_take_result(created, &err, &obj);
+    ASSERT_EQ(res, 1);
+    sr_free_object(*obj);
+    
+    /* A result can only be taken once */
+    res = sr_future_take_result(created, &err, &obj);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    sr_free_string(err);
+    sr_future_free(created);
+    
+    sr_future_t *selected;
+    res = sr_select_future(db, &err, &selected, "future_items");
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_EQ(sr_future_wait(selected, 5000), 1);
+    sr_value_t *vals;
+    int len = sr_future_take_result(selected, &err, &vals);
+    ASSERT_EQ(len, 1);
+    sr_free_arr(vals, len);
+    sr_future_free(selected);
+    
+    /* Errors are reported when the result is taken */
+    sr_future_t *queried;
+    res = sr_query_future(db, &err, &queried, "SELEC * FROM future_items", NULL);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_EQ(sr_future_wait(queried, -1), 1);
+    sr_arr_res_t *results;
+    res = sr_future_take_result(queried, &err, &results);
+    ASSERT_EQ(res, sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    sr_free_string(err);
+    sr_future_free(queried);
+    
+    sr_free_object(content);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_cancel_token(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    sr_cancel_token_t *token = sr_cancel_token_new();
+    ASSERT_NOT_NULL(token);
+    ASSERT_FALSE(sr_cancel_token_is_cancelled(token));
+    
+    /* A cancelled token aborts the call */
+    sr_cancel_token_cancel(token);
+    ASSERT_TRUE(sr_cancel_token_is_cancelled(token));
+    int len = sr_query_cancellable(db, &err, token, &results, "SLEEP 10s", NULL);
+    ASSERT_EQ(len, sr_SR_CANCELLED);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_CANCELLED);
+    sr_free_string(err);
+    
+    /* A reset token can be reused */
+    sr_cancel_token_reset(token);
+    len = sr_query_cancellable(db, &err, token, &results, "RETURN 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    sr_cancel_token_free(token);
+    
+    /* Interrupting the connection cancels in-flight operations */
+    sr_future_t *fut;
+    int res = sr_query_future(db, &err, &fut, "SLEEP 10s", NULL);
+    ASSERT_EQ(res, sr_SR_NONE);
+    sr_surreal_interrupt(db);
+    ASSERT_EQ(sr_future_wait(fut, 5000), 1);
+    res = sr_future_take_result(fut, &err, &results);
+    ASSERT_EQ(res, sr_SR_CANCELLED);
+    sr_free_string(err);
+    sr_future_free(fut);
+    
+    /* The connection stays usable */
+    len = sr_query(db, &err, &results, "RETURN 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_timeout(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    
+    int len = sr_query_timeout(db, &err, &results, "SLEEP 10s", NULL, 50);
+    ASSERT_EQ(len, sr_SR_TIMEOUT);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_TIMEOUT);
+    sr_free_string(err);
+    
+    ASSERT_EQ(sr_health_timeout(db, &err, 5000), 0);
+    
+    /* The default timeout applies to every call */
+    sr_surreal_set_timeout(db, 50);
+    len = sr_query(db, &err, &results, "SLEEP 10s", NULL);
+    ASSERT_EQ(len, sr_SR_TIMEOUT);
+    sr_free_string(err);
+    
+    /* A per-call timeout overrides the default */
+    len = sr_query_timeout(db, &err, &results, "SLEEP 100ms; RETURN 1", NULL, -1);
+    ASSERT_EQ(len, 2);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_set_timeout(db, -1);
+    len = sr_query(db, &err, &results, "SLEEP 100ms", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    /* A timeout bound to the thread applies to every call made on it */
+    sr_timeout_bind(50);
+    len = sr_query(db, &err, &results, "SLEEP 10s", NULL);
+    ASSERT_EQ(len, sr_SR_TIMEOUT);
+    sr_free_string(err);
+    ASSERT_EQ(sr_health(db, &err), 0);
+    sr_timeout_unbind();
+    len = sr_query(db, &err, &results, "SLEEP 100ms", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_cancel_token_bind(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    sr_cancel_token_t *token = sr_cancel_token_new();
+    
+    /* A token bound to the thread aborts calls without a token parameter */
+    sr_cancel_token_bind(token);
+    sr_cancel_token_cancel(token);
+    int len = sr_query(db, &err, &results, "SLEEP 10s", NULL);
+    ASSERT_EQ(len, sr_SR_CANCELLED);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_CANCELLED);
+    sr_free_string(err);
+    
+    /* Unbinding restores uncancelled calls */
+    sr_cancel_token_unbind();
+    len = sr_query(db, &err, &results, "RETURN 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_cancel_token_free(token);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+/* ============================================================================
+ * Runtime Tests
+ * ============================================================================ */
+
+static void count_thread_start(void *userdata) {
+    atomic_fetch_add((atomic_int *)userdata, 1);
+}
+
+int test_sr_runtime_shared(void) {
+    sr_string_t err;
+    atomic_int started = 0;
+    
+    sr_runtime_options_t rt_opts = {0};
+    rt_opts.version = sr_SR_RUNTIME_OPTIONS_VERSION;
+    rt_opts.worker_threads = 2;
+    rt_opts.thread_name = "sr-test-worker";
+    rt_opts.on_thread_start = count_thread_start;
+    rt_opts.userdata = &started;
+    
+    sr_runtime_t *rt;
+    int res = sr_runtime_new(&err, &rt, &rt_opts);
+    ASSERT_EQ(res, sr_SR_NONE);
+    ASSERT_NOT_NULL(rt);
+    
+    sr_connect_options_t opts = {0};
+    opts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+    opts.namespace_ = "test";
+    opts.database = "test";
+    opts.runtime = rt;
+    
+    sr_surreal_t *db1;
+    sr_surreal_t *db2;
+    ASSERT_GE(sr_connect_with_options(&err, &db1, "mem://", &opts), 0);
+    ASSERT_GE(sr_connect_with_options(&err, &db2, "mem://", &opts), 0);
+    
+    sr_surreal_rpc_t *rpc;
+    sr_option_t rpc_opts = {0};
+    ASSERT_GE(sr_surreal_rpc_new_with_runtime(&err, &rpc, "memory", rpc_opts, rt), 0);
+    
+    /* Connections keep the runtime alive after it is released */
+    sr_runtime_free(rt);
+    
+    sr_arr_res_t *results;
+    int len = sr_query(db1, &err, &results, "RETURN 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    len = sr_query(db2, &err, &results, "RETURN 2", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    ASSERT_EQ(atomic_load(&started), 2);
+    
+    sr_surreal_rpc_free(rpc);
+    sr_surreal_disconnect(db1);
+    sr_surreal_disconnect(db2);
+    return TEST_PASS;
+}
+
+int test_sr_runtime_current_thread(void) {
+    sr_string_t err;
+    sr_runtime_options_t rt_opts = {0};
+    rt_opts.version = sr_SR_RUNTIME_OPTIONS_VERSION;
+    rt_opts.current_thread = true;
+    
+    sr_runtime_t *rt;
+    ASSERT_EQ(sr_runtime_new(&err, &rt, &rt_opts), sr_SR_NONE);
+    
+    sr_connect_options_t opts = {0};
+    opts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+    opts.namespace_ = "test";
+    opts.database = "test";
+    opts.runtime = rt;
+    
+    sr_surreal_t *db;
+    ASSERT_GE(sr_connect_with_options(&err, &db, "mem://", &opts), 0);
+    
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "RETURN 1", NULL);
+    ASSERT_EQ(len, 1);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    sr_runtime_free(rt);
+    
+    /* Unsupported versions are rejected */
+    rt_opts.version = 0;
+    ASSERT_EQ(sr_runtime_new(&err, &rt, &rt_opts), sr_SR_ERROR);
+    sr_free_string(err);
+    return TEST_PASS;
+}
+
+/* ============================================================================
+ * Load Tests
+ * ============================================================================ */
+
+static void count_load_progress(void *userdata, int64_t rows, int64_t bytes) {
+    (void)rows;
+    (void)bytes;
+    (*(int *)userdata)++;
+}
+
+int test_sr_load_ndjson(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    const char *data = "{\"id\": \"a\", \"n\": 1}\n\n{\"n\": 2, \"tags\": [\"x\"]}\nnot json\n[1, 2]\n{\"n\": 3}";
+    
+    sr_load_options_t opts = {0};
+    opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+    opts.on_error = sr_SR_LOAD_COLLECT;
+    
+    /* Bad lines are skipped and reported with their line number */
+    sr_load_report_t report;
+    ASSERT_EQ(sr_load_ndjson_buf(db, &err, &report, "ndjson_rows", data, (int)strlen(data), &opts), sr_SR_NONE);
+    ASSERT_EQ(report.loaded, 3);
+    ASSERT_EQ(report.skipped, 2);
+    ASSERT_EQ(report.errors_len, 2);
+    ASSERT_EQ(report.errors[0].line, 4);
+    ASSERT_EQ(report.errors[1].line, 5);
+    sr_free_load_report(report);
+    
+    /* Rows failing to insert are skipped like rows failing to parse */
+    opts.on_error = sr_SR_LOAD_SKIP;
+    ASSERT_EQ(sr_load_ndjson_buf(db, &err, &report, "ndjson_rows", data, (int)strlen(data), &opts), sr_SR_NONE);
+    ASSERT_EQ(report.loaded, 2);
+    ASSERT_EQ(report.skipped, 3);
+    ASSERT_EQ(report.errors_len, 0);
+    sr_free_load_report(report);
+    
+    /* By default the load stops at the first error */
+    ASSERT_EQ(sr_load_ndjson_buf(db, &err, &report, "ndjson_other", data, (int)strlen(data), NULL), sr_SR_ERROR);
+    ASSERT_EQ(report.loaded, 2);
+    ASSERT_TRUE(strstr(err, "line 4") != NULL);
+    sr_free_string(err);
+    sr_free_load_report(report);
+    
+    /* A failing insert reports the line of the row at fault, not the first of its batch */
+    const char *dupes = "{\"id\": \"s1\"}\n{\"id\": \"s2\"}\n{\"id\": \"s1\"}\n";
+    ASSERT_EQ(sr_load_ndjson_buf(db, &err, &report, "ndjson_stop", dupes, (int)strlen(dupes), NULL), sr_SR_ERROR);
+    ASSERT_EQ(report.loaded, 2);
+    ASSERT_TRUE(strstr(err, "line 3") != NULL);
+    sr_free_string(err);
+    sr_free_load_report(report);
+    
+    ASSERT_EQ(sr_load_ndjson(db, &err, NULL, "ndjson_rows", "/nonexistent/rows.ndjson", NULL), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "SELECT VALUE n FROM ndjson_rows ORDER BY n", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 5);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+int test_sr_load_csv(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    const char *data = "id,name,price,listed,meta\n"
+                       "a,apple,1.5,true,\"{\"\"k\"\": 1}\"\n"
+                       "b,pear,cheap,false,\n"
+                       "c,\"plum, red\",2,0,[1]\n"
+                       "d,short\n";
+    int types[] = {sr_SR_COLUMN_STRING, sr_SR_COLUMN_STRING, sr_SR_COLUMN_FLOAT, sr_SR_COLUMN_BOOL, sr_SR_COLUMN_JSON};
+    int batches = 0;
+    
+    sr_load_options_t opts = {0};
+    opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+    opts.on_error = sr_SR_LOAD_COLLECT;
+    opts.batch_size = 1;
+    opts.progress = count_load_progress;
+    opts.userdata = &batches;
+    opts.column_types = types;
+    opts.column_count = 5;
+    
+    sr_load_report_t report;
+    ASSERT_EQ(sr_load_csv_buf(db, &err, &report, "product", data, (int)strlen(data), &opts), sr_SR_NONE);
+    ASSERT_EQ(report.loaded, 2);
+    ASSERT_EQ(report.skipped, 2);
+    ASSERT_EQ(report.errors_len, 2);
+    ASSERT_EQ(report.errors[0].line, 3);
+    ASSERT_TRUE(strstr(report.errors[0].message, "price") != NULL);
+    ASSERT_EQ(report.errors[1].line, 5);
+    sr_free_load_report(report);
+    ASSERT_EQ(batches, 2);
+    
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results, "SELECT VALUE price + meta[0] FROM product:c", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *sum = sr_array_get(&results[0].ok, 0);
+    ASSERT_NOT_NULL(sum);
+    ASSERT_EQ(sum->sr_value_number.tag, SR_NUMBER_FLOAT);
+    ASSERT_TRUE(sum->sr_value_number.sr_number_float == 3.0);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Files without a header take their column names from the options */
+    const char *path = "/tmp/sr_load_csv_test.csv";
+    FILE *file = fopen(path, "w");
+    ASSERT_NOT_NULL(file);
+    fputs("x;1\ny;2\n", file);
+    fclose(file);
+    
+    const char *columns[] = {"id", "n"};
+    sr_load_options_t plain = {0};
+    plain.version = sr_SR_LOAD_OPTIONS_VERSION;
+    plain.delimiter = ';';
+    plain.no_header = true;
+    ASSERT_EQ(sr_load_csv(db, &err, NULL, "plain", path, &plain), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    plain.columns = columns;
+    plain.column_count = 2;
+    ASSERT_EQ(sr_load_csv(db, &err, &report, "plain", path, &plain), sr_SR_NONE);
+    ASSERT_EQ(report.loaded, 2);
+    sr_free_load_report(report);
+    remove(path);
+    
+    len = sr_query(db, &err, &results, "RETURN plain:y.n", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *n = sr_array_get(&results[0].ok, 0);
+    ASSERT_NOT_NULL(n);
+    ASSERT_EQ(n->sr_value_number.sr_number_int, 2);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Automatic columns read booleans in any case, like boolean columns */
+    const char *flags = "id,flag\nt,TRUE\n";
+    ASSERT_EQ(sr_load_csv_buf(db, &err, NULL, "flags", flags, (int)strlen(flags), NULL), sr_SR_NONE);
+    len = sr_query(db, &err, &results, "RETURN flags:t.flag", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *flag = sr_array_get(&results[0].ok, 0);
+    ASSERT_NOT_NULL(flag);
+    ASSERT_EQ(flag->tag, SR_VALUE_BOOL);
+    ASSERT_TRUE(flag->sr_value_bool);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+/* ============================================================================
+ * Backup Tests
+ * ============================================================================ */
+
+typedef struct {
+    size_t len;
+    int chunks;
+    int abort_after;
+} export_sink_t;
+
+static int collect_export_chunk(void *userdata, const uint8_t *data, int len) {
+    (void)data;
+    export_sink_t *sink = userdata;
+    sink->len += (size_t)len;
+    sink->chunks++;
+    return sink->abort_after > 0 && sink->chunks >= sink->abort_after;
+}
+
+int test_sr_export_import_buffer(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results,
+                       "DEFINE TABLE dumped SCHEMALESS; FOR $i IN 1..=10 { CREATE dumped SET n = $i }", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    uint8_t *dump;
+    int dump_len = sr_export_to_buffer(db, &err, &dump, NULL);
+    ASSERT_TRUE(dump_len > 0);
+    
+    /* The callback receives the whole dump in chunks */
+    export_sink_t sink = {0};
+    ASSERT_EQ(sr_export_to_callback(db, &err, collect_export_chunk, &sink, NULL), sr_SR_NONE);
+    ASSERT_TRUE(sink.chunks > 0);
+    ASSERT_TRUE(sink.len > 0);
+    
+    /* A non-zero return aborts the export */
+    export_sink_t aborting = {0};
+    aborting.abort_after = 1;
+    ASSERT_EQ(sr_export_to_callback(db, &err, collect_export_chunk, &aborting, NULL), sr_SR_ERROR);
+    ASSERT_EQ(aborting.chunks, 1);
+    sr_free_string(err);
+    
+    ASSERT_EQ(sr_export_to_callback(db, &err, NULL, NULL, NULL), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    /* Restore the dump into a fresh database */
+    sr_surreal_t *restored;
+    if (setup_db(&restored) != TEST_PASS) return TEST_FAIL;
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, dump, dump_len, NULL), sr_SR_NONE);
+    sr_free_byte_arr(dump, dump_len);
+    
+    len = sr_query(restored, &err, &results, "RETURN math::sum(SELECT VALUE n FROM dumped)", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *sum = sr_array_get(&results[0].ok, 0);
+    ASSERT_NOT_NULL(sum);
+    ASSERT_EQ(sum->sr_value_number.sr_number_int, 55);
+    sr_free_arr_res_arr(results, len);
+    
+    const char *broken = "CREATE dumped:1; THROW 'broken dump';";
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, (const uint8_t *)broken, (int)strlen(broken), NULL), sr_SR_ERROR);
+    ASSERT_TRUE(strstr(err, "broken dump") != NULL);
+    sr_free_string(err);
+    
+    sr_surreal_disconnect(restored);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+static int export_contains(sr_surreal_t *db, const sr_export_options_t *opts, const char *needle) {
+    sr_string_t err;
+    uint8_t *dump;
+    int len = sr_export_to_buffer(db, &err, &dump, opts);
+    if (len < 0) {
+        sr_free_string(err);
+        return -1;
+    }
+    char *text = malloc((size_t)len + 1);
+    memcpy(text, dump, (size_t)len);
+    text[len] = '\0';
+    sr_free_byte_arr(dump, len);
+    int found = strstr(text, needle) != NULL;
+    free(text);
+    return found;
+}
+
+int test_sr_export_options(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results,
+                       "DEFINE TABLE kept SCHEMAFULL; DEFINE FIELD n ON kept TYPE int;"
+                       "DEFINE FUNCTION fn::twice($n: int) { RETURN $n * 2 };"
+                       "CREATE kept:1 SET n = 1; CREATE other:1 SET n = 2;", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    sr_export_options_t opts = {0};
+    opts.version = sr_SR_EXPORT_OPTIONS_VERSION;
+    ASSERT_EQ(export_contains(db, &opts, "fn::twice"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "INSERT"), 1);
+    
+    opts.no_functions = true;
+    ASSERT_EQ(export_contains(db, &opts, "fn::twice"), 0);
+    ASSERT_EQ(export_contains(db, &opts, "DEFINE FIELD"), 1);
+    
+    /* Schema only */
+    opts.no_functions = false;
+    opts.no_records = true;
+    ASSERT_EQ(export_contains(db, &opts, "DEFINE TABLE kept"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "INSERT"), 0);
+    
+    /* Records only dumps rely on the section headers of the export, which must keep this format */
+    opts.no_records = false;
+    ASSERT_EQ(export_contains(db, &opts, "-- ------------------------------\n-- OPTION\n-- ------------------------------\n"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "-- ------------------------------\n-- TABLE: kept\n-- ------------------------------\n"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "-- ------------------------------\n-- TABLE DATA: kept\n-- ------------------------------\n"), 1);
+    
+    /* Records only */
+    opts.no_schema = true;
+    ASSERT_EQ(export_contains(db, &opts, "DEFINE"), 0);
+    ASSERT_EQ(export_contains(db, &opts, "OPTION IMPORT"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "TABLE DATA: kept"), 1);
+    
+    /* Selected tables */
+    const char *tables[] = {"kept"};
+    opts.tables = tables;
+    opts.tables_len = 1;
+    ASSERT_EQ(export_contains(db, &opts, "TABLE DATA: kept"), 1);
+    ASSERT_EQ(export_contains(db, &opts, "other"), 0);
+    
+    opts.no_records = true;
+    ASSERT_EQ(export_contains(db, &opts, "INSERT"), -1);
+    opts.no_records = false;
+    
+    /* Records only dumps restore into an existing schema */
+    uint8_t *dump;
+    int dump_len = sr_export_to_buffer(db, &err, &dump, &opts);
+    ASSERT_TRUE(dump_len > 0);
+    sr_surreal_t *restored;
+    if (setup_db(&restored) != TEST_PASS) return TEST_FAIL;
+    len = sr_query(restored, &err, &results, "DEFINE TABLE kept SCHEMAFULL; DEFINE FIELD n ON kept TYPE int;", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, dump, dump_len, NULL), sr_SR_NONE);
+    sr_free_byte_arr(dump, dump_len);
+    
+    len = sr_query(restored, &err, &results, "RETURN kept:1.n", NULL);
+    ASSERT_EQ(len, 1);
+    const sr_value_t *n = sr_array_get(&results[0].ok, 0);
+    ASSERT_NOT_NULL(n);
+    ASSERT_EQ(n->sr_value_number.sr_number_int, 1);
+    sr_free_arr_res_arr(results, len);
+    sr_surreal_disconnect(restored);
+    
+    /* Files are written by the calling process */
+    const char *path = "/tmp/sr_export_options_test.surql";
+    sr_export_options_t schema = {0};
+    schema.version = sr_SR_EXPORT_OPTIONS_VERSION;
+    schema.no_records = true;
+    ASSERT_EQ(sr_export_with_options(db, &err, path, &schema), sr_SR_NONE);
+    FILE *file = fopen(path, "r");
+    ASSERT_NOT_NULL(file);
+    char line[256];
+    int defines = 0;
+    while (fgets(line, sizeof(line), file) != NULL) {
+        ASSERT_TRUE(strncmp(line, "INSERT", 6) != 0);
+        defines += strncmp(line, "DEFINE", 6) == 0;
+    }
+    fclose(file);
+    remove(path);
+    ASSERT_TRUE(defines > 0);
+    
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
+
+typedef struct {
+    int calls;
+    int64_t bytes;
+    int64_t statements;
+    int abort_after;
+} progress_state_t;
+
+static int record_progress(void *userdata, int64_t bytes, int64_t statements) {
+    progress_state_t *state = userdata;
+    state->calls++;
+    state->bytes = bytes;
+    state->statements = statements;
+    return state->abort_after > 0 && state->calls >= state->abort_after;
+}
+
+int test_sr_import_export_progress(void) {
+    sr_surreal_t *db;
+    if (setup_db(&db) != TEST_PASS) return TEST_FAIL;
+    
+    sr_string_t err;
+    sr_arr_res_t *results;
+    int len = sr_query(db, &err, &results,
+                       "DEFINE TABLE big SCHEMAFULL; DEFINE FIELD n ON big TYPE int; DEFINE FIELD pad ON big TYPE string;"
+                       "DEFINE EVENT counted ON big WHEN $event = 'CREATE' THEN { UPSERT stats:big SET n += 1 };"
+                       "FOR $i IN 1..=200 { CREATE big SET n = $i, pad = string::repeat('x', 100) };"
+                       "CREATE zlast:1;", NULL);
+    ASSERT_GE(len, 0);
+    sr_free_arr_res_arr(results, len);
+    
+    /* Export reports its progress and the final totals */
+    progress_state_t exported = {0};
+    sr_export_options_t opts = {0};
+    opts.version = sr_SR_EXPORT_OPTIONS_VERSION;
+    opts.progress = record_progress;
+    opts.userdata = &exported;
+    uint8_t *dump;
+    int dump_len = sr_export_to_buffer(db, &err, &dump, &opts);
+    ASSERT_TRUE(dump_len > 0);
+    ASSERT_TRUE(exported.calls >= 1);
+    ASSERT_EQ(exported.bytes, dump_len);
+    ASSERT_TRUE(exported.statements > 3);
+    
+    progress_state_t aborted = {0};
+    aborted.abort_after = 1;
+    opts.userdata = &aborted;
+    uint8_t *partial;
+    ASSERT_EQ(sr_export_to_buffer(db, &err, &partial, &opts), sr_SR_CANCELLED);
+    sr_free_string(err);
+    
+    /* Import runs in several chunks, with the OPTION IMPORT of the dump applied to all of them */
+    progress_state_t imported = {0};
+    sr_import_options_t import_opts = {0};
+    import_opts.version = sr_SR_IMPORT_OPTIONS_VERSION;
+    import_opts.chunk_size = 4096;
+    import_opts.progress = record_progress;
+    import_opts.userdata = &imported;
+    sr_surreal_t *restored;
+    if (setup_db(&restored) != TEST_PASS) return TEST_FAIL;
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, dump, dump_len, &import_opts), sr_SR_NONE);
+    ASSERT_TRUE(imported.calls >= 2);
+    ASSERT_EQ(imported.bytes, dump_len);
+    ASSERT_TRUE(imported.statements > 3);
+    
+    len = sr_query(restored, &err, &results, "RETURN [count(SELECT * FROM big), stats:big.n]", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 2);
+    ASSERT_EQ(sr_array_get(&results[0].ok, 0)->sr_value_number.sr_number_int, 200);
+    ASSERT_EQ(sr_array_get(&results[0].ok, 1)->sr_value_number.sr_number_int, 200);
+    sr_free_arr_res_arr(results, len);
+    sr_surreal_disconnect(restored);
+    
+    /* Aborting stops between chunks, the chunk with the records of big is executed before the one of zlast */
+    progress_state_t cancelled = {0};
+    cancelled.abort_after = 1;
+    import_opts.userdata = &cancelled;
+    if (setup_db(&restored) != TEST_PASS) return TEST_FAIL;
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, dump, dump_len, &import_opts), sr_SR_CANCELLED);
+    sr_free_string(err);
+    ASSERT_EQ(cancelled.calls, 1);
+    len = sr_query(restored, &err, &results, "RETURN count(SELECT * FROM big); SELECT * FROM zlast", NULL);
+    ASSERT_EQ(len, 2);
+    ASSERT_EQ(sr_array_get(&results[0].ok, 0)->sr_value_number.sr_number_int, 200);
+    ASSERT_EQ(results[1].err.kind, SR_ERROR_KIND_NOT_FOUND);
+    sr_free_arr_res_arr(results, len);
+    sr_surreal_disconnect(restored);
+    sr_free_byte_arr(dump, dump_len);
+    
+    /* Files are read by the calling process, transactions are never split */
+    const char *path = "/tmp/sr_import_progress_test.surql";
+    FILE *file = fopen(path, "w");
+    ASSERT_NOT_NULL(file);
+    fputs("-- a transaction\nBEGIN TRANSACTION;\nCREATE txn:1;\nDEFINE FUNCTION fn::f() {\n  RETURN 1;\n};\n", file);
+    fputs("COMMIT TRANSACTION;\nCREATE txn:2\n", file);
+    fclose(file);
+    progress_state_t from_file = {0};
+    import_opts.userdata = &from_file;
+    if (setup_db(&restored) != TEST_PASS) return TEST_FAIL;
+    ASSERT_EQ(sr_import_with_options(restored, &err, path, &import_opts), sr_SR_NONE);
+    remove(path);
+    ASSERT_EQ(from_file.calls, 1);
+    ASSERT_EQ(from_file.statements, 5);
+    len = sr_query(restored, &err, &results, "RETURN [fn::f(), count(SELECT * FROM txn)]", NULL);
+    ASSERT_EQ(len, 1);
+    ASSERT_EQ(sr_array_len(&results[0].ok), 2);
+    ASSERT_EQ(sr_array_get(&results[0].ok, 1)->sr_value_number.sr_number_int, 2);
+    sr_free_arr_res_arr(results, len);
+    
+    ASSERT_EQ(sr_import_with_options(restored, &err, "/nonexistent/dump.surql", NULL), sr_SR_ERROR);
+    sr_free_string(err);
+    
+    /* A statement which still does not parse once it is larger than a chunk fails the import early */
+    const char *long_statement = "CREATE txn:3 SET note = 'a;\nb;\nc;\nd;\ne;\nf';\n";
+    import_opts.chunk_size = 16;
+    import_opts.progress = NULL;
+    ASSERT_EQ(sr_import_from_buffer(restored, &err, (const uint8_t *)long_statement, strlen(long_statement), &import_opts),
+              sr_SR_ERROR);
+    ASSERT_EQ(sr_last_error_kind(), SR_ERROR_KIND_PARSE);
+    sr_free_string(err);
+    
+    sr_surreal_disconnect(restored);
+    sr_surreal_disconnect(db);
+    return TEST_PASS;
+}
@@ -36,6 +36,8 @@ int test_sr_use_ns(void);
 int test_sr_use_db(void);
 int test_sr_version(void);
 int test_sr_health(void);
+int test_sr_connect_with_options(void);
+int test_sr_surreal_reconnect(void);
 
 /* ============================================================================
  * Authentication Tests
@@ -66,6 +68,14 @@ int test_sr_merge(void);
 int test_sr_query(void);
 int test_sr_run(void);
 int test_sr_relate(void);
+int test_sr_query_response(void);
+int test_sr_prepare(void);
+int test_sr_parse_check(void);
+int test_sr_cursor(void);
+int test_sr_transaction(void);
+int test_sr_transaction_run(void);
+int test_sr_batch(void);
+int test_sr_insert_many(void);
 
 /* ============================================================================
  * Patch Tests
@@ -95,6 +105,8 @@ int test_sr_unset(void);
  * ============================================================================ */
 
 int test_sr_select_live(void);
+int test_sr_live_query(void);
+int test_sr_select_live_diff(void);
 
 /* ============================================================================
  * Import/Export Tests
@@ -162,6 +174,10 @@ int test_sr_stream_next(void);
 int test_sr_stream_kill(void);
 int test_sr_rpc_stream_next(void);
 int test_sr_rpc_stream_free(void);
+int test_sr_stream_try_next(void);
+int test_sr_rpc_stream_try_next(void);
+int test_sr_stream_subscribe(void);
+int test_sr_stream_set(void);
 
 /* ============================================================================
  * Utility Tests
@@ -201,6 +217,45 @@ int test_sr_object_keys(void);
 
 int test_sr_kill(void);
 
+/* ============================================================================
+ * Error Tests
+ * ============================================================================ */
+
+int test_sr_error_kind(void);
+int test_sr_last_error(void);
+
+/* ============================================================================
+ * Async Tests
+ * ============================================================================ */
+
+int test_sr_async_callbacks(void);
+int test_sr_future(void);
+int test_sr_cancel_token(void);
+int test_sr_timeout(void);
+int test_sr_cancel_token_bind(void);
+
+/* ============================================================================
+ * Runtime Tests
+ * ============================================================================ */
+
+int test_sr_runtime_shared(void);
+int test_sr_runtime_current_thread(void);
+
+/* ============================================================================
+ * Load Tests
+ * ============================================================================ */
+
+int test_sr_load_ndjson(void);
+int test_sr_load_csv(void);
+
+/* ============================================================================
+ * Backup Tests
+ * ============================================================================ */
+
+int test_sr_export_import_buffer(void);
+int test_sr_export_options(void);
+int test_sr_import_export_progress(void);
+
 #ifdef __cplusplus
 }
 #endif
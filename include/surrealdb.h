@@ -13,6 +13,185 @@
 
 #define sr_SR_FATAL -3
 
+#define sr_SR_PENDING -4
+
+#define sr_SR_CANCELLED -5
+
+#define sr_SR_TIMEOUT -6
+
+/**
+ * `sr_insert_many` flag: skip rows whose record already exists instead of failing
+ */
+#define sr_SR_INSERT_IGNORE 1
+
+/**
+ * `sr_insert_many` flag: update records which already exist with the fields of the row
+ */
+#define sr_SR_INSERT_UPDATE 2
+
+/**
+ * `sr_insert_many` flag: do not return the inserted records
+ */
+#define sr_SR_INSERT_DISCARD 4
+
+/**
+ * The current version of `sr_export_options_t`
+ *
+ * Callers must set `version` to this value so that fields added in later releases can be detected.
+ */
+#define sr_SR_EXPORT_OPTIONS_VERSION 1
+
+/**
+ * The current version of `sr_import_options_t`
+ *
+ * Callers must set `version` to this value so that fields added in later releases can be detected.
+ */
+#define sr_SR_IMPORT_OPTIONS_VERSION 1
+
+/**
+ * The current version of `sr_load_options_t`
+ *
+ * Callers must set `version` to this value so that fields added in later releases can be detected.
+ */
+#define sr_SR_LOAD_OPTIONS_VERSION 1
+
+/**
+ * Error policy: stop at the first row which can not be converted or inserted
+ */
+#define sr_SR_LOAD_STOP 0
+
+/**
+ * Error policy: skip rows which can not be converted or inserted
+ */
+#define sr_SR_LOAD_SKIP 1
+
+/**
+ * Error policy: skip rows which can not be converted or inserted and report their errors
+ */
+#define sr_SR_LOAD_COLLECT 2
+
+/**
+ * CSV column type: an integer, float or boolean if the field is one, a string otherwise
+ */
+#define sr_SR_COLUMN_AUTO 0
+
+/**
+ * CSV column type: the field as is
+ */
+#define sr_SR_COLUMN_STRING 1
+
+/**
+ * CSV column type: a 64-bit integer
+ */
+#define sr_SR_COLUMN_INT 2
+
+/**
+ * CSV column type: a 64-bit float
+ */
+#define sr_SR_COLUMN_FLOAT 3
+
+/**
+ * CSV column type: `true`, `false`, `1` or `0`, in any case
+ */
+#define sr_SR_COLUMN_BOOL 4
+
+/**
+ * CSV column type: an ISO 8601 datetime
+ */
+#define sr_SR_COLUMN_DATETIME 5
+
+/**
+ * CSV column type: a JSON value
+ */
+#define sr_SR_COLUMN_JSON 6
+
+/**
+ * The current version of `sr_connect_options_t`
+ *
+ * Callers must set `version` to this value so that fields added in later releases can be detected.
+ */
+#define sr_SR_CONNECT_OPTIONS_VERSION 2
+
+/**
+ * The current version of `sr_runtime_options_t`
+ *
+ * Callers must set `version` to this value so that fields added in later releases can be detected.
+ */
+#define sr_SR_RUNTIME_OPTIONS_VERSION 1
+
+/**
+ * The category of an error
+ *
+ * Stable across releases, so it can be used to drive retries and error handling
+ * without comparing error messages.
+ */
+typedef enum sr_error_kind_t {
+  /**
+   * No error
+   */
+  SR_ERROR_KIND_NONE,
+  /**
+   * Unexpected error inside SurrealDB or the bindings
+   */
+  SR_ERROR_KIND_INTERNAL,
+  /**
+   * An argument was null, not valid UTF-8 or otherwise invalid
+   */
+  SR_ERROR_KIND_INVALID_ARGUMENT,
+  /**
+   * A query could not be parsed
+   */
+  SR_ERROR_KIND_PARSE,
+  /**
+   * A table, record, namespace or database does not exist
+   */
+  SR_ERROR_KIND_NOT_FOUND,
+  /**
+   * A table, record, namespace or database already exists
+   */
+  SR_ERROR_KIND_ALREADY_EXISTS,
+  /**
+   * A transaction conflicted with another transaction and can be retried
+   */
+  SR_ERROR_KIND_CONFLICT,
+  /**
+   * Authentication failed or the session is not permitted to perform the action
+   */
+  SR_ERROR_KIND_UNAUTHORIZED,
+  /**
+   * The action is disabled by the capabilities of the database
+   */
+  SR_ERROR_KIND_NOT_ALLOWED,
+  /**
+   * A query or transaction timed out
+   */
+  SR_ERROR_KIND_TIMEOUT,
+  /**
+   * A query was cancelled
+   */
+  SR_ERROR_KIND_CANCELLED,
+  /**
+   * A statement was not executed because another statement in its transaction failed
+   */
+  SR_ERROR_KIND_NOT_EXECUTED,
+  /**
+   * The connection to the database failed
+   */
+  SR_ERROR_KIND_CONNECTION,
+  /**
+   * A value could not be serialized or deserialized
+   */
+  SR_ERROR_KIND_SERIALIZATION,
+  /**
+   * The feature is not supported by the connection or its configuration
+   */
+  SR_ERROR_KIND_CONFIGURATION,
+  /**
+   * An error raised with THROW
+   */
+  SR_ERROR_KIND_THROWN,
+} sr_error_kind_t;
+
 typedef enum sr_credentials_scope {
   ROOT,
   NAMESPACE,
@@ -25,22 +204,137 @@ typedef enum sr_action {
   SR_ACTION_UPDATE,
   SR_ACTION_DELETE,
   SR_ACTION_KILLED,
+} sr_action;
+
+/**
+ * The kind of a patch operation
+ */
+typedef enum sr_diff_op_kind {
   /**
-   * Represents an action type added in a newer version of SurrealDB
-   * that this C API version doesn't yet support
+   * Insert `value` at `path`, into the array or object holding it
    */
-  SR_ACTION_UNIMPLEMENTED,
-} sr_action;
+  SR_DIFF_ADD,
+  /**
+   * Remove the value at `path`
+   */
+  SR_DIFF_REMOVE,
+  /**
+   * Replace the value at `path` with `value`
+   */
+  SR_DIFF_REPLACE,
+  /**
+   * Apply the text patch in `value`, in diff-match-patch format, to the string at `path`
+   */
+  SR_DIFF_CHANGE,
+  /**
+   * Copy the value at `from` to `path`
+   */
+  SR_DIFF_COPY,
+  /**
+   * Move the value at `from` to `path`
+   */
+  SR_DIFF_MOVE,
+  /**
+   * Fail unless the value at `path` equals `value`
+   */
+  SR_DIFF_TEST,
+} sr_diff_op_kind;
 
 typedef struct sr_opaque_object_internal_t sr_opaque_object_internal_t;
 
 /**
- * Stream for receiving RPC notifications
+ * A list of operations to execute in one query
+ *
+ * Operations are executed in the order they were added. A batch can be executed any number of
+ * times and must be freed with `sr_batch_free`.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_batch_t *batch = sr_batch_new();
+ * sr_batch_create(batch, &err, "person:tobie", &tobie);
+ * sr_batch_create(batch, &err, "person:jaime", &jaime);
+ * sr_batch_relate(batch, &err, "person:tobie", "knows", "person:jaime", NULL);
+ *
+ * sr_arr_res_t *res;
+ * int len = sr_batch_execute(db, &err, &res, batch, true);
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * for (int i = 0; i < len; i++) {
+ *     if (res[i].err.code != 0) {
+ *         printf("operation %d failed: %s\n", i, res[i].err.msg);
+ *     }
+ * }
+ * sr_free_arr_res_arr(res, len);
+ * sr_batch_free(batch);
+ * ```
+ */
+typedef struct sr_batch_t sr_batch_t;
+
+/**
+ * Token used to cancel blocking calls from another thread
+ *
+ * May be shared across threads and passed to several calls at once.
+ * Once cancelled, every call using the token fails until it is reset with `sr_cancel_token_reset`.
+ * Free with `sr_cancel_token_free` once no call is using it.
+ */
+typedef struct sr_cancel_token_t sr_cancel_token_t;
+
+/**
+ * A cursor over the rows returned by a query
+ *
+ * Must not be used concurrently, and must be closed with `sr_cursor_close` before its connection
+ * is disconnected.
+ */
+typedef struct sr_cursor_t sr_cursor_t;
+
+/**
+ * The response to a query, holding the result of each statement as it was returned
+ *
+ * Unlike `sr_query`, values are not wrapped into arrays, so `RETURN 1` and `RETURN [1]` can be told apart.
+ * Pointers returned by the accessors are owned by the response and valid until `sr_response_free`.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_response_t *resp;
+ * int count = sr_query_response(db, &err, &resp, "RETURN 1; SELECT * FROM ONLY foo:1; THROW 'oops'", NULL);
+ * if (count < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * for (int i = 0; i < count; i++) {
+ *     const sr_error_t *e = sr_response_error(resp, i);
+ *     if (e) {
+ *         printf("statement %d failed: %s\n", i, e->msg);
+ *         continue;
+ *     }
+ *     sr_value_print(sr_response_value(resp, i));
+ * }
+ * sr_response_free(resp);
+ * ```
+ */
+typedef struct sr_response_t sr_response_t;
+
+/**
+ * Stream for receiving RPC live query notifications
  *
+ * Wraps a `Receiver<PublicNotification>` from the datastore's notification channel.
  * Uses synchronous blocking receives, so no async drop is required.
  */
 typedef struct sr_RpcStream sr_RpcStream;
 
+/**
+ * A validated query with its bound parameters
+ *
+ * Bound parameters are kept between executions until they are rebound or cleared.
+ * Must not be used concurrently, and must be freed with `sr_statement_free` before its
+ * connection is disconnected.
+ */
+typedef struct sr_statement_t sr_statement_t;
+
 /**
  * Stream for receiving live query notifications
  *
@@ -49,28 +343,68 @@ typedef struct sr_RpcStream sr_RpcStream;
  */
 typedef struct sr_stream_t sr_stream_t;
 
+/**
+ * A set of streams whose notifications are received together
+ *
+ * Each stream added to the set is read on its connection's runtime, and its notifications are
+ * queued until taken with `sr_stream_set_next`, so a single thread can wait on all of them.
+ * May be sent across threads, but must not be aliased. Free with `sr_stream_set_free`.
+ */
+typedef struct sr_stream_set_t sr_stream_set_t;
+
+/**
+ * A stream whose notifications are dispatched to a callback
+ *
+ * Created with `sr_stream_subscribe`, must be freed with `sr_stream_unsubscribe`.
+ */
+typedef struct sr_subscription_t sr_subscription_t;
+
 /**
  * The object representing a Surreal connection
  *
  * It is safe to be referenced from multiple threads
- * If any operation, on any thread returns SR_FATAL then the connection is poisoned and must not be used again.
- * (use will cause the program to abort)
+ * If any operation, on any thread returns SR_FATAL then the connection is poisoned,
+ * and every further call fails with SR_FATAL until it is rebuilt with sr_surreal_reconnect.
  *
  * should be freed with sr_surreal_disconnect
  */
 typedef struct sr_surreal_t sr_surreal_t;
 
 /**
- * The object representing a Surreal connection
+ * Handle to an operation running on a connection's runtime
+ *
+ * May be sent across threads, but must not be aliased. Unlike the `_async` callbacks, the result is
+ * taken on the caller's thread, so blocking calls may follow it.
+ * Free with `sr_future_free` once it is no longer needed, whether or not it has completed.
+ */
+typedef struct sr_future_t sr_future_t;
+
+/**
+ * The object representing a Surreal RPC connection
  *
  * It is safe to be referenced from multiple threads
- * If any operation, on any thread returns SR_FATAL then the connection is poisoned and must not be used again.
- * (use will cause the program to abort)
+ * If any operation, on any thread returns SR_FATAL then the connection is poisoned,
+ * and every further call fails with SR_FATAL, so it must be freed and created again.
  *
- * should be freed with sr_surreal_disconnect
+ * should be freed with sr_surreal_rpc_free
  */
 typedef struct sr_surreal_rpc_t sr_surreal_rpc_t;
 
+/**
+ * A runtime which can be shared by several connections
+ *
+ * Connections keep the runtime alive, so it may be freed with `sr_runtime_free` while they are still open.
+ */
+typedef struct sr_runtime_t sr_runtime_t;
+
+/**
+ * A transaction in progress on a connection
+ *
+ * Must not be used concurrently, and must be finished with `sr_transaction_commit` or
+ * `sr_transaction_rollback` before its connection is disconnected.
+ */
+typedef struct sr_transaction_t sr_transaction_t;
+
 /**
  * A null-terminated C string type
  *
@@ -88,56 +422,198 @@ typedef struct sr_object_t {
   struct sr_opaque_object_internal_t *_0;
 } sr_object_t;
 
-typedef enum sr_number_t_Tag {
-  SR_NUMBER_INT,
-  SR_NUMBER_FLOAT,
+/**
+ * A structured error
+ *
+ * when code = 0 there is no error
+ *
+ * `details` is null unless SurrealDB provided structured details for the error.
+ * Must be freed with `sr_free_error`
+ *
+ * Only the calls with an `_ex` variant write a structured error: `sr_connect_ex`, `sr_use_ns_ex`,
+ * `sr_use_db_ex`, `sr_signin_ex`, `sr_signup_ex`, `sr_authenticate_ex`, `sr_query_ex`, `sr_select_ex`,
+ * `sr_create_ex`, `sr_insert_ex`, `sr_update_ex`, `sr_upsert_ex`, `sr_merge_ex`, `sr_delete_ex`,
+ * `sr_relate_ex` and `sr_run_ex`. Every other call only writes the message of its error, this includes
+ * `sr_begin`, `sr_commit`, `sr_cancel`, `sr_export`, `sr_import`, `sr_health`, `sr_version`,
+ * `sr_insert_relation`, `sr_invalidate`, `sr_kill`, `sr_select_live`, `sr_set`, `sr_unset`, the
+ * `sr_patch_*` functions and the `sr_batch_*`, `sr_transaction_*`, `sr_load_*`, `sr_export_*` and
+ * `sr_import_*` functions. Their code and kind are read with `sr_last_error_code` and
+ * `sr_last_error_kind` right after the failing call, on the same thread; their details are not available.
+ */
+typedef struct sr_error_t {
   /**
-   * Decimal stored as string representation for C compatibility
+   * The code returned by the failed call, SR_ERROR, SR_CANCELLED, SR_TIMEOUT or SR_FATAL
    */
-  SR_NUMBER_DECIMAL,
-} sr_number_t_Tag;
-
-typedef struct sr_number_t {
-  sr_number_t_Tag tag;
-  union {
-    struct {
-      int64_t sr_number_int;
-    };
-    struct {
-      double sr_number_float;
-    };
-    struct {
-      sr_string_t sr_number_decimal;
-    };
-  };
-} sr_number_t;
+  int code;
+  /**
+   * Human readable error message
+   */
+  sr_string_t msg;
+  /**
+   * The category of the error
+   */
+  enum sr_error_kind_t kind;
+  /**
+   * Structured details of the error, may be null
+   */
+  struct sr_object_t *details;
+} sr_error_t;
 
 typedef struct sr_duration_t {
   uint64_t secs;
   uint32_t nanos;
 } sr_duration_t;
 
-typedef struct sr_uuid_t {
-  uint8_t _0[16];
-} sr_uuid_t;
-
-typedef struct sr_array_t {
-  struct sr_value_t *arr;
-  int len;
-} sr_array_t;
+typedef struct sr_credentials {
+  sr_string_t username;
+  sr_string_t password;
+} sr_credentials;
 
-typedef struct sr_sr_g_coord {
-  double x;
-  double y;
-} sr_sr_g_coord;
+typedef struct sr_credentials_access {
+  sr_string_t namespace_;
+  sr_string_t database;
+  sr_string_t access;
+} sr_credentials_access;
 
-typedef struct sr_sr_g_point {
-  struct sr_sr_g_coord _0;
-} sr_sr_g_point;
+/**
+ * Capabilities of an embedded database
+ *
+ * Function and net target lists are comma-separated, `"*"` matches everything.
+ * A null list keeps the default: all functions allowed, no net targets allowed, nothing denied.
+ */
+typedef struct sr_capabilities_t {
+  /**
+   * Enable the embedded javascript scripting runtime
+   */
+  bool scripting;
+  /**
+   * Allow unauthenticated users to execute queries
+   */
+  bool guest_access;
+  /**
+   * Enable live query notifications
+   */
+  bool live_query_notifications;
+  /**
+   * Functions which may be executed
+   */
+  const char *allow_functions;
+  /**
+   * Functions which may not be executed
+   */
+  const char *deny_functions;
+  /**
+   * Network targets which may be accessed
+   */
+  const char *allow_net_targets;
+  /**
+   * Network targets which may not be accessed
+   */
+  const char *deny_net_targets;
+} sr_capabilities_t;
 
-typedef struct sr_ArrayGen_sr_g_coord {
-  struct sr_sr_g_coord *ptr;
-  int len;
+/**
+ * Extended connection options for `sr_connect_with_options`
+ *
+ * Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+ * behaves like `sr_connect`.
+ */
+typedef struct sr_connect_options_t {
+  /**
+   * Must be set to SR_CONNECT_OPTIONS_VERSION
+   */
+  uint32_t version;
+  /**
+   * Define the selected database as STRICT if it does not exist yet (requires `namespace` and `database`)
+   */
+  bool strict;
+  /**
+   * Query timeout, zero for no timeout (embedded engines only)
+   */
+  struct sr_duration_t query_timeout;
+  /**
+   * Transaction timeout, zero for no timeout (embedded engines only)
+   */
+  struct sr_duration_t transaction_timeout;
+  /**
+   * Namespace to select after connecting, may be null
+   */
+  const char *namespace_;
+  /**
+   * Database to select after connecting, may be null
+   */
+  const char *database;
+  /**
+   * Scope to sign in with after connecting, null to skip signing in
+   */
+  const enum sr_credentials_scope *auth_scope;
+  /**
+   * Credentials used to sign in, may be null
+   */
+  const struct sr_credentials *credentials;
+  /**
+   * Signin target, if null `namespace` and `database` are used
+   */
+  const struct sr_credentials_access *access;
+  /**
+   * Record access params used to sign in, may be null
+   */
+  const struct sr_object_t *auth_params;
+  /**
+   * Capabilities of embedded engines, null for the defaults
+   */
+  const struct sr_capabilities_t *capabilities;
+  /**
+   * Runtime to run the connection on, null to create one for this connection (since version 2)
+   */
+  const struct sr_runtime_t *runtime;
+} sr_connect_options_t;
+
+typedef enum sr_number_t_Tag {
+  SR_NUMBER_INT,
+  SR_NUMBER_FLOAT,
+  /**
+   * Decimal stored as string representation for C compatibility
+   */
+  SR_NUMBER_DECIMAL,
+} sr_number_t_Tag;
+
+typedef struct sr_number_t {
+  sr_number_t_Tag tag;
+  union {
+    struct {
+      int64_t sr_number_int;
+    };
+    struct {
+      double sr_number_float;
+    };
+    struct {
+      sr_string_t sr_number_decimal;
+    };
+  };
+} sr_number_t;
+
+typedef struct sr_uuid_t {
+  uint8_t _0[16];
+} sr_uuid_t;
+
+typedef struct sr_array_t {
+  struct sr_value_t *arr;
+  int len;
+} sr_array_t;
+
+typedef struct sr_sr_g_coord {
+  double x;
+  double y;
+} sr_sr_g_coord;
+
+typedef struct sr_sr_g_point {
+  struct sr_sr_g_coord _0;
+} sr_sr_g_point;
+
+typedef struct sr_ArrayGen_sr_g_coord {
+  struct sr_sr_g_coord *ptr;
+  int len;
 } sr_ArrayGen_sr_g_coord;
 
 typedef struct sr_sr_g_linestring {
@@ -358,1100 +834,3238 @@ typedef struct sr_value_t {
   };
 } sr_value_t;
 
+typedef struct sr_arr_res_t {
+  struct sr_array_t ok;
+  struct sr_error_t err;
+} sr_arr_res_t;
+
 /**
- * when code = 0 there is no error
+ * Callback receiving the progress of an import or export
+ *
+ * `bytes` is the number of bytes of the dump processed so far, `statements` the number of
+ * statements executed or exported. Return 0 to continue, anything else aborts the operation, which
+ * then fails with SR_CANCELLED.
+ */
+typedef int (*sr_progress_callback_t)(void *userdata, int64_t bytes, int64_t statements);
+
+/**
+ * Options narrowing what an export contains
+ *
+ * Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+ * exports everything, like passing no options at all.
+ */
+typedef struct sr_export_options_t {
+  /**
+   * Must be set to SR_EXPORT_OPTIONS_VERSION
+   */
+  uint32_t version;
+  /**
+   * Names of the tables to export, null to export every table
+   */
+  const char *const *tables;
+  /**
+   * Number of entries in `tables`
+   */
+  int tables_len;
+  /**
+   * Leave out the database users
+   */
+  bool no_users;
+  /**
+   * Leave out the access methods
+   */
+  bool no_accesses;
+  /**
+   * Leave out the database parameters
+   */
+  bool no_params;
+  /**
+   * Leave out the functions
+   */
+  bool no_functions;
+  /**
+   * Leave out the analyzers
+   */
+  bool no_analyzers;
+  /**
+   * Leave out every definition, exporting only the records of the tables
+   *
+   * Fails with SR_ERROR_KIND_CONFIGURATION if the export of the server is not in the expected format.
+   */
+  bool no_schema;
+  /**
+   * Leave out the records, exporting only the definitions
+   */
+  bool no_records;
+  /**
+   * Export every version of the records, only supported by versioned storage engines
+   */
+  bool versions;
+  /**
+   * Called as the export is written, may be null
+   */
+  sr_progress_callback_t progress;
+  /**
+   * Passed to the progress callback
+   */
+  void *userdata;
+} sr_export_options_t;
+
+/**
+ * Callback receiving a chunk of an export
+ *
+ * `data` is only valid during the call. Return 0 to continue, anything else aborts the export.
+ */
+typedef int (*sr_write_callback_t)(void *userdata, const uint8_t *data, int len);
+
+/**
+ * Options of an import
+ *
+ * Zero or null fields are treated as unset.
+ */
+typedef struct sr_import_options_t {
+  /**
+   * Must be set to SR_IMPORT_OPTIONS_VERSION
+   */
+  uint32_t version;
+  /**
+   * Number of bytes of statements executed per query, zero for 1 MiB
+   *
+   * A statement which does not parse once it is larger than this fails the import with a syntax error.
+   */
+  int chunk_size;
+  /**
+   * Called after every chunk, may be null
+   */
+  sr_progress_callback_t progress;
+  /**
+   * Passed to the progress callback
+   */
+  void *userdata;
+} sr_import_options_t;
+
+/**
+ * Callback receiving the values returned by a CRUD operation
+ *
+ * On success `len` is the number of values written to `values`, which must be freed with `sr_free_arr`.
+ * On failure `len` is negative, `values` is null and `err` points to the error, which is only valid
+ * for the duration of the callback.
+ */
+typedef void (*sr_values_callback_t)(void *userdata,
+                                     int len,
+                                     struct sr_value_t *values,
+                                     const struct sr_error_t *err);
+
+/**
+ * Callback receiving the record returned by `sr_create_async`
+ *
+ * On success `code` is 1 and `obj` must be freed with `sr_free_object`.
+ * On failure `code` is negative, `obj` is null and `err` points to the error, which is only valid
+ * for the duration of the callback.
+ */
+typedef void (*sr_object_callback_t)(void *userdata,
+                                     int code,
+                                     struct sr_object_t *obj,
+                                     const struct sr_error_t *err);
+
+/**
+ * Callback receiving the statement results of `sr_query_async`
+ *
+ * On success `len` is the number of results written to `results`, which must be freed with
+ * `sr_free_arr_res_arr`. On failure `len` is negative, `results` is null and `err` points to the
+ * error, which is only valid for the duration of the callback.
+ */
+typedef void (*sr_query_callback_t)(void *userdata,
+                                    int len,
+                                    struct sr_arr_res_t *results,
+                                    const struct sr_error_t *err);
+
+/**
+ * A row which could not be loaded
+ */
+typedef struct sr_load_error_t {
+  /**
+   * Line of the row in the data, starting at 1
+   */
+  int64_t line;
+  /**
+   * The category of the error
+   */
+  enum sr_error_kind_t kind;
+  /**
+   * Description of the error
+   */
+  sr_string_t message;
+} sr_load_error_t;
+
+/**
+ * The outcome of a load
+ *
+ * Must be freed with `sr_free_load_report`
+ */
+typedef struct sr_load_report_t {
+  /**
+   * Number of rows inserted
+   */
+  int64_t loaded;
+  /**
+   * Number of rows skipped because of an error
+   */
+  int64_t skipped;
+  /**
+   * Errors of the skipped rows with SR_LOAD_COLLECT, null otherwise
+   */
+  struct sr_load_error_t *errors;
+  /**
+   * Number of entries in `errors`
+   */
+  int errors_len;
+} sr_load_report_t;
+
+/**
+ * Callback invoked after every batch with the number of rows loaded and bytes read so far
+ */
+typedef void (*sr_load_progress_callback_t)(void *userdata, int64_t rows, int64_t bytes);
+
+/**
+ * Options for the `sr_load_*` functions
+ *
+ * Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+ * loads with the defaults: batches of 1000 rows, stopping at the first error, and for CSV a comma
+ * delimited file with a header row and every column of type SR_COLUMN_AUTO.
+ */
+typedef struct sr_load_options_t {
+  /**
+   * Must be set to SR_LOAD_OPTIONS_VERSION
+   */
+  uint32_t version;
+  /**
+   * Number of rows inserted per query, zero for 1000
+   */
+  int batch_size;
+  /**
+   * SR_LOAD_STOP, SR_LOAD_SKIP or SR_LOAD_COLLECT
+   */
+  int on_error;
+  /**
+   * Called after every batch, may be null
+   */
+  sr_load_progress_callback_t progress;
+  /**
+   * Passed to the progress callback
+   */
+  void *userdata;
+  /**
+   * CSV field delimiter, zero for a comma
+   */
+  char delimiter;
+  /**
+   * The CSV data has no header row, `columns` must then name the columns
+   */
+  bool no_header;
+  /**
+   * Names of the CSV columns, overriding the header row, may be null
+   */
+  const char *const *columns;
+  /**
+   * Types of the CSV columns as SR_COLUMN_* constants, may be null
+   */
+  const int *column_types;
+  /**
+   * Number of entries in `columns` and `column_types`
+   */
+  int column_count;
+} sr_load_options_t;
+
+/**
+ * A syntax error found in a query
+ *
+ * Lines and columns start at 1, columns are counted in characters.
+ * Offsets and lengths are in bytes, so the span can be sliced out of the query directly.
+ * A diagnostic without a location has a line and column of 0.
+ */
+typedef struct sr_diagnostic_t {
+  /**
+   * Description of the error
+   */
+  sr_string_t message;
+  /**
+   * Line of the start of the span
+   */
+  int line;
+  /**
+   * Column of the start of the span
+   */
+  int column;
+  /**
+   * Byte offset of the span into the query
+   */
+  int offset;
+  /**
+   * Length of the span in bytes
+   */
+  int length;
+} sr_diagnostic_t;
+
+/**
+ * Connection options for SurrealDB
+ *
+ * Configures various settings for the database connection.
+ */
+typedef struct sr_option_t {
+  /**
+   * Enable strict mode for queries
+   */
+  bool strict;
+  /**
+   * Query timeout in seconds
+   */
+  uint8_t query_timeout;
+  /**
+   * Transaction timeout in seconds
+   */
+  uint8_t transaction_timeout;
+} sr_option_t;
+
+/**
+ * Hook invoked on a runtime thread, with the `userdata` of its `sr_runtime_options_t`
+ */
+typedef void (*sr_thread_callback_t)(void *userdata);
+
+/**
+ * Options for `sr_runtime_new`
+ *
+ * Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+ * creates the same runtime as a connection would.
+ */
+typedef struct sr_runtime_options_t {
+  /**
+   * Must be set to SR_RUNTIME_OPTIONS_VERSION
+   */
+  uint32_t version;
+  /**
+   * Number of worker threads, zero for one per CPU core (ignored in current-thread mode)
+   */
+  uint32_t worker_threads;
+  /**
+   * Run operations on the threads calling into the library instead of a thread pool
+   *
+   * Callback and future based operations then only make progress while a blocking call is running.
+   */
+  bool current_thread;
+  /**
+   * Name of the runtime's threads, null for the default
+   */
+  const char *thread_name;
+  /**
+   * Called on each runtime thread after it starts, may be null
+   */
+  sr_thread_callback_t on_thread_start;
+  /**
+   * Called on each runtime thread before it stops, may be null
+   */
+  sr_thread_callback_t on_thread_stop;
+  /**
+   * Passed to the thread hooks, must be usable from any thread
+   */
+  void *userdata;
+} sr_runtime_options_t;
+
+/**
+ * Unit of work run by `sr_transaction_run`
+ *
+ * Must only use `txn` for operations and must not commit or roll it back.
+ * Returns a non-negative value on success, or a negative value to roll the transaction back.
+ * A callback failing because of an operation on `txn` should return right after it, so that the
+ * error of the operation is the one reported by `sr_transaction_run`.
+ */
+typedef int (*sr_transaction_callback_t)(struct sr_transaction_t *txn, void *userdata);
+
+/**
+ * A single patch operation of a diff
+ *
+ * Paths are JSON pointers such as `/tags/0`, the empty path designates the whole document.
+ */
+typedef struct sr_diff_op_t {
+  enum sr_diff_op_kind op;
+  sr_string_t path;
+  /**
+   * Source path of SR_DIFF_COPY and SR_DIFF_MOVE, null otherwise
+   */
+  sr_string_t from;
+  /**
+   * Operand of the operation, SR_VALUE_NONE if it has none
+   */
+  struct sr_value_t value;
+} sr_diff_op_t;
+
+/**
+ * A live query notification holding the changes of the record as patch operations
+ *
+ * Received with `sr_stream_next_diff` from the stream of a `LIVE SELECT DIFF` statement.
+ * Must be freed with `sr_free_diff_notification`
+ */
+typedef struct sr_diff_notification_t {
+  struct sr_uuid_t query_id;
+  enum sr_action action;
+  struct sr_diff_op_t *ops;
+  int ops_len;
+} sr_diff_notification_t;
+
+typedef struct sr_notification_t {
+  struct sr_uuid_t query_id;
+  enum sr_action action;
+  struct sr_value_t data;
+} sr_notification_t;
+
+/**
+ * Callback receiving the notifications of a subscribed stream
+ *
+ * For each notification `code` is 1 and `notification` points to it, it is only valid for the
+ * duration of the callback. Once the stream ends the callback is invoked a last time, with
+ * `code` set to SR_CLOSED, or to a negative error code with `err` pointing to the error.
+ */
+typedef void (*sr_notification_callback_t)(void *userdata,
+                                           int code,
+                                           const struct sr_notification_t *notification,
+                                           const struct sr_error_t *err);
+
+/**
+ * Connects to a local, remote, or embedded database
+ *
+ * If any function returns SR_FATAL, the connection is poisoned: every further call on it fails
+ * with SR_FATAL without being executed, until the connection is rebuilt with `sr_surreal_reconnect`
+ * or freed with `sr_surreal_disconnect`.
+ *
+ * # Safety
+ *
+ * - `err_ptr` must be a valid pointer or null (the error is then only available through sr_last_error)
+ * - `surreal_ptr` must be a valid pointer to receive the connection handle
+ * - `endpoint` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_string_t err;
+ * sr_surreal_t *db;
+ *
+ * // connect to in-memory instance
+ * if (sr_connect(&err, &db, "mem://") < 0) {
+ *     printf("error connecting to db: %s\n", err);
+ *     return 1;
+ * }
+ *
+ * // connect to surrealkv file
+ * if (sr_connect(&err, &db, "surrealkv://test.skv") < 0) {
+ *     printf("error connecting to db: %s\n", err);
+ *     return 1;
+ * }
+ *
+ * // connect to surrealdb server
+ * if (sr_connect(&err, &db, "wss://localhost:8000") < 0) {
+ *     printf("error connecting to db: %s\n", err);
+ *     return 1;
+ * }
+ *
+ * sr_surreal_disconnect(db);
+ * ```
+ */
+int sr_connect(sr_string_t *err_ptr,
+               struct sr_surreal_t **surreal_ptr,
+               const char *endpoint);
+
+/**
+ * Connects to a local, remote, or embedded database, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_connect` except for the type of `err_ptr`.
+ */
+int sr_connect_ex(struct sr_error_t *err_ptr,
+                  struct sr_surreal_t **surreal_ptr,
+                  const char *endpoint);
+
+/**
+ * Connects to a database and applies connection options
+ *
+ * Timeouts and capabilities are applied to the engine, then the connection signs in
+ * (if `auth_scope` is set), selects the namespace and database, and only then is the
+ * connection handed back. If `strict` is set the database is defined as STRICT when it
+ * does not exist yet.
+ *
+ * # Safety
+ *
+ * - `err_ptr` must be a valid pointer or null (the error is then only available through sr_last_error)
+ * - `surreal_ptr` must be a valid pointer to receive the connection handle
+ * - `endpoint` must be a valid null-terminated UTF-8 string
+ * - `options` must be a valid pointer to connect options with `version` set
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_string_t err;
+ * sr_surreal_t *db;
+ * sr_credentials_scope scope = ROOT;
+ * sr_credentials creds = { "root", "root" };
+ *
+ * sr_connect_options_t opts = {0};
+ * opts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+ * opts.query_timeout = (sr_duration_t){ .secs = 2, .nanos = 500000000 };
+ * opts.namespace_ = "test";
+ * opts.database = "test";
+ * opts.auth_scope = &scope;
+ * opts.credentials = &creds;
+ *
+ * if (sr_connect_with_options(&err, &db, "ws://localhost:8000", &opts) < 0) {
+ *     printf("error connecting to db: %s\n", err);
+ *     return 1;
+ * }
+ *
+ * sr_surreal_disconnect(db);
+ * ```
+ */
+int sr_connect_with_options(sr_string_t *err_ptr,
+                            struct sr_surreal_t **surreal_ptr,
+                            const char *endpoint,
+                            const struct sr_connect_options_t *options);
+
+/**
+ * Rebuild the connection on the same endpoint, clearing poisoning
+ *
+ * Opens a new connection with the endpoint and options the connection was created with,
+ * then replaces the old one. Operations already in flight complete on the old connection.
+ * The configuration passed to `sr_connect_with_options` is kept, and the session is restored on the new
+ * connection: it is authenticated again with the token of the last signin, signup or authenticate,
+ * and the selected namespace and database are selected again. Session variables are not restored.
+ *
+ * Embedded in-memory engines (`mem://`) start out empty, so all their data is lost, including the
+ * users a restored token may refer to.
+ *
+ * Returns SR_NONE on success, in which case the connection is no longer poisoned. If the connection
+ * was rebuilt but the session could not be restored, the connection is no longer poisoned either,
+ * but SR_ERROR is returned and the session has to be applied again.
+ *
+ * # Examples
+ *
+ * ```c
+ * if (sr_query(db, &err, &res, "RETURN 1", NULL) == sr_SR_FATAL) {
+ *     sr_free_string(err);
+ *     if (sr_surreal_reconnect(db, &err) < 0) {
+ *         printf("%s", err);
+ *         return 1;
+ *     }
+ * }
+ * ```
+ */
+int sr_surreal_reconnect(const struct sr_surreal_t *db,
+                         sr_string_t *err_ptr);
+
+/**
+ * Cancel all in-flight operations on a connection
+ *
+ * May be called from any thread. Every blocking, callback and future based call that is running
+ * when this is called fails with SR_CANCELLED. Calls started afterwards are not affected, and the
+ * connection stays usable.
+ *
+ * Cancelling an operation stops waiting for it, it does not roll back statements the server
+ * has already executed.
+ *
+ * # Examples
+ *
+ * ```c
+ * // on another thread
+ * sr_surreal_interrupt(db);
+ * ```
+ */
+void sr_surreal_interrupt(const struct sr_surreal_t *db);
+
+/**
+ * Set the default timeout of every operation on a connection
+ *
+ * Applies to blocking, callback and future based calls started afterwards, which fail with SR_TIMEOUT
+ * once `timeout_ms` milliseconds have passed. A timeout bound to the calling thread with `sr_timeout_bind`,
+ * or passed as the last argument of a `_timeout` sibling, overrides it for a single call.
+ * A negative `timeout_ms`, the default, disables the timeout.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_set_timeout(db, 5000);
+ * if (sr_query(db, &err, &res, "SLEEP 10s", NULL) == sr_SR_TIMEOUT) {
+ *     printf("%s", err);
+ * }
+ * ```
+ */
+void sr_surreal_set_timeout(const struct sr_surreal_t *db,
+                            int timeout_ms);
+
+/**
+ * Disconnect a database connection
+ *
+ * The Surreal object must not be used after this function has been called.
+ * Any object allocations will still be valid and should be freed using the appropriate function.
+ *
+ * Note: Stream objects should be killed before disconnection to ensure proper cleanup.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * // connect
+ * disconnect(db);
+ * ```
+ */
+void sr_surreal_disconnect(struct sr_surreal_t *db);
+
+/**
+ * Authenticate with a token
+ *
+ * Authenticates the current connection with a JWT token.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `token` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * const sr_string_t token = "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9...";
+ * if (sr_authenticate(db, &err, token) < 0) {
+ *     printf("Failed to authenticate: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_authenticate(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *token);
+
+/**
+ * Authenticate with a token, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_authenticate` except for the type of `err_ptr`.
+ */
+int sr_authenticate_ex(const struct sr_surreal_t *db,
+                       struct sr_error_t *err_ptr,
+                       const char *token);
+
+/**
+ * Begin a new transaction
+ *
+ * Starts a new database transaction.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_begin(db, &err) < 0) {
+ *     printf("Failed to begin transaction: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_begin(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+
+/**
+ * Cancel the current transaction
+ *
+ * Cancels and rolls back the current database transaction.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_cancel(db, &err) < 0) {
+ *     printf("Failed to cancel transaction: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_cancel(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+
+/**
+ * Commit the current transaction
+ *
+ * Commits and finalizes the current database transaction.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_commit(db, &err) < 0) {
+ *     printf("Failed to commit transaction: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_commit(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+
+/**
+ * Create a record
+ *
+ * Creates a new record in the specified resource with the given content.
+ * The resource can be a table name (e.g., "user") for auto-generated IDs,
+ * or a specific record ID (e.g., "user:john").
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` may be null (result will be discarded)
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
+ */
+int sr_create(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_object_t **res_ptr,
+              const char *resource,
+              const struct sr_object_t *content);
+
+/**
+ * Create a record, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_create` except for the type of `err_ptr`.
+ */
+int sr_create_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_object_t **res_ptr,
+                 const char *resource,
+                 const struct sr_object_t *content);
+
+/**
+ * Delete a record or records
+ *
+ * Deletes records from the specified resource.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *deleted;
+ * int len = sr_delete(db, &err, &deleted, "foo:bar");
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(deleted, len);
+ * ```
+ */
+int sr_delete(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *resource);
+
+/**
+ * Delete a record or records, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_delete` except for the type of `err_ptr`.
+ */
+int sr_delete_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource);
+
+/**
+ * Export database data to a file
+ *
+ * Exports all data from the current namespace and database to a file.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `file_path` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_export(db, &err, "backup.surql") < 0) {
+ *     printf("Export failed: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_export(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *file_path);
+
+/**
+ * Check the health of the database server
+ *
+ * Performs a health check on the database connection.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_health(db, &err) < 0) {
+ *     printf("Database unhealthy: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_health(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+
+/**
+ * Check if the database is healthy, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+ *
+ * Identical to `sr_health` apart from the extra `timeout_ms`, which overrides the connection's default
+ * timeout. A negative `timeout_ms` waits indefinitely.
+ */
+int sr_health_timeout(const struct sr_surreal_t *db,
+                      sr_string_t *err_ptr,
+                      int timeout_ms);
+
+/**
+ * Import database data from a file
+ *
+ * Imports data from a file into the current namespace and database.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `file_path` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_import(db, &err, "backup.surql") < 0) {
+ *     printf("Import failed: %s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_import(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *file_path);
+
+/**
+ * Insert one or more records
+ *
+ * Inserts records into the specified resource with the given content.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *inserted;
+ * sr_object_t *content = ...; // create content object
+ * int len = sr_insert(db, &err, &inserted, "foo", content);
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(inserted, len);
+ * ```
+ */
+int sr_insert(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *resource,
+              const struct sr_object_t *content);
+
+/**
+ * Insert one or more records, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_insert` except for the type of `err_ptr`.
+ */
+int sr_insert_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource,
+                 const struct sr_object_t *content);
+
+/**
+ * Insert many records into a table in a single call
+ *
+ * Inserts `count` rows from the `rows` array into `table`. Rows without an `id` field get a
+ * generated one. By default the call fails if any record already exists; `flags` changes this:
+ *
+ * - SR_INSERT_IGNORE skips rows whose record already exists
+ * - SR_INSERT_UPDATE updates existing records with the fields of the row, fields absent or null
+ *   in the row keep their current value
+ * - SR_INSERT_DISCARD does not return the records, `res_ptr` may then be null and 0 is returned
+ *
+ * Returns the number of records written to *res_ptr, which must be freed with sr_free_arr.
+ * Rows skipped by SR_INSERT_IGNORE are not returned.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array, or null with SR_INSERT_DISCARD
+ * - `table` must be a valid null-terminated UTF-8 string
+ * - `rows` must point to `count` objects, or be null if `count` is 0
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_object_t rows[1000];
+ * for (int i = 0; i < 1000; i++) {
+ *     rows[i] = sr_object_new();
+ *     sr_object_insert_int(&rows[i], "n", i);
+ * }
+ * if (sr_insert_many(db, &err, NULL, "reading", rows, 1000, sr_SR_INSERT_DISCARD) < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_insert_many(const struct sr_surreal_t *db,
+                   sr_string_t *err_ptr,
+                   struct sr_value_t **res_ptr,
+                   const char *table,
+                   const struct sr_object_t *rows,
+                   int count,
+                   int flags);
+
+/**
+ * Insert many records into a table from an array of objects
+ *
+ * Identical to `sr_insert_many` except that the rows are given as an array, every element of
+ * which must be an object.
+ */
+int sr_insert_many_arr(const struct sr_surreal_t *db,
+                       sr_string_t *err_ptr,
+                       struct sr_value_t **res_ptr,
+                       const char *table,
+                       const struct sr_array_t *rows,
+                       int flags);
+
+/**
+ * Insert a relation between records
+ *
+ * Creates a relation record in a relation table.
+ *
+ * The content object must contain 'in' and 'out' fields specifying the records to relate.
+ * Additional fields can be added as relation properties.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `table` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *result;
+ * sr_object_t *content = sr_object_new();
+ * sr_object_insert_str(content, "in", "person:john");
+ * sr_object_insert_str(content, "out", "person:jane");
+ * sr_object_insert_str(content, "met", "2024-01-01");
+ * int len = sr_insert_relation(db, &err, &result, "knows", content);
+ * if (len < 0) {
+ *     printf("Failed to insert relation: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(result, len);
+ * ```
+ */
+int sr_insert_relation(const struct sr_surreal_t *db,
+                       sr_string_t *err_ptr,
+                       struct sr_value_t **res_ptr,
+                       const char *table,
+                       const struct sr_object_t *content);
+
+/**
+ * Execute a SurrealDB function
+ *
+ * Runs a custom or built-in SurrealDB function with the specified arguments.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `function_name` must be a valid null-terminated UTF-8 string
+ * - `args` may be null (empty arguments)
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *result;
+ * sr_array_t args = ...; // create args array
+ * if (sr_run(db, &err, &result, "fn::my_function", &args) < 0) {
+ *     printf("Failed to run function: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(result, 1);
+ * ```
+ */
+int sr_run(const struct sr_surreal_t *db,
+           sr_string_t *err_ptr,
+           struct sr_value_t **res_ptr,
+           const char *function_name,
+           const struct sr_array_t *args);
+
+/**
+ * Execute a SurrealDB function, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_run` except for the type of `err_ptr`.
+ */
+int sr_run_ex(const struct sr_surreal_t *db,
+              struct sr_error_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *function_name,
+              const struct sr_array_t *args);
+
+/**
+ * Execute a SurrealDB function, aborting with SR_CANCELLED once `token` is cancelled
+ *
+ * Identical to `sr_run` apart from the extra `token`, which may be null.
+ */
+int sr_run_cancellable(const struct sr_surreal_t *db,
+                       sr_string_t *err_ptr,
+                       const struct sr_cancel_token_t *token,
+                       struct sr_value_t **res_ptr,
+                       const char *function_name,
+                       const struct sr_array_t *args);
+
+/**
+ * Create a graph relation between two records
+ *
+ * Establishes a directed relation from one record to another through a relation table.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `from` must be a valid null-terminated UTF-8 string
+ * - `relation` must be a valid null-terminated UTF-8 string
+ * - `to` must be a valid null-terminated UTF-8 string
+ * - `content` may be null (no content will be added)
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *result;
+ * sr_object_t *content = sr_object_new();
+ * int len = sr_relate(db, &err, &result, "person:john", "knows", "person:jane", content);
+ * if (len < 0) {
+ *     printf("Failed to create relation: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(result, len);
+ * ```
+ */
+int sr_relate(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *from,
+              const char *relation,
+              const char *to,
+              const struct sr_object_t *content);
+
+/**
+ * Create a graph relation between two records, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_relate` except for the type of `err_ptr`.
+ */
+int sr_relate_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *from,
+                 const char *relation,
+                 const char *to,
+                 const struct sr_object_t *content);
+
+/**
+ * Invalidate the current authentication session
+ *
+ * Clears the current authentication for the connection.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * if (sr_invalidate(db, &err) < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_invalidate(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+
+/**
+ * Kill a live query by its UUID string
+ *
+ * Terminates an active live query subscription.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `query_id` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * const char *query_id = "..."; // UUID string from live query
+ * if (sr_kill(db, &err, query_id) < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_kill(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *query_id);
+
+/**
+ * Make a live selection
+ *
+ * Creates a live query subscription that streams changes.
+ * if successful sets *stream_ptr to be an exclusive reference to an opaque Stream object
+ * which can be moved across threads but not aliased
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `stream_ptr` must be a valid pointer to receive the stream
+ * - `resource` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * sr_stream_t *stream;
+ * if (sr_select_live(db, &err, &stream, "foo") < 0)
+ * {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ *
+ * sr_notification_t not ;
+ * if (sr_stream_next(stream, &not ) > 0)
+ * {
+ *     sr_print_notification(&not );
+ * }
+ * sr_stream_kill(stream);
+ */
+int sr_select_live(const struct sr_surreal_t *db,
+                   sr_string_t *err_ptr,
+                   struct sr_stream_t **stream_ptr,
+                   const char *resource);
+
+/**
+ * Start a live query from a `LIVE SELECT` statement
+ *
+ * Unlike `sr_select_live` the statement may filter and project the records with `WHERE`,
+ * `FETCH` and parameters, which are evaluated by the server. The query must hold exactly
+ * one `LIVE SELECT` statement, otherwise it fails before anything is sent to the server.
+ * On success sets *stream_ptr to a Stream, consumed like the one of `sr_select_live`.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `stream_ptr` must be a valid pointer to receive the stream
+ * - `query` must be a valid null-terminated UTF-8 string
+ * - `vars` may be null (no variables bound)
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_stream_t *stream;
+ * if (sr_live_query(db, &err, &stream, "LIVE SELECT * FROM order WHERE status = $s", vars) < 0)
+ * {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * sr_stream_kill(stream);
+ * ```
+ */
+int sr_live_query(const struct sr_surreal_t *db,
+                  sr_string_t *err_ptr,
+                  struct sr_stream_t **stream_ptr,
+                  const char *query,
+                  const struct sr_object_t *vars);
+
+/**
+ * Make a live selection whose notifications hold the changes of the records
+ *
+ * Equivalent to `LIVE SELECT DIFF FROM resource`. Notifications are read with `sr_stream_next_diff`,
+ * which returns the changes as patch operations to apply with `sr_value_apply_diff`.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `stream_ptr` must be a valid pointer to receive the stream
+ * - `resource` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_stream_t *stream;
+ * if (sr_select_live_diff(db, &err, &stream, "foo") < 0)
+ * {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ *
+ * sr_diff_notification_t not;
+ * if (sr_stream_next_diff(stream, &not) > 0)
+ * {
+ *     sr_free_diff_notification(not);
+ * }
+ * sr_stream_kill(stream);
+ * ```
+ */
+int sr_select_live_diff(const struct sr_surreal_t *db,
+                        sr_string_t *err_ptr,
+                        struct sr_stream_t **stream_ptr,
+                        const char *resource);
+
+/**
+ * Merge data into existing records
+ *
+ * Merges the provided content into existing records, preserving unmodified fields.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *merged;
+ * sr_object_t *content = ...; // create content object
+ * int len = sr_merge(db, &err, &merged, "foo:bar", content);
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(merged, len);
+ * ```
+ */
+int sr_merge(const struct sr_surreal_t *db,
+             sr_string_t *err_ptr,
+             struct sr_value_t **res_ptr,
+             const char *resource,
+             const struct sr_object_t *content);
+
+/**
+ * Merge data into existing records, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_merge` except for the type of `err_ptr`.
+ */
+int sr_merge_ex(const struct sr_surreal_t *db,
+                struct sr_error_t *err_ptr,
+                struct sr_value_t **res_ptr,
+                const char *resource,
+                const struct sr_object_t *content);
+
+/**
+ * Add a value at a JSON path using JSON Patch
+ *
+ * Applies a JSON Patch add operation to the specified resource.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `path` must be a valid null-terminated UTF-8 string
+ * - `value` must be a valid pointer to a Value
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *patched;
+ * sr_value_t value = ...; // create value to add
+ * int len = sr_patch_add(db, &err, &patched, "person:john", "/tags/0", &value);
+ * if (len < 0) {
+ *     printf("Failed to patch: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(patched, len);
+ * ```
+ */
+int sr_patch_add(const struct sr_surreal_t *db,
+                 sr_string_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource,
+                 const char *path,
+                 const struct sr_value_t *value);
+
+/**
+ * Remove a value at a JSON path using JSON Patch
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `path` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *patched;
+ * int len = sr_patch_remove(db, &err, &patched, "person:john", "/temporary_field");
+ * if (len < 0) {
+ *     printf("Failed to patch: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(patched, len);
+ * ```
+ */
+int sr_patch_remove(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    struct sr_value_t **res_ptr,
+                    const char *resource,
+                    const char *path);
+
+/**
+ * Replace a value at a JSON path using JSON Patch
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `path` must be a valid null-terminated UTF-8 string
+ * - `value` must be a valid pointer to a Value
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *patched;
+ * sr_value_t value = ...; // create new value
+ * int len = sr_patch_replace(db, &err, &patched, "person:john", "/name", &value);
+ * if (len < 0) {
+ *     printf("Failed to patch: %s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(patched, len);
+ * ```
+ */
+int sr_patch_replace(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_value_t **res_ptr,
+                     const char *resource,
+                     const char *path,
+                     const struct sr_value_t *value);
+
+/**
+ * Execute a SurrealQL query
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `query` must be a valid null-terminated UTF-8 string
+ * - `vars` may be null (no variables bound)
+ */
+int sr_query(const struct sr_surreal_t *db,
+             sr_string_t *err_ptr,
+             struct sr_arr_res_t **res_ptr,
+             const char *query,
+             const struct sr_object_t *vars);
+
+/**
+ * Execute a SurrealQL query, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_query` except for the type of `err_ptr`.
+ */
+int sr_query_ex(const struct sr_surreal_t *db,
+                struct sr_error_t *err_ptr,
+                struct sr_arr_res_t **res_ptr,
+                const char *query,
+                const struct sr_object_t *vars);
+
+/**
+ * Execute a SurrealQL query, aborting with SR_CANCELLED once `token` is cancelled
+ *
+ * Identical to `sr_query` apart from the extra `token`, which may be null.
+ */
+int sr_query_cancellable(const struct sr_surreal_t *db,
+                         sr_string_t *err_ptr,
+                         const struct sr_cancel_token_t *token,
+                         struct sr_arr_res_t **res_ptr,
+                         const char *query,
+                         const struct sr_object_t *vars);
+
+/**
+ * Execute a SurrealQL query, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+ *
+ * Identical to `sr_query` apart from the extra `timeout_ms`, which overrides the connection's default
+ * timeout. A negative `timeout_ms` waits indefinitely.
+ */
+int sr_query_timeout(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_arr_res_t **res_ptr,
+                     const char *query,
+                     const struct sr_object_t *vars,
+                     int timeout_ms);
+
+/**
+ * Execute a SurrealQL query, returning a response object
+ *
+ * Writes a response to *res_ptr holding each statement's value as returned, its error and its
+ * execution time, and returns the number of statements. A failing statement does not fail the call.
+ * The response must be freed with sr_response_free.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the response
+ * - `query` must be a valid null-terminated UTF-8 string
+ * - `vars` may be null (no variables bound)
+ */
+int sr_query_response(const struct sr_surreal_t *db,
+                      sr_string_t *err_ptr,
+                      struct sr_response_t **res_ptr,
+                      const char *query,
+                      const struct sr_object_t *vars);
+
+/**
+ * Select a resource
+ *
+ * Selects records from the specified resource (table or record ID).
+ *
+ * can be used to select everything from a table or a single record
+ * writes values to *res_ptr, and returns the number of values
+ * result values are allocated by Surreal and must be freed with sr_free_arr
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *foos;
+ * int len = sr_select(db, &err, &foos, "foo");
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * ```
+ * for (int i = 0; i < len; i++)
+ * {
+ *     sr_value_print(&foos[i]);
+ * }
+ * sr_free_arr(foos, len);
+ */
+int sr_select(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *resource);
+
+/**
+ * Select a resource, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_select` except for the type of `err_ptr`.
+ */
+int sr_select_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource);
+
+/**
+ * Select a resource, aborting with SR_CANCELLED once `token` is cancelled
+ *
+ * Identical to `sr_select` apart from the extra `token`, which may be null.
+ */
+int sr_select_cancellable(const struct sr_surreal_t *db,
+                          sr_string_t *err_ptr,
+                          const struct sr_cancel_token_t *token,
+                          struct sr_value_t **res_ptr,
+                          const char *resource);
+
+/**
+ * Select a resource, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+ *
+ * Identical to `sr_select` apart from the extra `timeout_ms`, which overrides the connection's default
+ * timeout. A negative `timeout_ms` waits indefinitely.
+ */
+int sr_select_timeout(const struct sr_surreal_t *db,
+                      sr_string_t *err_ptr,
+                      struct sr_value_t **res_ptr,
+                      const char *resource,
+                      int timeout_ms);
+
+/**
+ * Set a variable for the current session
+ *
+ * Defines a session variable that can be referenced in queries.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `key` must be a valid null-terminated UTF-8 string
+ * - `value` must be a valid pointer to a Value
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_value_t *value = ...; // create value
+ * if (sr_set(db, &err, "my_var", value) < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * ```
+ */
+int sr_set(const struct sr_surreal_t *db,
+           sr_string_t *err_ptr,
+           const char *key,
+           const struct sr_value_t *value);
+
+/**
+ * Sign in utilizing the surreal authentication types
+ *
+ * Authenticates with the database using the provided credentials.
+ *
+ * Used to provide credentials to a db for access permissions, either root or scoped.
+ * Returns the JWT token via token_ptr if not null.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_string_t token;
+ *
+ * sr_credentials_scope scope = sr_credentials_scope::ROOT;
+ * const sr_string_t user = "<user>";
+ * // SHOULD NEVER BE HARDCODED
+ * const sr_string_t password = "<password>";
+ * sr_credentials creds = sr_credentials {
+ *     .username = user,
+ *     .password = pass,
+ * };
+ *
+ * if (sr_signin(db, &err, &token, &scope, &creds, nullptr, nullptr) < 0) {
+ *     printf("Failed to authenticate credentials: %s", err);
+ *     return 1;
+ * }
+ * // token now contains the JWT
+ * sr_free_string(token);
+ * ```
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_string_t token;
+ *
+ * sr_credentials_scope scope = sr_credentials_scope::DATABASE;
+ * const sr_string_t user = "<user>";
+ * // SHOULD NEVER BE HARDCODED
+ * const sr_string_t password = "<password>";
+ * sr_credentials creds = sr_credentials {
+ *     .username = user,
+ *     .password = pass,
+ * };
+ * sr_string_t namespace_ = "testing";
+ * sr_string_t db_name = "perf-test";
+ * sr_credentials_access details = sr_credentials_access {
+ *     .namespace_ = namespace_,
+ *     .database = db_name,
+ *     .access = nullptr,
+ * };
+ *
+ * if (sr_signin(db, &err, &token, &scope, &creds, &details, nullptr) < 0) {
+ *     printf("Failed to authenticate credentials: %s", err);
+ *     return 1;
+ * }
+ * ```
+ * For RECORD scope with custom params:
+ * ```c
+ * sr_object_t params = sr_object_new();
+ * sr_object_insert_str(&params, "email", "user@example.com");
+ * sr_object_insert_str(&params, "password", "secret");
+ * if (sr_signin(db, &err, &token, &scope, nullptr, &details, &params) < 0) {
+ *     // handle error
+ * }
+ * ```
+ */
+int sr_signin(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              sr_string_t *token_ptr,
+              const enum sr_credentials_scope *scope,
+              const struct sr_credentials *creds,
+              const struct sr_credentials_access *details,
+              const struct sr_object_t *params);
+
+/**
+ * Sign in utilizing the surreal authentication types, reporting errors as a structured `sr_error_t`
+ *
+ * Identical to `sr_signin` except for the type of `err_ptr`.
+ */
+int sr_signin_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 sr_string_t *token_ptr,
+                 const enum sr_credentials_scope *scope,
+                 const struct sr_credentials *creds,
+                 const struct sr_credentials_access *details,
+                 const struct sr_object_t *params);
+
+/**
+ * Sign up a new user with credentials
+ *
+ * Registers a new user account with the database.
+ * Returns the JWT token via token_ptr if not null.
+ * Only RECORD scope is supported for signup.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * sr_string_t err;
+ * sr_string_t token;
+ * sr_credentials_scope scope = sr_credentials_scope::RECORD;
+ * const sr_string_t user = "newuser";
+ * const sr_string_t password = "password123";
+ * sr_credentials creds = sr_credentials {
+ *     .username = user,
+ *     .password = password,
+ * };
+ * sr_string_t namespace_ = "test";
+ * sr_string_t db_name = "test";
+ * sr_string_t access = "user";
+ * sr_credentials_access details = sr_credentials_access {
+ *     .namespace_ = namespace_,
+ *     .database = db_name,
+ *     .access = access,
+ * };
+ *
+ * if (sr_signup(db, &err, &token, &scope, &creds, &details, nullptr) < 0) {
+ *     printf("Failed to sign up: %s", err);
+ *     return 1;
+ * }
+ * // token now contains the JWT
+ * sr_free_string(token);
+ * ```
+ * For custom params:
+ * ```c
+ * sr_object_t params = sr_object_new();
+ * sr_object_insert_str(&params, "email", "user@example.com");
+ * sr_object_insert_str(&params, "password", "secret");
+ * if (sr_signup(db, &err, &token, &scope, nullptr, &details, &params) < 0) {
+ *     // handle error
+ * }
+ * ```
  */
-typedef struct sr_SurrealError {
-  int code;
-  sr_string_t msg;
-} sr_SurrealError;
-
-typedef struct sr_arr_res_t {
-  struct sr_array_t ok;
-  struct sr_SurrealError err;
-} sr_arr_res_t;
-
-typedef struct sr_credentials {
-  sr_string_t username;
-  sr_string_t password;
-} sr_credentials;
-
-typedef struct sr_credentials_access {
-  sr_string_t namespace_;
-  sr_string_t database;
-  sr_string_t access;
-} sr_credentials_access;
+int sr_signup(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              sr_string_t *token_ptr,
+              const enum sr_credentials_scope *scope,
+              const struct sr_credentials *creds,
+              const struct sr_credentials_access *details,
+              const struct sr_object_t *params);
 
 /**
- * Connection options for SurrealDB
+ * Sign up a new user with credentials, reporting errors as a structured `sr_error_t`
  *
- * Configures various settings for the database connection.
+ * Identical to `sr_signup` except for the type of `err_ptr`.
  */
-typedef struct sr_option_t {
-  /**
-   * Enable strict mode for queries
-   */
-  bool strict;
-  /**
-   * Query timeout in seconds
-   */
-  uint8_t query_timeout;
-  /**
-   * Transaction timeout in seconds
-   */
-  uint8_t transaction_timeout;
-} sr_option_t;
-
-typedef struct sr_notification_t {
-  struct sr_uuid_t query_id;
-  enum sr_action action;
-  struct sr_value_t data;
-} sr_notification_t;
+int sr_signup_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 sr_string_t *token_ptr,
+                 const enum sr_credentials_scope *scope,
+                 const struct sr_credentials *creds,
+                 const struct sr_credentials_access *details,
+                 const struct sr_object_t *params);
 
 /**
- * Connects to a local, remote, or embedded database
+ * Unset a variable from the current session
  *
- * If any function returns SR_FATAL, the connection is poisoned and must not be used
- * (except to drop). Continued use will cause the program to abort.
+ * Removes a previously defined session variable.
  *
  * # Safety
  *
- * - `err_ptr` must be a valid pointer or null (errors ignored if null)
- * - `surreal_ptr` must be a valid pointer to receive the connection handle
- * - `endpoint` must be a valid null-terminated UTF-8 string
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `key` must be a valid null-terminated UTF-8 string
  *
  * # Examples
  *
  * ```c
- * sr_string_t err;
  * sr_surreal_t *db;
- *
- * // connect to in-memory instance
- * if (sr_connect(&err, &db, "mem://") < 0) {
- *     printf("error connecting to db: %s\n", err);
- *     return 1;
- * }
- *
- * // connect to surrealkv file
- * if (sr_connect(&err, &db, "surrealkv://test.skv") < 0) {
- *     printf("error connecting to db: %s\n", err);
- *     return 1;
- * }
- *
- * // connect to surrealdb server
- * if (sr_connect(&err, &db, "wss://localhost:8000") < 0) {
- *     printf("error connecting to db: %s\n", err);
+ * sr_string_t err;
+ * if (sr_unset(db, &err, "my_var") < 0) {
+ *     printf("%s", err);
  *     return 1;
  * }
- *
- * sr_surreal_disconnect(db);
  * ```
  */
-int sr_connect(sr_string_t *err_ptr, struct sr_surreal_t **surreal_ptr, const char *endpoint);
+int sr_unset(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *key);
 
 /**
- * Disconnect a database connection
+ * Update records with new content
  *
- * The Surreal object must not be used after this function has been called.
- * Any object allocations will still be valid and should be freed using the appropriate function.
+ * Replaces the content of existing records with new data.
  *
- * Note: Stream objects should be killed before disconnection to ensure proper cleanup.
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
  *
  * # Examples
  *
  * ```c
  * sr_surreal_t *db;
- * // connect
- * disconnect(db);
+ * sr_string_t err;
+ * sr_value_t *updated;
+ * sr_object_t *content = ...; // create content object
+ * int len = sr_update(db, &err, &updated, "foo:bar", content);
+ * if (len < 0) {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ * sr_free_arr(updated, len);
  * ```
  */
-void sr_surreal_disconnect(struct sr_surreal_t *db);
+int sr_update(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *resource,
+              const struct sr_object_t *content);
 
 /**
- * Authenticate with a token
+ * Update records with new content, reporting errors as a structured `sr_error_t`
  *
- * Authenticates the current connection with a JWT token.
+ * Identical to `sr_update` except for the type of `err_ptr`.
+ */
+int sr_update_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource,
+                 const struct sr_object_t *content);
+
+/**
+ * Upsert (insert or update) records
+ *
+ * Creates records if they don't exist, or updates them if they do.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `token` must be a valid null-terminated UTF-8 string
+ * - `res_ptr` must be a valid pointer to receive the result array
+ * - `resource` must be a valid null-terminated UTF-8 string
+ * - `content` must be a valid pointer to an Object
  *
  * # Examples
  *
  * ```c
  * sr_surreal_t *db;
  * sr_string_t err;
- * const sr_string_t token = "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9...";
- * if (sr_authenticate(db, &err, token) < 0) {
- *     printf("Failed to authenticate: %s", err);
+ * sr_value_t *upserted;
+ * sr_object_t *content = ...; // create content object
+ * int len = sr_upsert(db, &err, &upserted, "foo:bar", content);
+ * if (len < 0) {
+ *     printf("%s", err);
  *     return 1;
  * }
+ * sr_free_arr(upserted, len);
  * ```
  */
-int sr_authenticate(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *token);
+int sr_upsert(const struct sr_surreal_t *db,
+              sr_string_t *err_ptr,
+              struct sr_value_t **res_ptr,
+              const char *resource,
+              const struct sr_object_t *content);
 
 /**
- * Begin a new transaction
+ * Upsert (insert or update) records, reporting errors as a structured `sr_error_t`
  *
- * Starts a new database transaction.
+ * Identical to `sr_upsert` except for the type of `err_ptr`.
+ */
+int sr_upsert_ex(const struct sr_surreal_t *db,
+                 struct sr_error_t *err_ptr,
+                 struct sr_value_t **res_ptr,
+                 const char *resource,
+                 const struct sr_object_t *content);
+
+/**
+ * Select database
  *
- * # Examples
+ * Sets the database to use for subsequent operations.
+ * NOTE: namespace must be selected first with sr_use_ns
  *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `db_name` must be a valid null-terminated UTF-8 string
+ *
+ * # Examples
  * ```c
  * sr_surreal_t *db;
  * sr_string_t err;
- * if (sr_begin(db, &err) < 0) {
- *     printf("Failed to begin transaction: %s", err);
+ * if (sr_use_db(db, &err, "test") < 0)
+ * {
+ *     printf("%s", err);
  *     return 1;
  * }
  * ```
  */
-int sr_begin(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+int sr_use_db(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *db_name);
 
 /**
- * Cancel the current transaction
+ * Select database, reporting errors as a structured `sr_error_t`
  *
- * Cancels and rolls back the current database transaction.
+ * Identical to `sr_use_db` except for the type of `err_ptr`.
+ */
+int sr_use_db_ex(const struct sr_surreal_t *db, struct sr_error_t *err_ptr, const char *db_name);
+
+/**
+ * Select namespace
  *
- * # Examples
+ * Sets the namespace to use for subsequent operations.
+ * NOTE: database must be selected before use with sr_use_db
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `ns_name` must be a valid null-terminated UTF-8 string
  *
+ * # Examples
  * ```c
  * sr_surreal_t *db;
  * sr_string_t err;
- * if (sr_cancel(db, &err) < 0) {
- *     printf("Failed to cancel transaction: %s", err);
+ * if (sr_use_ns(db, &err, "test") < 0)
+ * {
+ *     printf("%s", err);
  *     return 1;
  * }
  * ```
  */
-int sr_cancel(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+int sr_use_ns(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *ns_name);
 
 /**
- * Commit the current transaction
+ * Select namespace, reporting errors as a structured `sr_error_t`
  *
- * Commits and finalizes the current database transaction.
+ * Identical to `sr_use_ns` except for the type of `err_ptr`.
+ */
+int sr_use_ns_ex(const struct sr_surreal_t *db, struct sr_error_t *err_ptr, const char *ns_name);
+
+/**
+ * Returns the database version
  *
- * # Examples
+ * Retrieves the version string of the connected SurrealDB server.
+ * NOTE: version is allocated in Surreal and must be freed with sr_free_string
+ *
+ * # Safety
  *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `res_ptr` must be a valid pointer to receive the version string
+ *
+ * # Examples
  * ```c
  * sr_surreal_t *db;
  * sr_string_t err;
- * if (sr_commit(db, &err) < 0) {
- *     printf("Failed to commit transaction: %s", err);
+ * sr_string_t ver;
+ *
+ * if (sr_version(db, &err, &ver) < 0)
+ * {
+ *     printf("%s", err);
  *     return 1;
  * }
+ * printf("%s", ver);
+ * sr_free_string(ver);
  * ```
  */
-int sr_commit(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+int sr_version(const struct sr_surreal_t *db, sr_string_t *err_ptr, sr_string_t *res_ptr);
 
 /**
- * Create a record
+ * Export the current namespace and database to a file, narrowed by options
  *
- * Creates a new record in the specified resource with the given content.
- * The resource can be a table name (e.g., "user") for auto-generated IDs,
- * or a specific record ID (e.g., "user:john").
+ * Identical to `sr_export` apart from the extra `options`, which may be null to export everything.
+ * The file is written by the calling process, also for remote connections.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` may be null (result will be discarded)
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * - `file_path` must be a valid null-terminated UTF-8 string
+ * - `options` must be a valid pointer or null
+ *
+ * # Examples
+ *
+ * ```c
+ * // schema-only snapshot for a migration
+ * sr_export_options_t opts = {0};
+ * opts.version = sr_SR_EXPORT_OPTIONS_VERSION;
+ * opts.no_records = true;
+ * if (sr_export_with_options(db, &err, "schema.surql", &opts) < 0) {
+ *     printf("Export failed: %s", err);
+ *     return 1;
+ * }
+ * ```
  */
-int sr_create(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_object_t **res_ptr,
-              const char *resource,
-              const struct sr_object_t *content);
+int sr_export_with_options(const struct sr_surreal_t *db,
+                           sr_string_t *err_ptr,
+                           const char *file_path,
+                           const struct sr_export_options_t *options);
 
 /**
- * Delete a record or records
+ * Export the current namespace and database to a callback
  *
- * Deletes records from the specified resource.
+ * The dump is produced as a stream of SurrealQL chunks, each passed to `write_cb` on the calling
+ * thread as soon as it is available, so the export is never held in memory as a whole.
+ * `options` may be null to export everything.
+ * Returns SR_NONE once the export is complete, or SR_ERROR if it failed or was aborted by the
+ * callback.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *deleted;
- * int len = sr_delete(db, &err, &deleted, "foo:bar");
- * if (len < 0) {
- *     printf("%s", err);
- *     return 1;
+ * int write_chunk(void *userdata, const uint8_t *data, int len) {
+ *     return fwrite(data, 1, len, (FILE *)userdata) == (size_t)len ? 0 : 1;
  * }
- * sr_free_arr(deleted, len);
+ *
+ * FILE *out = popen("gzip > backup.surql.gz", "w");
+ * if (sr_export_to_callback(db, &err, write_chunk, out, NULL) < 0) {
+ *     printf("Export failed: %s", err);
+ * }
+ * pclose(out);
  * ```
  */
-int sr_delete(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *resource);
+int sr_export_to_callback(const struct sr_surreal_t *db,
+                          sr_string_t *err_ptr,
+                          sr_write_callback_t write_cb,
+                          void *userdata,
+                          const struct sr_export_options_t *options);
 
 /**
- * Export database data to a file
+ * Export the current namespace and database into memory
  *
- * Exports all data from the current namespace and database to a file.
+ * `options` may be null to export everything.
+ * Returns the length of the dump written to *res_ptr, which must be freed with `sr_free_byte_arr`.
+ * Dumps larger than 2 GiB can only be exported with `sr_export_to_callback`.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `file_path` must be a valid null-terminated UTF-8 string
+ * - `res_ptr` must be a valid pointer to receive the dump
+ * - `options` must be a valid pointer or null
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_export(db, &err, "backup.surql") < 0) {
+ * uint8_t *dump;
+ * int len = sr_export_to_buffer(db, &err, &dump, NULL);
+ * if (len < 0) {
  *     printf("Export failed: %s", err);
  *     return 1;
  * }
+ * fwrite(dump, 1, len, stdout);
+ * sr_free_byte_arr(dump, len);
  * ```
  */
-int sr_export(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *file_path);
+int sr_export_to_buffer(const struct sr_surreal_t *db,
+                        sr_string_t *err_ptr,
+                        uint8_t **res_ptr,
+                        const struct sr_export_options_t *options);
 
 /**
- * Check the health of the database server
+ * Import a dump from a file into the current namespace and database, with options
  *
- * Performs a health check on the database connection.
+ * Unlike `sr_import`, the file is read by the calling process, also for remote connections, and
+ * executed in chunks of whole statements, 1 MiB by default. `options` may be null.
+ * The first statement which fails fails the import; the chunks executed before it are kept.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection
+ * - `err_ptr` must be a valid pointer or null
+ * - `file_path` must be a valid null-terminated UTF-8 string
+ * - `options` must be a valid pointer or null
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_health(db, &err) < 0) {
- *     printf("Database unhealthy: %s", err);
+ * int show_progress(void *userdata, int64_t bytes, int64_t statements) {
+ *     printf("\r%lld / %lld bytes, %lld statements", bytes, *(int64_t *)userdata, statements);
+ *     return cancel_requested;
+ * }
+ *
+ * sr_import_options_t opts = {0};
+ * opts.version = sr_SR_IMPORT_OPTIONS_VERSION;
+ * opts.progress = show_progress;
+ * opts.userdata = &file_size;
+ * if (sr_import_with_options(db, &err, "backup.surql", &opts) < 0) {
+ *     printf("Import failed: %s", err);
  *     return 1;
  * }
  * ```
  */
-int sr_health(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+int sr_import_with_options(const struct sr_surreal_t *db,
+                           sr_string_t *err_ptr,
+                           const char *file_path,
+                           const struct sr_import_options_t *options);
 
 /**
- * Import database data from a file
+ * Import a dump held in memory into the current namespace and database
  *
- * Imports data from a file into the current namespace and database.
+ * `data` holds `len` bytes of SurrealQL, as produced by the export functions. It is executed like
+ * `sr_import_with_options` executes a file, and `options` may be null.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `file_path` must be a valid null-terminated UTF-8 string
+ * - `data` must point to at least `len` readable bytes
+ * - `options` must be a valid pointer or null
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_import(db, &err, "backup.surql") < 0) {
+ * if (sr_import_from_buffer(db, &err, dump, len, NULL) < 0) {
  *     printf("Import failed: %s", err);
  *     return 1;
  * }
  * ```
  */
-int sr_import(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *file_path);
+int sr_import_from_buffer(const struct sr_surreal_t *db,
+                          sr_string_t *err_ptr,
+                          const uint8_t *data,
+                          int len,
+                          const struct sr_import_options_t *options);
 
 /**
- * Insert one or more records
+ * Create an empty batch
+ */
+struct sr_batch_t *sr_batch_new(void);
+
+/**
+ * Get the number of operations in a batch
+ */
+int sr_batch_len(const struct sr_batch_t *self);
+
+/**
+ * Free a batch
  *
- * Inserts records into the specified resource with the given content.
+ * # Safety
+ *
+ * - `batch` must be a pointer returned by `sr_batch_new` which was not freed yet, or null
+ */
+void sr_batch_free(struct sr_batch_t *batch);
+
+/**
+ * Add a create operation, like `sr_create`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_create(struct sr_batch_t *self,
+                    sr_string_t *err_ptr,
+                    const char *what,
+                    const struct sr_object_t *content);
+
+/**
+ * Add an insert operation, like `sr_insert`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_insert(struct sr_batch_t *self,
+                    sr_string_t *err_ptr,
+                    const char *what,
+                    const struct sr_object_t *content);
+
+/**
+ * Add an update operation replacing the content of records, like `sr_update`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_update(struct sr_batch_t *self,
+                    sr_string_t *err_ptr,
+                    const char *what,
+                    const struct sr_object_t *content);
+
+/**
+ * Add a merge operation, like `sr_merge`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_merge(struct sr_batch_t *self,
+                   sr_string_t *err_ptr,
+                   const char *what,
+                   const struct sr_object_t *content);
+
+/**
+ * Add a JSON Patch operation adding a value, like `sr_patch_add`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_patch_add(struct sr_batch_t *self,
+                       sr_string_t *err_ptr,
+                       const char *what,
+                       const char *path,
+                       const struct sr_value_t *value);
+
+/**
+ * Add a JSON Patch operation removing a value, like `sr_patch_remove`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_patch_remove(struct sr_batch_t *self,
+                          sr_string_t *err_ptr,
+                          const char *what,
+                          const char *path);
+
+/**
+ * Add a JSON Patch operation replacing a value, like `sr_patch_replace`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_patch_replace(struct sr_batch_t *self,
+                           sr_string_t *err_ptr,
+                           const char *what,
+                           const char *path,
+                           const struct sr_value_t *value);
+
+/**
+ * Add a delete operation, like `sr_delete`
+ *
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_delete(struct sr_batch_t *self, sr_string_t *err_ptr, const char *what);
+
+/**
+ * Add a relate operation, like `sr_relate`
+ *
+ * `content` may be null.
+ * Returns the index of the operation in the results, or SR_ERROR.
+ */
+int sr_batch_relate(struct sr_batch_t *self,
+                    sr_string_t *err_ptr,
+                    const char *from,
+                    const char *relation,
+                    const char *to,
+                    const struct sr_object_t *content);
+
+/**
+ * Add a function call, like `sr_run`
+ *
+ * `args` may be null for no arguments.
+ * Returns the index of the operation in the results, or SR_ERROR.
+ *
+ * # Safety
+ *
+ * - `err_ptr` must be a valid pointer or null
+ * - `function_name` must be a valid null-terminated UTF-8 string
+ * - `args` must be a valid pointer or null
+ */
+int sr_batch_run(struct sr_batch_t *self,
+                 sr_string_t *err_ptr,
+                 const char *function_name,
+                 const struct sr_array_t *args);
+
+/**
+ * Execute all operations of a batch in a single query
+ *
+ * Writes one result per operation to *res_ptr, in the order they were added, and returns the
+ * number of operations. A failing operation does not fail the call; its result holds the error.
+ * If `atomic` is true the operations run in a transaction, and when any of them fails none of
+ * them are applied. Free the results with `sr_free_arr_res_arr`.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
+ * - `res_ptr` must be a valid pointer to receive the results
+ * - `batch` must be a valid pointer to a batch
+ */
+int sr_batch_execute(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_arr_res_t **res_ptr,
+                     const struct sr_batch_t *batch,
+                     bool atomic);
+
+/**
+ * Select a resource without blocking
+ *
+ * Returns SR_NONE once the operation has been started, or SR_ERROR if the arguments are invalid,
+ * in which case the callback is not invoked.
+ * The callback receives the same values as `sr_select`. It runs on a runtime worker thread, where
+ * blocking calls on any connection fail with SR_ERROR, so chain further operations with `_async` calls.
+ *
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+ * - `err_ptr` must be a valid pointer or null
  * - `resource` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * - `callback` must not be null
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *inserted;
- * sr_object_t *content = ...; // create content object
- * int len = sr_insert(db, &err, &inserted, "foo", content);
- * if (len < 0) {
+ * void on_select(void *userdata, int len, sr_value_t *values, const sr_error_t *err) {
+ *     if (len < 0) {
+ *         printf("%s", err->msg);
+ *         return;
+ *     }
+ *     for (int i = 0; i < len; i++) {
+ *         sr_value_print(&values[i]);
+ *     }
+ *     sr_free_arr(values, len);
+ * }
+ *
+ * if (sr_select_async(db, &err, "foo", on_select, NULL) < 0) {
  *     printf("%s", err);
  *     return 1;
  * }
- * sr_free_arr(inserted, len);
  * ```
  */
-int sr_insert(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *resource,
-              const struct sr_object_t *content);
+int sr_select_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    sr_values_callback_t callback,
+                    void *userdata);
 
 /**
- * Insert a relation between records
+ * Create a record without blocking
  *
- * Creates a relation record in a relation table.
+ * The callback receives the created record, see `sr_select_async` for the calling convention.
+ */
+int sr_create_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    const struct sr_object_t *content,
+                    sr_object_callback_t callback,
+                    void *userdata);
+
+/**
+ * Insert one or more records without blocking
  *
- * The content object must contain 'in' and 'out' fields specifying the records to relate.
- * Additional fields can be added as relation properties.
+ * The callback receives the same values as `sr_insert`, see `sr_select_async` for the calling convention.
+ */
+int sr_insert_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    const struct sr_object_t *content,
+                    sr_values_callback_t callback,
+                    void *userdata);
+
+/**
+ * Update records with new content without blocking
  *
- * # Safety
+ * The callback receives the same values as `sr_update`, see `sr_select_async` for the calling convention.
+ */
+int sr_update_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    const struct sr_object_t *content,
+                    sr_values_callback_t callback,
+                    void *userdata);
+
+/**
+ * Upsert (insert or update) records without blocking
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `table` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * The callback receives the same values as `sr_upsert`, see `sr_select_async` for the calling convention.
+ */
+int sr_upsert_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    const struct sr_object_t *content,
+                    sr_values_callback_t callback,
+                    void *userdata);
+
+/**
+ * Merge data into existing records without blocking
  *
- * # Examples
+ * The callback receives the same values as `sr_merge`, see `sr_select_async` for the calling convention.
+ */
+int sr_merge_async(const struct sr_surreal_t *db,
+                   sr_string_t *err_ptr,
+                   const char *resource,
+                   const struct sr_object_t *content,
+                   sr_values_callback_t callback,
+                   void *userdata);
+
+/**
+ * Delete a record or records without blocking
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *result;
- * sr_object_t *content = sr_object_new();
- * sr_object_insert_str(content, "in", "person:john");
- * sr_object_insert_str(content, "out", "person:jane");
- * sr_object_insert_str(content, "met", "2024-01-01");
- * int len = sr_insert_relation(db, &err, &result, "knows", content);
- * if (len < 0) {
- *     printf("Failed to insert relation: %s", err);
- *     return 1;
- * }
- * sr_free_arr(result, len);
- * ```
+ * The callback receives the same values as `sr_delete`, see `sr_select_async` for the calling convention.
  */
-int sr_insert_relation(const struct sr_surreal_t *db,
-                       sr_string_t *err_ptr,
-                       struct sr_value_t **res_ptr,
-                       const char *table,
-                       const struct sr_object_t *content);
+int sr_delete_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *resource,
+                    sr_values_callback_t callback,
+                    void *userdata);
 
 /**
- * Execute a SurrealDB function
+ * Create a graph relation between two records without blocking
  *
- * Runs a custom or built-in SurrealDB function with the specified arguments.
+ * `content` may be null.
+ * The callback receives the same values as `sr_relate`, see `sr_select_async` for the calling convention.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the operation
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `function_name` must be a valid null-terminated UTF-8 string
- * - `args` may be null (empty arguments)
+ * - `from`, `relation` and `to` must be valid null-terminated UTF-8 strings
+ * - `content` must be a valid pointer or null
+ * - `callback` must not be null
+ */
+int sr_relate_async(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    const char *from,
+                    const char *relation,
+                    const char *to,
+                    const struct sr_object_t *content,
+                    sr_values_callback_t callback,
+                    void *userdata);
+
+/**
+ * Execute a SurrealDB function without blocking
  *
- * # Examples
+ * `args` may be null.
+ * The callback receives the same values as `sr_run`, see `sr_select_async` for the calling convention.
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *result;
- * sr_array_t args = ...; // create args array
- * if (sr_run(db, &err, &result, "fn::my_function", &args) < 0) {
- *     printf("Failed to run function: %s", err);
- *     return 1;
- * }
- * sr_free_arr(result, 1);
- * ```
+ * # Safety
+ *
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+ * - `err_ptr` must be a valid pointer or null
+ * - `function_name` must be a valid null-terminated UTF-8 string
+ * - `args` must be a valid pointer or null
+ * - `callback` must not be null
  */
-int sr_run(const struct sr_surreal_t *db,
-           sr_string_t *err_ptr,
-           struct sr_value_t **res_ptr,
-           const char *function_name,
-           const struct sr_array_t *args);
+int sr_run_async(const struct sr_surreal_t *db,
+                 sr_string_t *err_ptr,
+                 const char *function_name,
+                 const struct sr_array_t *args,
+                 sr_values_callback_t callback,
+                 void *userdata);
 
 /**
- * Create a graph relation between two records
+ * Execute a SurrealQL query without blocking
  *
- * Establishes a directed relation from one record to another through a relation table.
+ * `vars` may be null.
+ * The callback receives the same results as `sr_query`, see `sr_select_async` for the calling convention.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the operation
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `from` must be a valid null-terminated UTF-8 string
- * - `relation` must be a valid null-terminated UTF-8 string
- * - `to` must be a valid null-terminated UTF-8 string
- * - `content` may be null (no content will be added)
+ * - `query` must be a valid null-terminated UTF-8 string
+ * - `vars` must be a valid pointer or null
+ * - `callback` must not be null
+ */
+int sr_query_async(const struct sr_surreal_t *db,
+                   sr_string_t *err_ptr,
+                   const char *query,
+                   const struct sr_object_t *vars,
+                   sr_query_callback_t callback,
+                   void *userdata);
+
+/**
+ * Create a new cancellation token
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *result;
- * sr_object_t *content = sr_object_new();
- * int len = sr_relate(db, &err, &result, "person:john", "knows", "person:jane", content);
- * if (len < 0) {
- *     printf("Failed to create relation: %s", err);
- *     return 1;
+ * sr_cancel_token_t *token = sr_cancel_token_new();
+ *
+ * // on another thread
+ * sr_cancel_token_cancel(token);
+ *
+ * // on the calling thread
+ * int len = sr_query_cancellable(db, &err, token, &res, "SLEEP 1h", NULL);
+ * if (len == sr_SR_CANCELLED) {
+ *     printf("cancelled: %s", err);
  * }
- * sr_free_arr(result, len);
+ * sr_cancel_token_free(token);
  * ```
  */
-int sr_relate(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *from,
-              const char *relation,
-              const char *to,
-              const struct sr_object_t *content);
+struct sr_cancel_token_t *sr_cancel_token_new(void);
 
 /**
- * Invalidate the current authentication session
+ * Cancel all calls using the token, including those started after this call
+ */
+void sr_cancel_token_cancel(const struct sr_cancel_token_t *self);
+
+/**
+ * Check whether the token has been cancelled
+ */
+bool sr_cancel_token_is_cancelled(const struct sr_cancel_token_t *self);
+
+/**
+ * Reset a cancelled token so it can be reused
+ */
+void sr_cancel_token_reset(const struct sr_cancel_token_t *self);
+
+/**
+ * Bind the token to the calling thread
  *
- * Clears the current authentication for the connection.
+ * Every blocking call made on the calling thread is aborted with SR_CANCELLED once the token is
+ * cancelled, in addition to any token passed to a `_cancellable` sibling, until `sr_cancel_token_unbind`
+ * is called or another token is bound. Freeing a bound token leaves the calls uncancellable.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_invalidate(db, &err) < 0) {
- *     printf("%s", err);
- *     return 1;
+ * sr_cancel_token_bind(token);
+ * if (sr_create(db, &err, &res, "person", content) == sr_SR_CANCELLED) {
+ *     printf("cancelled: %s", err);
  * }
+ * sr_cancel_token_unbind();
  * ```
  */
-int sr_invalidate(const struct sr_surreal_t *db, sr_string_t *err_ptr);
+void sr_cancel_token_bind(const struct sr_cancel_token_t *self);
 
 /**
- * Kill a live query by its UUID string
- *
- * Terminates an active live query subscription.
+ * Unbind the token bound to the calling thread, if any
+ */
+void sr_cancel_token_unbind(void);
+
+/**
+ * Free a cancellation token
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `query_id` must be a valid null-terminated UTF-8 string
+ * - `token` must be a pointer returned by `sr_cancel_token_new` which was not freed yet, or null
+ */
+void sr_cancel_token_free(struct sr_cancel_token_t *token);
+
+/**
+ * Bind a timeout to the calling thread
+ *
+ * Every blocking call made on the calling thread fails with SR_TIMEOUT once `timeout_ms` milliseconds
+ * have passed, overriding the connection's default timeout, until `sr_timeout_unbind` is called.
+ * A negative `timeout_ms` waits indefinitely. The `_timeout` siblings still use their own timeout.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * const char *query_id = "..."; // UUID string from live query
- * if (sr_kill(db, &err, query_id) < 0) {
- *     printf("%s", err);
- *     return 1;
+ * sr_timeout_bind(500);
+ * if (sr_create(db, &err, &res, "person", content) == sr_SR_TIMEOUT) {
+ *     printf("timed out: %s", err);
  * }
+ * sr_timeout_unbind();
  * ```
  */
-int sr_kill(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *query_id);
+void sr_timeout_bind(int timeout_ms);
 
 /**
- * Make a live selection
+ * Unbind the timeout bound to the calling thread, restoring the connection's default timeout
+ */
+void sr_timeout_unbind(void);
+
+/**
+ * Open a cursor over the result of a query
  *
- * Creates a live query subscription that streams changes.
- * if successful sets *stream_ptr to be an exclusive reference to an opaque Stream object
- * which can be moved across threads but not aliased
+ * `query` must be a single statement returning an array, usually a SELECT.
+ * `batch_size` is the number of rows fetched at once, 0 or less for the default of 1000.
+ * No rows are fetched until the first call to `sr_cursor_next`.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the cursor
  * - `err_ptr` must be a valid pointer or null
- * - `stream_ptr` must be a valid pointer to receive the stream
- * - `resource` must be a valid null-terminated UTF-8 string
+ * - `cursor_ptr` must be a valid pointer to receive the cursor
+ * - `query` must be a valid null-terminated UTF-8 string
+ * - `vars` may be null (no variables bound)
  *
  * # Examples
  *
- * sr_stream_t *stream;
- * if (sr_select_live(db, &err, &stream, "foo") < 0)
- * {
+ * ```c
+ * sr_cursor_t *cursor;
+ * if (sr_cursor_open(db, &err, &cursor, "SELECT * FROM event ORDER BY time", NULL, 500) < 0) {
  *     printf("%s", err);
  *     return 1;
  * }
- *
- * sr_notification_t not ;
- * if (sr_stream_next(stream, &not ) > 0)
- * {
- *     sr_print_notification(&not );
+ * sr_value_t *row;
+ * int res;
+ * while ((res = sr_cursor_next(cursor, &err, &row)) > 0) {
+ *     sr_value_print(row);
+ *     sr_value_free(row);
  * }
- * sr_stream_kill(stream);
+ * sr_cursor_close(cursor);
+ * ```
  */
-int sr_select_live(const struct sr_surreal_t *db,
+int sr_cursor_open(const struct sr_surreal_t *db,
                    sr_string_t *err_ptr,
-                   struct sr_stream_t **stream_ptr,
-                   const char *resource);
+                   struct sr_cursor_t **cursor_ptr,
+                   const char *query,
+                   const struct sr_object_t *vars,
+                   int batch_size);
 
 /**
- * Merge data into existing records
+ * Get the next row of a cursor
  *
- * Merges the provided content into existing records, preserving unmodified fields.
+ * Fetches the next batch when the current one is used up.
+ * Returns 1 and writes the row to *val_ptr, SR_NONE once all rows have been returned, or a
+ * negative error code if fetching a batch failed. Free the row with `sr_value_free`.
+ */
+int sr_cursor_next(struct sr_cursor_t *self, sr_string_t *err_ptr, struct sr_value_t **val_ptr);
+
+/**
+ * Close a cursor and free any rows it still holds
+ */
+void sr_cursor_close(struct sr_cursor_t *cursor);
+
+/**
+ * Get a file descriptor which becomes readable when any future of this connection completes
  *
- * # Safety
+ * The descriptor is created on the first call and owned by the connection, so it must not be closed
+ * and is only valid until `sr_surreal_disconnect`. Completions are only signalled once the descriptor
+ * exists, so call this before starting the futures it should report.
+ * After it becomes readable, read and discard the available bytes, then check each pending future with
+ * `sr_future_poll`. A single readable event may stand for several completed futures.
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * Returns the descriptor, or SR_ERROR on failure or on platforms without descriptor support.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *merged;
- * sr_object_t *content = ...; // create content object
- * int len = sr_merge(db, &err, &merged, "foo:bar", content);
- * if (len < 0) {
- *     printf("%s", err);
- *     return 1;
+ * int fd = sr_surreal_fd(db, &err);
+ * struct pollfd pfd = {.fd = fd, .events = POLLIN};
+ * poll(&pfd, 1, -1);
+ *
+ * char buf[64];
+ * while (read(fd, buf, sizeof(buf)) > 0) {}
+ * if (sr_future_poll(fut) == 1) {
+ *     // take the result
  * }
- * sr_free_arr(merged, len);
  * ```
  */
-int sr_merge(const struct sr_surreal_t *db,
-             sr_string_t *err_ptr,
-             struct sr_value_t **res_ptr,
-             const char *resource,
-             const struct sr_object_t *content);
+int sr_surreal_fd(const struct sr_surreal_t *db,
+                  sr_string_t *err_ptr);
 
 /**
- * Add a value at a JSON path using JSON Patch
+ * Select a resource, returning a future instead of blocking
  *
- * Applies a JSON Patch add operation to the specified resource.
+ * Returns SR_NONE and writes the future to `fut_ptr` once the operation has been started,
+ * or SR_ERROR if the arguments are invalid.
+ * `sr_future_take_result` yields the same values as `sr_select`, written to a `sr_value_t**`.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the future
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
+ * - `fut_ptr` must be a valid pointer to receive the future
  * - `resource` must be a valid null-terminated UTF-8 string
- * - `path` must be a valid null-terminated UTF-8 string
- * - `value` must be a valid pointer to a Value
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *patched;
- * sr_value_t value = ...; // create value to add
- * int len = sr_patch_add(db, &err, &patched, "person:john", "/tags/0", &value);
- * if (len < 0) {
- *     printf("Failed to patch: %s", err);
+ * sr_future_t *fut;
+ * if (sr_select_future(db, &err, &fut, "foo") < 0) {
+ *     printf("%s", err);
  *     return 1;
  * }
- * sr_free_arr(patched, len);
+ *
+ * sr_future_wait(fut, -1);
+ * sr_value_t *vals;
+ * int len = sr_future_take_result(fut, &err, &vals);
+ * if (len >= 0) {
+ *     sr_free_arr(vals, len);
+ * }
+ * sr_future_free(fut);
  * ```
  */
-int sr_patch_add(const struct sr_surreal_t *db,
-                 sr_string_t *err_ptr,
-                 struct sr_value_t **res_ptr,
-                 const char *resource,
-                 const char *path,
-                 const struct sr_value_t *value);
+int sr_select_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *resource);
 
 /**
- * Remove a value at a JSON path using JSON Patch
+ * Create a record, returning a future instead of blocking
  *
- * # Safety
+ * `sr_future_take_result` yields the created record, written to a `sr_object_t**`.
+ * See `sr_select_future` for the calling convention.
+ */
+int sr_create_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *resource,
+                     const struct sr_object_t *content);
+
+/**
+ * Insert one or more records, returning a future instead of blocking
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `path` must be a valid null-terminated UTF-8 string
+ * `sr_future_take_result` yields the same values as `sr_insert`, see `sr_select_future` for the calling convention.
+ */
+int sr_insert_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *resource,
+                     const struct sr_object_t *content);
+
+/**
+ * Update records with new content, returning a future instead of blocking
  *
- * # Examples
+ * `sr_future_take_result` yields the same values as `sr_update`, see `sr_select_future` for the calling convention.
+ */
+int sr_update_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *resource,
+                     const struct sr_object_t *content);
+
+/**
+ * Upsert (insert or update) records, returning a future instead of blocking
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *patched;
- * int len = sr_patch_remove(db, &err, &patched, "person:john", "/temporary_field");
- * if (len < 0) {
- *     printf("Failed to patch: %s", err);
- *     return 1;
- * }
- * sr_free_arr(patched, len);
- * ```
+ * `sr_future_take_result` yields the same values as `sr_upsert`, see `sr_select_future` for the calling convention.
  */
-int sr_patch_remove(const struct sr_surreal_t *db,
+int sr_upsert_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *resource,
+                     const struct sr_object_t *content);
+
+/**
+ * Merge data into existing records, returning a future instead of blocking
+ *
+ * `sr_future_take_result` yields the same values as `sr_merge`, see `sr_select_future` for the calling convention.
+ */
+int sr_merge_future(const struct sr_surreal_t *db,
                     sr_string_t *err_ptr,
-                    struct sr_value_t **res_ptr,
+                    struct sr_future_t **fut_ptr,
                     const char *resource,
-                    const char *path);
+                    const struct sr_object_t *content);
 
 /**
- * Replace a value at a JSON path using JSON Patch
- *
- * # Safety
- *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `path` must be a valid null-terminated UTF-8 string
- * - `value` must be a valid pointer to a Value
- *
- * # Examples
+ * Delete a record or records, returning a future instead of blocking
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *patched;
- * sr_value_t value = ...; // create new value
- * int len = sr_patch_replace(db, &err, &patched, "person:john", "/name", &value);
- * if (len < 0) {
- *     printf("Failed to patch: %s", err);
- *     return 1;
- * }
- * sr_free_arr(patched, len);
- * ```
+ * `sr_future_take_result` yields the same values as `sr_delete`, see `sr_select_future` for the calling convention.
  */
-int sr_patch_replace(const struct sr_surreal_t *db,
+int sr_delete_future(const struct sr_surreal_t *db,
                      sr_string_t *err_ptr,
-                     struct sr_value_t **res_ptr,
-                     const char *resource,
-                     const char *path,
-                     const struct sr_value_t *value);
+                     struct sr_future_t **fut_ptr,
+                     const char *resource);
 
 /**
- * Execute a SurrealQL query
+ * Create a graph relation between two records, returning a future instead of blocking
  *
- * # Safety
+ * `content` may be null.
+ * `sr_future_take_result` yields the same values as `sr_relate`, see `sr_select_future` for the calling convention.
+ */
+int sr_relate_future(const struct sr_surreal_t *db,
+                     sr_string_t *err_ptr,
+                     struct sr_future_t **fut_ptr,
+                     const char *from,
+                     const char *relation,
+                     const char *to,
+                     const struct sr_object_t *content);
+
+/**
+ * Execute a SurrealDB function, returning a future instead of blocking
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `query` must be a valid null-terminated UTF-8 string
- * - `vars` may be null (no variables bound)
+ * `args` may be null.
+ * `sr_future_take_result` yields the same values as `sr_run`, see `sr_select_future` for the calling convention.
  */
-int sr_query(const struct sr_surreal_t *db,
-             sr_string_t *err_ptr,
-             struct sr_arr_res_t **res_ptr,
-             const char *query,
-             const struct sr_object_t *vars);
+int sr_run_future(const struct sr_surreal_t *db,
+                  sr_string_t *err_ptr,
+                  struct sr_future_t **fut_ptr,
+                  const char *function_name,
+                  const struct sr_array_t *args);
 
 /**
- * Select a resource
+ * Execute a SurrealQL query, returning a future instead of blocking
  *
- * Selects records from the specified resource (table or record ID).
+ * `vars` may be null.
+ * `sr_future_take_result` yields the same results as `sr_query`, written to a `sr_arr_res_t**`.
+ * See `sr_select_future` for the calling convention.
+ */
+int sr_query_future(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    struct sr_future_t **fut_ptr,
+                    const char *query,
+                    const struct sr_object_t *vars);
+
+/**
+ * Check whether a future has completed without blocking
  *
- * can be used to select everything from a table or a single record
- * writes values to *res_ptr, and returns the number of values
- * result values are allocated by Surreal and must be freed with sr_free_arr
+ * Returns 1 if the result is ready or has already been taken, and SR_NONE if the operation is still running.
+ */
+int sr_future_poll(const struct sr_future_t *self);
+
+/**
+ * Block until a future completes or the timeout expires
  *
- * # Safety
+ * A negative `timeout_ms` waits indefinitely.
+ * Returns 1 once the future has completed, and SR_NONE if the timeout expired first.
+ */
+int sr_future_wait(const struct sr_future_t *self, int timeout_ms);
+
+/**
+ * Take the result of a completed future
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
+ * `res_ptr` must point to the result type documented by the function which created the future:
+ * a `sr_value_t**`, `sr_object_t**` or `sr_arr_res_t**`. It is written, and ownership passes to the
+ * caller, only if the operation succeeded.
  *
- * # Examples
+ * Returns the same value as the blocking function would have, SR_ERROR if the operation failed,
+ * or SR_PENDING if it has not completed yet. The result can only be taken once.
+ */
+int sr_future_take_result(struct sr_future_t *self, sr_string_t *err_ptr, void *res_ptr);
+
+/**
+ * Free a future
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *foos;
- * int len = sr_select(db, &err, &foos, "foo");
- * if (len < 0) {
- *     printf("%s", err);
- *     return 1;
- * }
- * ```
- * for (int i = 0; i < len; i++)
- * {
- *     sr_value_print(&foos[i]);
- * }
- * sr_free_arr(foos, len);
+ * A future that has not completed is detached: the operation keeps running and its result is discarded.
  */
-int sr_select(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *resource);
+void sr_future_free(struct sr_future_t *fut);
+
+/**
+ * Free the errors held by a load report
+ */
+void sr_free_load_report(struct sr_load_report_t report);
 
 /**
- * Set a variable for the current session
+ * Load newline-delimited JSON from a file into a table
  *
- * Defines a session variable that can be referenced in queries.
+ * Every non-empty line must hold a JSON object, which is inserted as a record. Objects with an
+ * `id` field keep it as their record id.
+ * Returns SR_NONE once all rows have been handled, or a negative error code if the load stopped.
+ * If `report_ptr` is not null the report is written to it in both cases, and must be freed with
+ * `sr_free_load_report`.
  *
  * # Safety
  *
  * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `key` must be a valid null-terminated UTF-8 string
- * - `value` must be a valid pointer to a Value
+ * - `report_ptr` must be a valid pointer or null
+ * - `table` and `path` must be valid null-terminated UTF-8 strings
+ * - `options` may be null for the defaults
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *value = ...; // create value
- * if (sr_set(db, &err, "my_var", value) < 0) {
+ * sr_load_options_t opts = {0};
+ * opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+ * opts.on_error = sr_SR_LOAD_COLLECT;
+ *
+ * sr_load_report_t report;
+ * if (sr_load_ndjson(db, &err, &report, "event", "events.ndjson", &opts) < 0) {
  *     printf("%s", err);
- *     return 1;
  * }
+ * for (int i = 0; i < report.errors_len; i++) {
+ *     printf("line %lld: %s\n", report.errors[i].line, report.errors[i].message);
+ * }
+ * sr_free_load_report(report);
  * ```
  */
-int sr_set(const struct sr_surreal_t *db,
-           sr_string_t *err_ptr,
-           const char *key,
-           const struct sr_value_t *value);
+int sr_load_ndjson(const struct sr_surreal_t *db,
+                   sr_string_t *err_ptr,
+                   struct sr_load_report_t *report_ptr,
+                   const char *table,
+                   const char *path,
+                   const struct sr_load_options_t *options);
 
 /**
- * Sign in utilizing the surreal authentication types
+ * Load newline-delimited JSON from a buffer into a table
  *
- * Authenticates with the database using the provided credentials.
+ * Identical to `sr_load_ndjson` except that the data is read from the `len` bytes at `data`.
+ */
+int sr_load_ndjson_buf(const struct sr_surreal_t *db,
+                       sr_string_t *err_ptr,
+                       struct sr_load_report_t *report_ptr,
+                       const char *table,
+                       const char *data,
+                       int len,
+                       const struct sr_load_options_t *options);
+
+/**
+ * Load CSV from a file into a table
  *
- * Used to provide credentials to a db for access permissions, either root or scoped.
- * Returns the JWT token via token_ptr if not null.
+ * Every row is inserted as a record with a field per column, named after the header row or
+ * `columns` of the options. Fields are converted according to `column_types`, empty fields are
+ * NULL unless the column is of type SR_COLUMN_STRING. A column named `id` is used as record id.
+ * Returns and reports like `sr_load_ndjson`.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_string_t token;
+ * const char *columns[] = {"id", "name", "price", "listed"};
+ * int types[] = {sr_SR_COLUMN_STRING, sr_SR_COLUMN_STRING, sr_SR_COLUMN_FLOAT, sr_SR_COLUMN_DATETIME};
  *
- * sr_credentials_scope scope = sr_credentials_scope::ROOT;
- * const sr_string_t user = "<user>";
- * // SHOULD NEVER BE HARDCODED
- * const sr_string_t password = "<password>";
- * sr_credentials creds = sr_credentials {
- *     .username = user,
- *     .password = pass,
- * };
+ * sr_load_options_t opts = {0};
+ * opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+ * opts.delimiter = ';';
+ * opts.columns = columns;
+ * opts.column_types = types;
+ * opts.column_count = 4;
  *
- * if (sr_signin(db, &err, &token, &scope, &creds, nullptr, nullptr) < 0) {
- *     printf("Failed to authenticate credentials: %s", err);
+ * if (sr_load_csv(db, &err, NULL, "product", "products.csv", &opts) < 0) {
+ *     printf("%s", err);
  *     return 1;
  * }
- * // token now contains the JWT
- * sr_free_string(token);
  * ```
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_string_t token;
- *
- * sr_credentials_scope scope = sr_credentials_scope::DATABASE;
- * const sr_string_t user = "<user>";
- * // SHOULD NEVER BE HARDCODED
- * const sr_string_t password = "<password>";
- * sr_credentials creds = sr_credentials {
- *     .username = user,
- *     .password = pass,
- * };
- * sr_string_t namespace_ = "testing";
- * sr_string_t db_name = "perf-test";
- * sr_credentials_access details = sr_credentials_access {
- *     .namespace_ = namespace_,
- *     .database = db_name,
- *     .access = nullptr,
- * };
+ */
+int sr_load_csv(const struct sr_surreal_t *db,
+                sr_string_t *err_ptr,
+                struct sr_load_report_t *report_ptr,
+                const char *table,
+                const char *path,
+                const struct sr_load_options_t *options);
+
+/**
+ * Load CSV from a buffer into a table
  *
- * if (sr_signin(db, &err, &token, &scope, &creds, &details, nullptr) < 0) {
- *     printf("Failed to authenticate credentials: %s", err);
- *     return 1;
- * }
- * ```
- * For RECORD scope with custom params:
- * ```c
- * sr_object_t params = sr_object_new();
- * sr_object_insert_str(&params, "email", "user@example.com");
- * sr_object_insert_str(&params, "password", "secret");
- * if (sr_signin(db, &err, &token, &scope, nullptr, &details, &params) < 0) {
- *     // handle error
- * }
- * ```
+ * Identical to `sr_load_csv` except that the data is read from the `len` bytes at `data`.
  */
-int sr_signin(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              sr_string_t *token_ptr,
-              const enum sr_credentials_scope *scope,
-              const struct sr_credentials *creds,
-              const struct sr_credentials_access *details,
-              const struct sr_object_t *params);
+int sr_load_csv_buf(const struct sr_surreal_t *db,
+                    sr_string_t *err_ptr,
+                    struct sr_load_report_t *report_ptr,
+                    const char *table,
+                    const char *data,
+                    int len,
+                    const struct sr_load_options_t *options);
 
 /**
- * Sign up a new user with credentials
+ * Check a query for syntax errors without executing it
  *
- * Registers a new user account with the database.
- * Returns the JWT token via token_ptr if not null.
- * Only RECORD scope is supported for signup.
+ * Returns the number of diagnostics written to `diag_ptr`, 0 if the query is valid SurrealQL.
+ * The parser stops at the first error, so one error may produce several diagnostics pointing at
+ * the different locations involved, each with its own message.
+ * Free the diagnostics with `sr_free_diagnostics`.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_string_t token;
- * sr_credentials_scope scope = sr_credentials_scope::RECORD;
- * const sr_string_t user = "newuser";
- * const sr_string_t password = "password123";
- * sr_credentials creds = sr_credentials {
- *     .username = user,
- *     .password = password,
- * };
- * sr_string_t namespace_ = "test";
- * sr_string_t db_name = "test";
- * sr_string_t access = "user";
- * sr_credentials_access details = sr_credentials_access {
- *     .namespace_ = namespace_,
- *     .database = db_name,
- *     .access = access,
- * };
- *
- * if (sr_signup(db, &err, &token, &scope, &creds, &details, nullptr) < 0) {
- *     printf("Failed to sign up: %s", err);
- *     return 1;
- * }
- * // token now contains the JWT
- * sr_free_string(token);
- * ```
- * For custom params:
- * ```c
- * sr_object_t params = sr_object_new();
- * sr_object_insert_str(&params, "email", "user@example.com");
- * sr_object_insert_str(&params, "password", "secret");
- * if (sr_signup(db, &err, &token, &scope, nullptr, &details, &params) < 0) {
- *     // handle error
+ * sr_diagnostic_t *diags;
+ * int len = sr_parse_check(&err, &diags, "SELECT * FROM user WHERE");
+ * for (int i = 0; i < len; i++) {
+ *     printf("%d:%d: %s\n", diags[i].line, diags[i].column, diags[i].message);
  * }
+ * sr_free_diagnostics(diags, len);
  * ```
  */
-int sr_signup(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              sr_string_t *token_ptr,
-              const enum sr_credentials_scope *scope,
-              const struct sr_credentials *creds,
-              const struct sr_credentials_access *details,
-              const struct sr_object_t *params);
+int sr_parse_check(sr_string_t *err_ptr, struct sr_diagnostic_t **diag_ptr, const char *query);
 
 /**
- * Unset a variable from the current session
+ * Free diagnostics returned by `sr_parse_check`
+ */
+void sr_free_diagnostics(struct sr_diagnostic_t *ptr, int len);
+
+int sr_surreal_rpc_new(sr_string_t *err_ptr,
+                       struct sr_surreal_rpc_t **surreal_ptr,
+                       const char *endpoint,
+                       struct sr_option_t options);
+
+/**
+ * Create a new rpc context running on a shared runtime
  *
- * Removes a previously defined session variable.
+ * Identical to `sr_surreal_rpc_new` apart from `runtime`, which may be null to create a runtime
+ * for this context.
+ */
+int sr_surreal_rpc_new_with_runtime(sr_string_t *err_ptr,
+                                    struct sr_surreal_rpc_t **surreal_ptr,
+                                    const char *endpoint,
+                                    struct sr_option_t options,
+                                    const struct sr_runtime_t *runtime);
+
+/**
+ * Execute an RPC request via raw CBOR bytes
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `key` must be a valid null-terminated UTF-8 string
- *
- * # Examples
+ * - `res_ptr` must be a valid pointer to receive the result
+ * - `ptr` must be a valid pointer to CBOR-encoded request data
+ * - `len` must be the length of the data at ptr
  *
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_unset(db, &err, "my_var") < 0) {
- *     printf("%s", err);
- *     return 1;
- * }
- * ```
+ * Free result with sr_free_byte_arr
  */
-int sr_unset(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *key);
+int sr_surreal_rpc_execute(const struct sr_surreal_rpc_t *self,
+                           sr_string_t *err_ptr,
+                           uint8_t **res_ptr,
+                           const uint8_t *ptr,
+                           int len);
 
 /**
- * Update records with new content
- *
- * Replaces the content of existing records with new data.
+ * Get a stream for receiving live query notifications
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * - `stream_ptr` must be a valid pointer to receive the stream
+ *
+ * Returns a stream that can be polled for notifications using sr_rpc_stream_next
+ */
+int sr_surreal_rpc_notifications(const struct sr_surreal_rpc_t *self,
+                                 sr_string_t *err_ptr,
+                                 struct sr_RpcStream **stream_ptr);
+
+/**
+ * Free an RPC context
+ */
+void sr_surreal_rpc_free(struct sr_surreal_rpc_t *ctx);
+
+/**
+ * Create a new runtime
+ *
+ * `options` may be null for the defaults.
+ * Returns SR_NONE and writes the runtime to `rt_ptr` on success.
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *updated;
- * sr_object_t *content = ...; // create content object
- * int len = sr_update(db, &err, &updated, "foo:bar", content);
- * if (len < 0) {
+ * sr_runtime_options_t opts = {0};
+ * opts.version = sr_SR_RUNTIME_OPTIONS_VERSION;
+ * opts.worker_threads = 4;
+ * opts.thread_name = "surreal-worker";
+ *
+ * sr_runtime_t *rt;
+ * if (sr_runtime_new(&err, &rt, &opts) < 0) {
  *     printf("%s", err);
  *     return 1;
  * }
- * sr_free_arr(updated, len);
+ *
+ * sr_connect_options_t copts = {0};
+ * copts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+ * copts.runtime = rt;
+ * sr_connect_with_options(&err, &db1, "mem://", &copts);
+ * sr_connect_with_options(&err, &db2, "mem://", &copts);
+ * sr_runtime_free(rt);
  * ```
  */
-int sr_update(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *resource,
-              const struct sr_object_t *content);
+int sr_runtime_new(sr_string_t *err_ptr,
+                   struct sr_runtime_t **rt_ptr,
+                   const struct sr_runtime_options_t *options);
 
 /**
- * Upsert (insert or update) records
+ * Release a runtime
  *
- * Creates records if they don't exist, or updates them if they do.
+ * The runtime shuts down once every connection using it has been disconnected.
+ */
+void sr_runtime_free(struct sr_runtime_t *rt);
+
+/**
+ * Validate a query and prepare it for execution on a connection
+ *
+ * Returns SR_NONE and writes the statement to `stmt_ptr`, or SR_ERROR with an error of kind
+ * SR_ERROR_KIND_PARSE if the query is not valid SurrealQL.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the statement
  * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result array
- * - `resource` must be a valid null-terminated UTF-8 string
- * - `content` must be a valid pointer to an Object
+ * - `stmt_ptr` must be a valid pointer to receive the statement
+ * - `query` must be a valid null-terminated UTF-8 string
  *
  * # Examples
  *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_value_t *upserted;
- * sr_object_t *content = ...; // create content object
- * int len = sr_upsert(db, &err, &upserted, "foo:bar", content);
- * if (len < 0) {
+ * sr_statement_t *stmt;
+ * if (sr_prepare(db, &err, &stmt, "CREATE event SET kind = $kind, value = $value") < 0) {
  *     printf("%s", err);
  *     return 1;
  * }
- * sr_free_arr(upserted, len);
+ * for (int i = 0; i < 1000; i++) {
+ *     sr_statement_bind_str(stmt, "kind", "tick");
+ *     sr_statement_bind_int(stmt, "value", i);
+ *
+ *     sr_arr_res_t *res;
+ *     int len = sr_statement_execute(stmt, &err, &res);
+ *     if (len >= 0) {
+ *         sr_free_arr_res_arr(res, len);
+ *     }
+ * }
+ * sr_statement_free(stmt);
  * ```
  */
-int sr_upsert(const struct sr_surreal_t *db,
-              sr_string_t *err_ptr,
-              struct sr_value_t **res_ptr,
-              const char *resource,
-              const struct sr_object_t *content);
+int sr_prepare(const struct sr_surreal_t *db,
+               sr_string_t *err_ptr,
+               struct sr_statement_t **stmt_ptr,
+               const char *query);
 
 /**
- * Select database
- *
- * Sets the database to use for subsequent operations.
- * NOTE: namespace must be selected first with sr_use_ns
- *
- * # Safety
+ * Execute a prepared statement with its bound parameters
  *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `db_name` must be a valid null-terminated UTF-8 string
+ * The query text is sent and parsed by the engine on every call, as with `sr_query`.
+ * Writes the results to *res_ptr like `sr_query` and returns the number of statements.
+ * Free the results with `sr_free_arr_res_arr`.
+ */
+int sr_statement_execute(const struct sr_statement_t *self,
+                         sr_string_t *err_ptr,
+                         struct sr_arr_res_t **res_ptr);
+
+/**
+ * Bind a value to a named parameter
  *
- * # Examples
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_use_db(db, &err, "test") < 0)
- * {
- *     printf("%s", err);
- *     return 1;
- * }
- * ```
+ * `name` may be given with or without the leading `$`. The value is copied.
+ */
+int sr_statement_bind(struct sr_statement_t *self,
+                      sr_string_t *err_ptr,
+                      const char *name,
+                      const struct sr_value_t *value);
+
+/**
+ * Bind a string to a named parameter
+ */
+int sr_statement_bind_str(struct sr_statement_t *self,
+                          sr_string_t *err_ptr,
+                          const char *name,
+                          const char *value);
+
+/**
+ * Bind an integer to a named parameter
+ */
+int sr_statement_bind_int(struct sr_statement_t *self,
+                          sr_string_t *err_ptr,
+                          const char *name,
+                          int64_t value);
+
+/**
+ * Bind a float to a named parameter
  */
-int sr_use_db(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *db_name);
+int sr_statement_bind_float(struct sr_statement_t *self,
+                            sr_string_t *err_ptr,
+                            const char *name,
+                            double value);
 
 /**
- * Select namespace
+ * Bind a boolean to a named parameter
+ */
+int sr_statement_bind_bool(struct sr_statement_t *self,
+                           sr_string_t *err_ptr,
+                           const char *name,
+                           bool value);
+
+/**
+ * Bind NULL to a named parameter
+ */
+int sr_statement_bind_null(struct sr_statement_t *self, sr_string_t *err_ptr, const char *name);
+
+/**
+ * Remove all bound parameters
+ */
+void sr_statement_clear_bindings(struct sr_statement_t *self);
+
+/**
+ * Free a prepared statement
+ */
+void sr_statement_free(struct sr_statement_t *stmt);
+
+/**
+ * Begin a transaction
  *
- * Sets the namespace to use for subsequent operations.
- * NOTE: database must be selected before use with sr_use_db
+ * Writes the transaction to *txn_ptr and returns SR_NONE.
  *
  * # Safety
  *
- * - `db` must be a valid pointer to a Surreal connection
+ * - `db` must be a valid pointer to a Surreal connection, which outlives the transaction
  * - `err_ptr` must be a valid pointer or null
- * - `ns_name` must be a valid null-terminated UTF-8 string
+ * - `txn_ptr` must be a valid pointer to receive the transaction
  *
  * # Examples
+ *
  * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * if (sr_use_ns(db, &err, "test") < 0)
- * {
+ * sr_transaction_t *txn;
+ * if (sr_transaction_begin(db, &err, &txn) < 0) {
  *     printf("%s", err);
  *     return 1;
  * }
+ * sr_arr_res_t *res;
+ * int len = sr_transaction_query(txn, &err, &res, "UPDATE account:a SET balance -= 10", NULL);
+ * if (len < 0) {
+ *     sr_transaction_rollback(txn, NULL);
+ *     return 1;
+ * }
+ * sr_free_arr_res_arr(res, len);
+ * if (sr_transaction_commit(txn, &err) < 0) {
+ *     printf("%s", err);
+ * }
  * ```
  */
-int sr_use_ns(const struct sr_surreal_t *db, sr_string_t *err_ptr, const char *ns_name);
+int sr_transaction_begin(const struct sr_surreal_t *db,
+                         sr_string_t *err_ptr,
+                         struct sr_transaction_t **txn_ptr);
 
 /**
- * Returns the database version
+ * Commit a transaction
  *
- * Retrieves the version string of the connected SurrealDB server.
- * NOTE: version is allocated in Surreal and must be freed with sr_free_string
- *
- * # Safety
- *
- * - `db` must be a valid pointer to a Surreal connection
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the version string
- *
- * # Examples
- * ```c
- * sr_surreal_t *db;
- * sr_string_t err;
- * sr_string_t ver;
+ * The transaction is freed whether or not the commit succeeds, and must not be used again.
+ * A commit which conflicts with another transaction fails with an error of kind
+ * SR_ERROR_KIND_CONFLICT, and can be retried by running the transaction again.
+ */
+int sr_transaction_commit(struct sr_transaction_t *txn, sr_string_t *err_ptr);
+
+/**
+ * Roll back a transaction, discarding its changes
  *
- * if (sr_version(db, &err, &ver) < 0)
- * {
- *     printf("%s", err);
- *     return 1;
- * }
- * printf("%s", ver);
- * sr_free_string(ver);
- * ```
+ * The transaction is freed whether or not the rollback succeeds, and must not be used again.
  */
-int sr_version(const struct sr_surreal_t *db, sr_string_t *err_ptr, sr_string_t *res_ptr);
+int sr_transaction_rollback(struct sr_transaction_t *txn, sr_string_t *err_ptr);
 
-int sr_surreal_rpc_new(sr_string_t *err_ptr,
-                       struct sr_surreal_rpc_t **surreal_ptr,
-                       const char *endpoint,
-                       struct sr_option_t options);
+/**
+ * Execute a SurrealQL query inside a transaction
+ *
+ * Behaves like `sr_query`.
+ */
+int sr_transaction_query(const struct sr_transaction_t *self,
+                         sr_string_t *err_ptr,
+                         struct sr_arr_res_t **res_ptr,
+                         const char *query,
+                         const struct sr_object_t *vars);
 
 /**
- * Execute an RPC request
+ * Select records inside a transaction
  *
- * # Safety
+ * Behaves like `sr_select`.
+ */
+int sr_transaction_select(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_value_t **res_ptr,
+                          const char *resource);
+
+/**
+ * Create a record inside a transaction
  *
- * - `err_ptr` must be a valid pointer or null
- * - `res_ptr` must be a valid pointer to receive the result
- * - `ptr` must be a valid pointer to CBOR-encoded request data
- * - `len` must be the length of the data at ptr
+ * Behaves like `sr_create`.
+ */
+int sr_transaction_create(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_object_t **res_ptr,
+                          const char *resource,
+                          const struct sr_object_t *content);
+
+/**
+ * Insert records inside a transaction
  *
- * Free result with sr_free_byte_arr
+ * Behaves like `sr_insert`.
  */
-int sr_surreal_rpc_execute(const struct sr_surreal_rpc_t *self,
-                           sr_string_t *err_ptr,
-                           uint8_t **res_ptr,
-                           const uint8_t *ptr,
-                           int len);
+int sr_transaction_insert(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_value_t **res_ptr,
+                          const char *resource,
+                          const struct sr_object_t *content);
 
 /**
- * Get a stream for receiving live query notifications
+ * Replace records inside a transaction
  *
- * # Safety
+ * Behaves like `sr_update`.
+ */
+int sr_transaction_update(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_value_t **res_ptr,
+                          const char *resource,
+                          const struct sr_object_t *content);
+
+/**
+ * Create or replace records inside a transaction
  *
- * - `err_ptr` must be a valid pointer or null
- * - `stream_ptr` must be a valid pointer to receive the stream
+ * Behaves like `sr_upsert`.
+ */
+int sr_transaction_upsert(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_value_t **res_ptr,
+                          const char *resource,
+                          const struct sr_object_t *content);
+
+/**
+ * Delete records inside a transaction
  *
- * Returns a stream that can be polled for notifications using sr_rpc_stream_next
+ * Behaves like `sr_delete`.
  */
-int sr_surreal_rpc_notifications(const struct sr_surreal_rpc_t *self,
-                                 sr_string_t *err_ptr,
-                                 struct sr_RpcStream **stream_ptr);
+int sr_transaction_delete(const struct sr_transaction_t *self,
+                          sr_string_t *err_ptr,
+                          struct sr_value_t **res_ptr,
+                          const char *resource);
 
 /**
- * Free an RPC context
+ * Run a unit of work in a transaction, retrying it when the commit conflicts
  *
- * # Safety
+ * Begins a transaction, passes it to `callback` and commits it if the callback succeeds.
+ * If the callback or the commit fails with an error of kind SR_ERROR_KIND_CONFLICT, the work is
+ * run again in a new transaction, up to `max_retries` times. Other failures roll the transaction
+ * back and are returned without retrying.
+ *
+ * Conflicts are detected from the errors the transaction records itself, not from `sr_last_error`:
+ * the callback failed with a conflict if it returns a negative value and the last operation it ran
+ * on `txn` failed with SR_ERROR_KIND_CONFLICT. That error is the one written to `err_ptr`, or a
+ * generic error naming the returned value if the last operation succeeded.
+ *
+ * Returns the value returned by the last run of the callback, or a negative error code.
+ * The last error of the calling thread is left as it was before the call if the run succeeds.
+ *
+ * # Examples
+ *
+ * ```c
+ * int transfer(sr_transaction_t *txn, void *userdata) {
+ *     sr_arr_res_t *res;
+ *     int len = sr_transaction_query(txn, NULL, &res,
+ *         "UPDATE account:a SET balance -= 10; UPDATE account:b SET balance += 10", NULL);
+ *     if (len < 0) return len;
+ *     sr_free_arr_res_arr(res, len);
+ *     return 0;
+ * }
  *
- * - `ctx` must be a valid pointer to a SurrealRpc, or null (no-op)
+ * if (sr_transaction_run(db, &err, transfer, NULL, 5) < 0) {
+ *     printf("%s", err);
+ * }
+ * ```
  */
-void sr_surreal_rpc_free(struct sr_surreal_rpc_t *ctx);
+int sr_transaction_run(const struct sr_surreal_t *db,
+                       sr_string_t *err_ptr,
+                       sr_transaction_callback_t callback,
+                       void *userdata,
+                       int max_retries);
 
 void sr_free_arr(struct sr_value_t *ptr, int len);
 
@@ -1482,6 +4096,52 @@ void sr_free_bytes(struct sr_bytes_t bytes);
 
 void sr_free_byte_arr(uint8_t *ptr, int len);
 
+/**
+ * Free the patch operations held by a diff notification
+ */
+void sr_free_diff_notification(struct sr_diff_notification_t notification);
+
+/**
+ * Apply patch operations to a value
+ *
+ * Keeps a local copy of a record in sync with the notifications of a `LIVE SELECT DIFF` query,
+ * starting from the empty value for a record which was not known yet.
+ * Either every operation is applied, or `value` is left unchanged and an error is returned.
+ * Returns SR_NONE on success.
+ *
+ * Operations are applied in order like JSON Patch (RFC 6902) operations, with one exception made for
+ * the diffs generated by SurrealDB: the elements removed from the end of an array are listed in
+ * ascending order, so consecutive removals from the same array are applied together, each index
+ * referring to the array before the first of them. Patches from other sources which remove several
+ * elements of an array in a row are therefore not applied as RFC 6902 specifies.
+ * Removing or replacing a value which does not exist, or adding past the end of an array, fails.
+ *
+ * # Safety
+ *
+ * - `err_ptr` must be a valid pointer or null
+ * - `value` must be a valid pointer to a Value
+ * - `ops` must point to `ops_len` operations, it may be null if `ops_len` is 0
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_value_t *cache = sr_value_none();
+ * sr_diff_notification_t not;
+ * while (sr_stream_next_diff(stream, &not) > 0)
+ * {
+ *     if (sr_value_apply_diff(&err, cache, not.ops, not.ops_len) < 0)
+ *     {
+ *         printf("%s", err);
+ *     }
+ *     sr_free_diff_notification(not);
+ * }
+ * ```
+ */
+int sr_value_apply_diff(sr_string_t *err_ptr,
+                        struct sr_value_t *value,
+                        const struct sr_diff_op_t *ops,
+                        int ops_len);
+
 void sr_print_notification(const struct sr_notification_t *notification);
 
 /**
@@ -1575,17 +4235,159 @@ int sr_object_keys(const struct sr_object_t *obj, char ***keys_ptr);
  */
 void sr_free_string_arr(char **arr, int len);
 
+/**
+ * Get the number of statements in a response
+ */
+int sr_response_count(const struct sr_response_t *self);
+
+/**
+ * Check whether a statement succeeded
+ *
+ * Returns false if the statement failed or `index` is out of range.
+ */
+bool sr_response_is_ok(const struct sr_response_t *self, int index);
+
+/**
+ * Get the value returned by a statement
+ *
+ * Returns null if the statement failed or `index` is out of range.
+ */
+const struct sr_value_t *sr_response_value(const struct sr_response_t *self, int index);
+
+/**
+ * Get the error of a failed statement
+ *
+ * Returns null if the statement succeeded or `index` is out of range.
+ */
+const struct sr_error_t *sr_response_error(const struct sr_response_t *self, int index);
+
+/**
+ * Get the execution time of a statement
+ *
+ * Returns a zero duration if the time is unknown or `index` is out of range.
+ */
+struct sr_duration_t sr_response_time(const struct sr_response_t *self, int index);
+
+/**
+ * Free a response and all values and errors it holds
+ */
+void sr_response_free(struct sr_response_t *resp);
+
+/**
+ * Free an error's message and details
+ */
+void sr_free_error(struct sr_error_t err);
+
+/**
+ * Returns the message of the last error raised on the calling thread
+ *
+ * Every `sr_*` call that fails records its error here, whether or not an `err_ptr` was passed.
+ * Successful calls do not clear it, use `sr_clear_error` before a call to detect new errors.
+ *
+ * Returns null if there is no error. The string is owned by the thread and stays valid until
+ * the next failing call or `sr_clear_error` on the same thread, it must not be freed.
+ *
+ * # Examples
+ *
+ * ```c
+ * sr_surreal_t *db;
+ * if (sr_connect(NULL, &db, "mem://") < 0) {
+ *     printf("error connecting to db: %s\n", sr_last_error());
+ *     return 1;
+ * }
+ * ```
+ */
+const char *sr_last_error(void);
+
+/**
+ * Returns the code of the last error raised on the calling thread
+ *
+ * SR_ERROR or SR_FATAL, or 0 if there is no error
+ */
+int sr_last_error_code(void);
+
+/**
+ * Returns the kind of the last error raised on the calling thread
+ */
+enum sr_error_kind_t sr_last_error_kind(void);
+
+/**
+ * Clear the last error of the calling thread
+ */
+void sr_clear_error(void);
+
 void sr_free_arr_res(struct sr_arr_res_t res);
 
 void sr_free_arr_res_arr(struct sr_arr_res_t *ptr, int len);
 
 /**
  * Blocks until next item is received on stream
- * will return 1 and write notification to notification_ptr is recieved
- * will return SR_NONE if the stream is closed
+ * will return 1 and write notification to notification_ptr if received
+ * will return SR_NONE if the stream is closed, unlike every other function reading a stream which
+ * returns SR_CLOSED, this is kept for compatibility
+ * will return SR_ERROR if the stream reported an error, which can be read with sr_last_error
+ *
+ * sr_stream_t *stream;
+ * if (sr_select_live(db, &err, &stream, "foo") < 0)
+ * {
+ *     printf("%s", err);
+ *     return 1;
+ * }
+ *
+ * sr_notification_t not ;
+ * if (sr_stream_next(stream, &not ) > 0)
+ * {
+ *     sr_print_notification(&not );
+ * }
+ * sr_stream_kill(stream);
  */
 int sr_stream_next(struct sr_stream_t *self, struct sr_notification_t *notification_ptr);
 
+/**
+ * Blocks until next item is received on stream, or `timeout_ms` milliseconds have passed
+ * will return SR_TIMEOUT if no notification was received in time, with the stream left usable
+ * will return SR_CLOSED if the stream is closed
+ * otherwise identical to sr_stream_next, a negative `timeout_ms` waits indefinitely
+ */
+int sr_stream_next_timeout(struct sr_stream_t *self,
+                           struct sr_notification_t *notification_ptr,
+                           int timeout_ms);
+
+/**
+ * Blocks until next item is received on the stream of a `LIVE SELECT DIFF` statement
+ * will return 1 and write the notification, with its changes as patch operations, to notification_ptr
+ * will return SR_CLOSED if the stream is closed
+ * will return SR_ERROR if the notification does not hold a diff, otherwise identical to sr_stream_next
+ *
+ * The notification must be freed with sr_free_diff_notification
+ */
+int sr_stream_next_diff(struct sr_stream_t *self,
+                        struct sr_diff_notification_t *notification_ptr);
+
+/**
+ * Blocks until next diff is received on stream, or `timeout_ms` milliseconds have passed
+ * will return SR_TIMEOUT if no notification was received in time, with the stream left usable
+ * otherwise identical to sr_stream_next_diff, a negative `timeout_ms` waits indefinitely
+ */
+int sr_stream_next_diff_timeout(struct sr_stream_t *self,
+                                struct sr_diff_notification_t *notification_ptr,
+                                int timeout_ms);
+
+/**
+ * Returns immediately with the next notification if one was already received
+ * will return 1 and write notification to notification_ptr if one was available
+ * will return SR_NONE if no notification is pending, and SR_CLOSED if the stream is closed
+ * will return SR_ERROR if the stream reported an error, which can be read with sr_last_error
+ */
+int sr_stream_try_next(struct sr_stream_t *self, struct sr_notification_t *notification_ptr);
+
+/**
+ * Returns immediately with the next diff if one was already received
+ * otherwise identical to sr_stream_try_next, see sr_stream_next_diff for the notification
+ */
+int sr_stream_try_next_diff(struct sr_stream_t *self,
+                            struct sr_diff_notification_t *notification_ptr);
+
 /**
  * Kill and free a stream
  *
@@ -1594,12 +4396,130 @@ int sr_stream_next(struct sr_stream_t *self, struct sr_notification_t *notificat
  */
 void sr_stream_kill(struct sr_stream_t *stream);
 
+/**
+ * Dispatch the notifications of a stream to a callback
+ *
+ * Takes ownership of the stream, which must not be used afterwards, and invokes `callback`
+ * from a runtime worker thread for each notification, so it must be thread safe and must not block.
+ * On success writes the subscription to *sub_ptr and returns SR_NONE, on failure the stream is
+ * killed and SR_ERROR is returned, with the error available from sr_last_error.
+ *
+ * sr_subscription_t *sub;
+ * if (sr_stream_subscribe(stream, &sub, on_notification, &state) < 0)
+ * {
+ *     return 1;
+ * }
+ * ...
+ * sr_stream_unsubscribe(sub);
+ */
+int sr_stream_subscribe(struct sr_stream_t *stream,
+                        struct sr_subscription_t **sub_ptr,
+                        sr_notification_callback_t callback,
+                        void *userdata);
+
+/**
+ * Stop dispatching notifications, kill the live query and free the subscription
+ *
+ * May be called from any thread, including from the callback itself. Once it returns the
+ * callback is not running and will not be invoked again.
+ */
+void sr_stream_unsubscribe(struct sr_subscription_t *sub);
+
+/**
+ * Create an empty stream set
+ */
+struct sr_stream_set_t *sr_stream_set_new(void);
+
+/**
+ * Add a stream to the set
+ *
+ * Takes ownership of the stream, which must not be used afterwards.
+ * Returns the index identifying the stream in the set, indexes are not reused.
+ *
+ * sr_stream_set_t *set = sr_stream_set_new();
+ * int orders = sr_stream_set_add(set, orders_stream);
+ * int users = sr_stream_set_add(set, users_stream);
+ */
+int sr_stream_set_add(struct sr_stream_set_t *self, struct sr_stream_t *stream);
+
+/**
+ * Remove a stream from the set and kill it
+ *
+ * Notifications of the stream which were not taken yet are discarded.
+ */
+void sr_stream_set_remove(struct sr_stream_set_t *self, int index);
+
+/**
+ * Waits until one of the streams of the set reports, or `timeout_ms` milliseconds have passed
+ * will return 1 and write the index of the stream to index_ptr and the notification to notification_ptr
+ * will return SR_CLOSED and write the index of the stream to index_ptr if that stream closed
+ * will return SR_ERROR and write the index of the stream to index_ptr if that stream reported an error,
+ * which can be read with sr_last_error
+ * will return SR_TIMEOUT if nothing was received in time, and SR_NONE if every stream was closed or removed
+ *
+ * A `timeout_ms` of 0 returns immediately and a negative one waits indefinitely.
+ *
+ * int index;
+ * sr_notification_t not;
+ * while (sr_stream_set_next(set, &index, &not, -1) > 0)
+ * {
+ *     printf("stream %d: ", index);
+ *     sr_print_notification(&not);
+ * }
+ */
+int sr_stream_set_next(struct sr_stream_set_t *self,
+                       int *index_ptr,
+                       struct sr_notification_t *notification_ptr,
+                       int timeout_ms);
+
+/**
+ * Returns a descriptor which becomes readable when a stream of the set reports
+ *
+ * The descriptor is owned by the set, must not be closed and is only supported on unix platforms.
+ * Drain it before taking the pending notifications with a `timeout_ms` of 0, as with `sr_surreal_fd`.
+ *
+ * int fd = sr_stream_set_fd(set, &err);
+ * struct pollfd pfd = {.fd = fd, .events = POLLIN};
+ * poll(&pfd, 1, -1);
+ *
+ * char buf[64];
+ * while (read(fd, buf, sizeof(buf)) > 0) {}
+ * while (sr_stream_set_next(set, &index, &not, 0) > 0) {}
+ */
+int sr_stream_set_fd(const struct sr_stream_set_t *self,
+                     sr_string_t *err_ptr);
+
+/**
+ * Kill every stream of the set and free it
+ */
+void sr_stream_set_free(struct sr_stream_set_t *set);
+
 /**
  * Get the next notification from the stream
- * Returns the length of the CBOR-encoded notification, or SR_CLOSED if the stream is closed
+ *
+ * Returns the length of the CBOR-encoded notification, or SR_CLOSED if the
+ * channel is closed. The CBOR-encoded bytes are written to *res_ptr.
+ *
+ * Free the result with sr_free_byte_arr.
  */
 int sr_rpc_stream_next(struct sr_RpcStream *self, uint8_t **res_ptr);
 
+/**
+ * Get the next notification from the stream, if one was already received
+ *
+ * Returns SR_NONE without waiting if no notification is pending, otherwise identical to
+ * sr_rpc_stream_next.
+ */
+int sr_rpc_stream_try_next(struct sr_RpcStream *self, uint8_t **res_ptr);
+
+/**
+ * Get the next notification from the stream, waiting at most `timeout_ms` milliseconds
+ *
+ * Returns SR_TIMEOUT if no notification was received in time, with the stream left usable,
+ * otherwise identical to sr_rpc_stream_next. A negative `timeout_ms` waits indefinitely.
+ */
+int sr_rpc_stream_next_timeout(struct sr_RpcStream *self, uint8_t **res_ptr, int timeout_ms);
+
 /**
  * Free an RpcStream
  */
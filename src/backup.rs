@@ -0,0 +1,847 @@
+//! Export and import of database dumps without the filesystem
+//!
+//! `sr_export` and `sr_import` read and write files on the machine running the engine. The functions
+//! here stream an export to a callback or into memory and import a dump held in memory, so backups
+//! can be sent to object storage or kept in memory for tests.
+//!
+//! Exports can be narrowed with `sr_export_options_t`, for example to schema-only snapshots or to
+//! the records of a few tables.
+//!
+//! Both directions can report their progress to a callback, which can abort them. Imports through
+//! this module are executed by the client in chunks of statements, so progress can be reported and
+//! the import interrupted between chunks.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    fs::File,
+    io::{BufRead, BufReader, Write},
+    mem,
+};
+
+use futures::StreamExt;
+use surrealdb::{engine::any::Any, Surreal as sdbSurreal};
+
+use crate::{
+    array::{ArrayGen, MakeArray},
+    ops, parse,
+    result::{write_error, ErrorKind, SurrealError},
+    string::string_t,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// Number of bytes between two calls of a progress callback
+const PROGRESS_INTERVAL: i64 = 1 << 20;
+
+/// Number of bytes of statements executed per query by an import when no chunk size is given
+const IMPORT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Callback receiving the progress of an import or export
+///
+/// `bytes` is the number of bytes of the dump processed so far, `statements` the number of
+/// statements executed or exported. Return 0 to continue, anything else aborts the operation, which
+/// then fails with SR_CANCELLED.
+pub type ProgressCallback = Option<extern "C" fn(userdata: *mut c_void, bytes: i64, statements: i64) -> c_int>;
+
+/// Calls a progress callback about once every PROGRESS_INTERVAL bytes, or when forced
+struct Progress {
+    callback: ProgressCallback,
+    userdata: *mut c_void,
+    bytes: i64,
+    statements: i64,
+    reported: i64,
+}
+
+impl Progress {
+    fn new(callback: ProgressCallback, userdata: *mut c_void) -> Self {
+        Progress {
+            callback,
+            userdata,
+            bytes: 0,
+            statements: 0,
+            reported: 0,
+        }
+    }
+
+    /// Call the callback if enough bytes were processed since the last call, or if `force` is set
+    fn report(&mut self, force: bool) -> Result<(), SurrealError> {
+        let Some(callback) = self.callback else {
+            return Ok(());
+        };
+        if !force && self.bytes - self.reported < PROGRESS_INTERVAL {
+            return Ok(());
+        }
+        self.reported = self.bytes;
+        match callback(self.userdata, self.bytes, self.statements) {
+            0 => Ok(()),
+            _ => Err(SurrealError::cancelled("aborted by the progress callback")),
+        }
+    }
+}
+
+/// The current version of `sr_export_options_t`
+///
+/// Callers must set `version` to this value so that fields added in later releases can be detected.
+pub const SR_EXPORT_OPTIONS_VERSION: u32 = 1;
+
+/// Options narrowing what an export contains
+///
+/// Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+/// exports everything, like passing no options at all.
+#[repr(C)]
+pub struct ExportOptions {
+    /// Must be set to SR_EXPORT_OPTIONS_VERSION
+    pub version: u32,
+    /// Names of the tables to export, null to export every table
+    pub tables: *const *const c_char,
+    /// Number of entries in `tables`
+    pub tables_len: c_int,
+    /// Leave out the database users
+    pub no_users: bool,
+    /// Leave out the access methods
+    pub no_accesses: bool,
+    /// Leave out the database parameters
+    pub no_params: bool,
+    /// Leave out the functions
+    pub no_functions: bool,
+    /// Leave out the analyzers
+    pub no_analyzers: bool,
+    /// Leave out every definition, exporting only the records of the tables
+    ///
+    /// Fails with SR_ERROR_KIND_CONFIGURATION if the export of the server is not in the expected format.
+    pub no_schema: bool,
+    /// Leave out the records, exporting only the definitions
+    pub no_records: bool,
+    /// Export every version of the records, only supported by versioned storage engines
+    pub versions: bool,
+    /// Called as the export is written, may be null
+    pub progress: ProgressCallback,
+    /// Passed to the progress callback
+    pub userdata: *mut c_void,
+}
+
+/// What to export, read from `sr_export_options_t`
+struct ExportConfig {
+    tables: Option<Vec<String>>,
+    users: bool,
+    accesses: bool,
+    params: bool,
+    functions: bool,
+    analyzers: bool,
+    schema: bool,
+    records: bool,
+    versions: bool,
+    progress: Progress,
+}
+
+impl ExportConfig {
+    fn new(options: *const ExportOptions) -> Result<Self, SurrealError> {
+        let mut cfg = ExportConfig {
+            tables: None,
+            users: true,
+            accesses: true,
+            params: true,
+            functions: true,
+            analyzers: true,
+            schema: true,
+            records: true,
+            versions: false,
+            progress: Progress::new(None, std::ptr::null_mut()),
+        };
+        if options.is_null() {
+            return Ok(cfg);
+        }
+        let opts = unsafe { &*options };
+        if opts.version == 0 || opts.version > SR_EXPORT_OPTIONS_VERSION {
+            return Err(SurrealError::invalid_argument(format!(
+                "unsupported export options version: {}",
+                opts.version
+            )));
+        }
+        if opts.no_schema && opts.no_records {
+            return Err(SurrealError::invalid_argument("no_schema and no_records leave nothing to export"));
+        }
+
+        if !opts.tables.is_null() {
+            let len = usize::try_from(opts.tables_len)
+                .map_err(|_| SurrealError::invalid_argument("tables_len is negative"))?;
+            let mut tables = Vec::with_capacity(len);
+            for table in unsafe { std::slice::from_raw_parts(opts.tables, len) } {
+                if table.is_null() {
+                    return Err(SurrealError::invalid_argument("table name is null"));
+                }
+                tables.push(unsafe { CStr::from_ptr(*table) }.to_str()?.to_owned());
+            }
+            cfg.tables = Some(tables);
+        }
+        // without a schema only the table data sections are kept, so the rest need not be exported
+        cfg.users = !opts.no_users && !opts.no_schema;
+        cfg.accesses = !opts.no_accesses && !opts.no_schema;
+        cfg.params = !opts.no_params && !opts.no_schema;
+        cfg.functions = !opts.no_functions && !opts.no_schema;
+        cfg.analyzers = !opts.no_analyzers && !opts.no_schema;
+        cfg.schema = !opts.no_schema;
+        cfg.records = !opts.no_records;
+        cfg.versions = opts.versions;
+        cfg.progress = Progress::new(opts.progress, opts.userdata);
+        Ok(cfg)
+    }
+}
+
+/// Stream an export to `write` chunk by chunk
+async fn export(
+    db: &sdbSurreal<Any>,
+    cfg: ExportConfig,
+    mut write: impl FnMut(&[u8]) -> Result<(), SurrealError>,
+) -> Result<(), SurrealError> {
+    let export = db
+        .export(())
+        .with_config()
+        .users(cfg.users)
+        .accesses(cfg.accesses)
+        .params(cfg.params)
+        .functions(cfg.functions)
+        .analyzers(cfg.analyzers)
+        .records(cfg.records)
+        .versions(cfg.versions);
+    let mut backup = match cfg.tables {
+        Some(tables) => export.tables(tables).await?,
+        None => export.await?,
+    };
+
+    let mut progress = cfg.progress;
+    let mut write = |chunk: &[u8]| {
+        write(chunk)?;
+        progress.bytes += chunk.len() as i64;
+        progress.statements += chunk
+            .split(|b| *b == b'\n')
+            .filter(|line| line.trim_ascii_end().ends_with(b";"))
+            .count() as i64;
+        progress.report(false)
+    };
+
+    let mut filter = match cfg.schema {
+        true => None,
+        false => Some(SchemaFilter::default()),
+    };
+    while let Some(chunk) = backup.next().await {
+        let chunk = chunk?;
+        match filter.as_mut() {
+            Some(filter) => filter.push(&chunk, &mut write)?,
+            None => write(&chunk)?,
+        }
+    }
+    if let Some(filter) = filter {
+        filter.finish(&mut write)?;
+    }
+    progress.report(true)
+}
+
+/// Removes the definitions from an export, keeping its options and table data
+///
+/// An export is made of sections, each starting with a three line header comment such as
+/// `-- TABLE: user` or `-- TABLE DATA: user`. Comments never span lines, so the headers can be
+/// found line by line however the export is split into chunks.
+///
+/// The SDK has no option to export records without their table definitions, so the sections are
+/// read from the text of the export. Every export starts with the `-- OPTION` section, an export
+/// which does not is rejected before anything is written, rather than exported with its definitions.
+#[derive(Default)]
+struct SchemaFilter {
+    /// Incomplete last line of the chunks seen so far
+    line: Vec<u8>,
+    /// Opening line of a section header which has not been decided yet
+    opening: Vec<u8>,
+    /// Position in a section header, 0 outside of one
+    header: u8,
+    /// Whether the current section is dropped
+    drop: bool,
+    /// Whether the header of the first section was recognised
+    recognised: bool,
+}
+
+impl SchemaFilter {
+    const SEPARATOR: &'static [u8] = b"-- ------------------------------";
+
+    fn push(
+        &mut self,
+        chunk: &[u8],
+        write: &mut impl FnMut(&[u8]) -> Result<(), SurrealError>,
+    ) -> Result<(), SurrealError> {
+        let mut rest = chunk;
+        while let Some(end) = rest.iter().position(|b| *b == b'\n') {
+            let (line, tail) = rest.split_at(end + 1);
+            rest = tail;
+            match self.line.is_empty() {
+                true => self.filter(line, write)?,
+                false => {
+                    self.line.extend_from_slice(line);
+                    let line = mem::take(&mut self.line);
+                    self.filter(&line, write)?;
+                }
+            }
+        }
+        self.line.extend_from_slice(rest);
+        Ok(())
+    }
+
+    fn finish(mut self, write: &mut impl FnMut(&[u8]) -> Result<(), SurrealError>) -> Result<(), SurrealError> {
+        let line = mem::take(&mut self.line);
+        match line.is_empty() {
+            true => Ok(()),
+            false => self.filter(&line, write),
+        }
+    }
+
+    fn filter(
+        &mut self,
+        line: &[u8],
+        write: &mut impl FnMut(&[u8]) -> Result<(), SurrealError>,
+    ) -> Result<(), SurrealError> {
+        let text = line.trim_ascii_end();
+        if !self.recognised {
+            match (self.header, text) {
+                (0, Self::SEPARATOR) => {}
+                (1, b"-- OPTION") => self.recognised = true,
+                _ => {
+                    return Err(SurrealError::new(
+                        ErrorKind::SR_ERROR_KIND_CONFIGURATION,
+                        "no_schema is not supported by the export format of this server",
+                    ))
+                }
+            }
+        }
+        match self.header {
+            0 if text == Self::SEPARATOR => {
+                self.opening = line.to_vec();
+                self.header = 1;
+                Ok(())
+            }
+            1 => {
+                self.header = 2;
+                self.drop = !(text == b"-- OPTION" || text.starts_with(b"-- TABLE DATA: "));
+                match self.drop {
+                    true => Ok(()),
+                    false => {
+                        write(&mem::take(&mut self.opening))?;
+                        write(line)
+                    }
+                }
+            }
+            _ => {
+                if self.header == 2 {
+                    self.header = 0;
+                }
+                match self.drop {
+                    true => Ok(()),
+                    false => write(line),
+                }
+            }
+        }
+    }
+}
+
+/// Callback receiving a chunk of an export
+///
+/// `data` is only valid during the call. Return 0 to continue, anything else aborts the export.
+pub type WriteCallback = Option<extern "C" fn(userdata: *mut c_void, data: *const u8, len: c_int) -> c_int>;
+
+/// Export the current namespace and database to a file, narrowed by options
+///
+/// Identical to `sr_export` apart from the extra `options`, which may be null to export everything.
+/// The file is written by the calling process, also for remote connections.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+/// - `file_path` must be a valid null-terminated UTF-8 string
+/// - `options` must be a valid pointer or null
+///
+/// # Examples
+///
+/// ```c
+/// // schema-only snapshot for a migration
+/// sr_export_options_t opts = {0};
+/// opts.version = sr_SR_EXPORT_OPTIONS_VERSION;
+/// opts.no_records = true;
+/// if (sr_export_with_options(db, &err, "schema.surql", &opts) < 0) {
+///     printf("Export failed: %s", err);
+///     return 1;
+/// }
+/// ```
+#[export_name = "sr_export_with_options"]
+pub unsafe extern "C" fn export_with_options(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    file_path: *const c_char,
+    options: *const ExportOptions,
+) -> c_int {
+    if file_path.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("file_path is null"));
+        return SR_ERROR;
+    }
+    let cfg = match ExportConfig::new(options) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    with_surreal_async(db, err_ptr, |surreal| async move {
+        let file_path = unsafe { CStr::from_ptr(file_path) }.to_str()?;
+        let mut file = File::create(file_path)
+            .map_err(|e| SurrealError::invalid_argument(format!("failed to create `{file_path}`: {e}")))?;
+        export(&surreal.db(), cfg, |chunk| {
+            file.write_all(chunk)
+                .map_err(|e| SurrealError::from_msg(format!("failed to write `{file_path}`: {e}")))
+        })
+        .await?;
+        Ok(SR_NONE)
+    })
+}
+
+/// Export the current namespace and database to a callback
+///
+/// The dump is produced as a stream of SurrealQL chunks, each passed to `write_cb` on the calling
+/// thread as soon as it is available, so the export is never held in memory as a whole.
+/// `options` may be null to export everything.
+/// Returns SR_NONE once the export is complete, or SR_ERROR if it failed or was aborted by the
+/// callback.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+///
+/// # Examples
+///
+/// ```c
+/// int write_chunk(void *userdata, const uint8_t *data, int len) {
+///     return fwrite(data, 1, len, (FILE *)userdata) == (size_t)len ? 0 : 1;
+/// }
+///
+/// FILE *out = popen("gzip > backup.surql.gz", "w");
+/// if (sr_export_to_callback(db, &err, write_chunk, out, NULL) < 0) {
+///     printf("Export failed: %s", err);
+/// }
+/// pclose(out);
+/// ```
+#[export_name = "sr_export_to_callback"]
+pub extern "C" fn export_to_callback(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    write_cb: WriteCallback,
+    userdata: *mut c_void,
+    options: *const ExportOptions,
+) -> c_int {
+    let Some(write_cb) = write_cb else {
+        write_error(err_ptr, SurrealError::invalid_argument("write_cb is null"));
+        return SR_ERROR;
+    };
+    let cfg = match ExportConfig::new(options) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    with_surreal_async(db, err_ptr, |surreal| async move {
+        export(&surreal.db(), cfg, |chunk| {
+            for part in chunk.chunks(c_int::MAX as usize) {
+                if write_cb(userdata, part.as_ptr(), part.len() as c_int) != 0 {
+                    return Err(SurrealError::from_msg("export aborted by the write callback"));
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(SR_NONE)
+    })
+}
+
+/// Export the current namespace and database into memory
+///
+/// `options` may be null to export everything.
+/// Returns the length of the dump written to *res_ptr, which must be freed with `sr_free_byte_arr`.
+/// Dumps larger than 2 GiB can only be exported with `sr_export_to_callback`.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+/// - `res_ptr` must be a valid pointer to receive the dump
+/// - `options` must be a valid pointer or null
+///
+/// # Examples
+///
+/// ```c
+/// uint8_t *dump;
+/// int len = sr_export_to_buffer(db, &err, &dump, NULL);
+/// if (len < 0) {
+///     printf("Export failed: %s", err);
+///     return 1;
+/// }
+/// fwrite(dump, 1, len, stdout);
+/// sr_free_byte_arr(dump, len);
+/// ```
+#[export_name = "sr_export_to_buffer"]
+pub unsafe extern "C" fn export_to_buffer(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    res_ptr: *mut *mut u8,
+    options: *const ExportOptions,
+) -> c_int {
+    if res_ptr.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+        return SR_ERROR;
+    }
+    let cfg = match ExportConfig::new(options) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    with_surreal_async(db, err_ptr, |surreal| async move {
+        let mut dump = Vec::new();
+        export(&surreal.db(), cfg, |chunk| {
+            dump.extend_from_slice(chunk);
+            match dump.len() > c_int::MAX as usize {
+                true => Err(SurrealError::from_msg("export is too large for a buffer, use sr_export_to_callback")),
+                false => Ok(()),
+            }
+        })
+        .await?;
+
+        let ArrayGen { ptr, len } = dump.make_array();
+        unsafe { res_ptr.write(ptr) }
+        Ok(len)
+    })
+}
+
+/// The current version of `sr_import_options_t`
+///
+/// Callers must set `version` to this value so that fields added in later releases can be detected.
+pub const SR_IMPORT_OPTIONS_VERSION: u32 = 1;
+
+/// Options of an import
+///
+/// Zero or null fields are treated as unset.
+#[repr(C)]
+pub struct ImportOptions {
+    /// Must be set to SR_IMPORT_OPTIONS_VERSION
+    pub version: u32,
+    /// Number of bytes of statements executed per query, zero for 1 MiB
+    ///
+    /// A statement which does not parse once it is larger than this fails the import with a syntax error.
+    pub chunk_size: c_int,
+    /// Called after every chunk, may be null
+    pub progress: ProgressCallback,
+    /// Passed to the progress callback
+    pub userdata: *mut c_void,
+}
+
+/// Executes a dump in chunks of whole statements
+///
+/// Every chunk is a separate query with its own timeout. OPTION statements only apply to the query
+/// they are part of, so they are repeated at the start of every chunk, and a transaction is never
+/// split across chunks.
+struct Importer<'a> {
+    db: &'a Surreal,
+    err_ptr: *mut string_t,
+    progress: Progress,
+    chunk_size: usize,
+    /// OPTION statements read so far
+    options: String,
+    /// Complete statements which have not been executed yet
+    chunk: String,
+    /// Number of statements in `chunk`
+    pending: i64,
+    /// Lines of the statement being read
+    statement: String,
+    /// Whether `chunk` ends inside a transaction
+    in_transaction: bool,
+}
+
+impl<'a> Importer<'a> {
+    fn new(db: &'a Surreal, err_ptr: *mut string_t, options: *const ImportOptions) -> Result<Self, SurrealError> {
+        let mut progress = Progress::new(None, std::ptr::null_mut());
+        let mut chunk_size = IMPORT_CHUNK_SIZE;
+        if !options.is_null() {
+            let opts = unsafe { &*options };
+            if opts.version == 0 || opts.version > SR_IMPORT_OPTIONS_VERSION {
+                return Err(SurrealError::invalid_argument(format!(
+                    "unsupported import options version: {}",
+                    opts.version
+                )));
+            }
+            progress = Progress::new(opts.progress, opts.userdata);
+            if let Ok(size @ 1..) = usize::try_from(opts.chunk_size) {
+                chunk_size = size;
+            }
+        }
+        Ok(Importer {
+            db,
+            err_ptr,
+            progress,
+            chunk_size,
+            options: String::new(),
+            chunk: String::new(),
+            pending: 0,
+            statement: String::new(),
+            in_transaction: false,
+        })
+    }
+
+    /// Add a line of the dump, executing the current chunk once it is large enough
+    ///
+    /// A statement is complete once it ends a line with `;` and parses on its own. A statement which
+    /// still does not parse once it is larger than a chunk is rejected with its syntax error, so an
+    /// invalid dump is not read into memory and parsed again on every line until its end.
+    fn line(&mut self, line: &str) -> Result<(), c_int> {
+        self.progress.bytes += line.len() as i64;
+        self.statement.push_str(line);
+        if !line.trim_end().ends_with(';') {
+            return Ok(());
+        }
+        if u32::try_from(self.statement.len()).is_err() {
+            write_error(self.err_ptr, SurrealError::invalid_argument("statement is too large"));
+            return Err(SR_ERROR);
+        }
+        let parsed = match parse::parse(&self.statement) {
+            Ok(parsed) => parsed,
+            Err(e) if self.statement.len() > self.chunk_size => {
+                let msg = format!("Parse error: {}", e.render_on(&self.statement));
+                write_error(self.err_ptr, SurrealError::new(ErrorKind::SR_ERROR_KIND_PARSE, msg));
+                return Err(SR_ERROR);
+            }
+            Err(_) => return Ok(()),
+        };
+
+        let statement = mem::take(&mut self.statement);
+        match keyword(&statement).as_str() {
+            "OPTION" => self.options.push_str(&statement),
+            "BEGIN" => self.in_transaction = true,
+            "COMMIT" | "CANCEL" => self.in_transaction = false,
+            _ => {}
+        }
+        self.chunk.push_str(&statement);
+        self.pending += parsed.statements as i64;
+
+        match self.chunk.len() >= self.chunk_size && !self.in_transaction {
+            true => self.flush(),
+            false => Ok(()),
+        }
+    }
+
+    /// Execute the current chunk
+    fn flush(&mut self) -> Result<(), c_int> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = mem::take(&mut self.chunk);
+        // the chunk starts with the OPTION statements if they were read in it
+        let query = match chunk.starts_with(&self.options) {
+            true => chunk,
+            false => format!("{}{chunk}", self.options),
+        };
+        let res = with_surreal_async(self.db, self.err_ptr, |surreal| async {
+            for res in ops::query(&surreal.db(), &query, Default::default()).await? {
+                res?;
+            }
+            Ok(SR_NONE)
+        });
+        if res < 0 {
+            return Err(res);
+        }
+
+        self.progress.statements += mem::take(&mut self.pending);
+        self.progress.report(true).map_err(|e| {
+            let code = e.code;
+            write_error(self.err_ptr, e);
+            code
+        })
+    }
+
+    /// Execute the rest of the dump
+    fn finish(mut self) -> c_int {
+        // a trailing statement without `;` is left to the engine to accept or reject
+        if !self.statement.trim().is_empty() {
+            let statement = mem::take(&mut self.statement);
+            self.chunk.push_str(&statement);
+            self.pending += 1;
+        }
+        // an empty chunk is not executed, so the final progress is reported here instead
+        let res = match self.chunk.is_empty() {
+            true => self.progress.report(true).map_err(|e| {
+                let code = e.code;
+                write_error(self.err_ptr, e);
+                code
+            }),
+            false => self.flush(),
+        };
+        match res {
+            Ok(()) => SR_NONE,
+            Err(code) => code,
+        }
+    }
+}
+
+/// The first keyword of a statement, skipping comments
+fn keyword(statement: &str) -> String {
+    statement
+        .lines()
+        .map(str::trim_start)
+        .find(|line| !line.is_empty() && !line.starts_with("--") && !line.starts_with("//") && !line.starts_with('#'))
+        .and_then(|line| line.split(|c: char| !c.is_ascii_alphabetic()).next())
+        .unwrap_or_default()
+        .to_ascii_uppercase()
+}
+
+/// Import a dump from a file into the current namespace and database, with options
+///
+/// Unlike `sr_import`, the file is read by the calling process, also for remote connections, and
+/// executed in chunks of whole statements, 1 MiB by default. `options` may be null.
+/// The first statement which fails fails the import; the chunks executed before it are kept.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+/// - `file_path` must be a valid null-terminated UTF-8 string
+/// - `options` must be a valid pointer or null
+///
+/// # Examples
+///
+/// ```c
+/// int show_progress(void *userdata, int64_t bytes, int64_t statements) {
+///     printf("\r%lld / %lld bytes, %lld statements", bytes, *(int64_t *)userdata, statements);
+///     return cancel_requested;
+/// }
+///
+/// sr_import_options_t opts = {0};
+/// opts.version = sr_SR_IMPORT_OPTIONS_VERSION;
+/// opts.progress = show_progress;
+/// opts.userdata = &file_size;
+/// if (sr_import_with_options(db, &err, "backup.surql", &opts) < 0) {
+///     printf("Import failed: %s", err);
+///     return 1;
+/// }
+/// ```
+#[export_name = "sr_import_with_options"]
+pub unsafe extern "C" fn import_with_options(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    file_path: *const c_char,
+    options: *const ImportOptions,
+) -> c_int {
+    if file_path.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("file_path is null"));
+        return SR_ERROR;
+    }
+    let mut importer = match Importer::new(db, err_ptr, options) {
+        Ok(importer) => importer,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    let file = unsafe { CStr::from_ptr(file_path) }
+        .to_str()
+        .map_err(SurrealError::from)
+        .and_then(|path| {
+            File::open(path).map_err(|e| SurrealError::invalid_argument(format!("failed to open `{path}`: {e}")))
+        });
+    let mut reader = match file {
+        Ok(file) => BufReader::new(file),
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return importer.finish(),
+            Ok(_) => {}
+            Err(e) => {
+                write_error(err_ptr, SurrealError::from_msg(format!("failed to read the dump: {e}")));
+                return SR_ERROR;
+            }
+        }
+        let text = match std::str::from_utf8(&line) {
+            Ok(text) => text,
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+        if let Err(code) = importer.line(text) {
+            return code;
+        }
+    }
+}
+
+/// Import a dump held in memory into the current namespace and database
+///
+/// `data` holds `len` bytes of SurrealQL, as produced by the export functions. It is executed like
+/// `sr_import_with_options` executes a file, and `options` may be null.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+/// - `data` must point to at least `len` readable bytes
+/// - `options` must be a valid pointer or null
+///
+/// # Examples
+///
+/// ```c
+/// if (sr_import_from_buffer(db, &err, dump, len, NULL) < 0) {
+///     printf("Import failed: %s", err);
+///     return 1;
+/// }
+/// ```
+#[export_name = "sr_import_from_buffer"]
+pub unsafe extern "C" fn import_from_buffer(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    data: *const u8,
+    len: c_int,
+    options: *const ImportOptions,
+) -> c_int {
+    let data = match (data.is_null(), usize::try_from(len)) {
+        (_, Ok(0)) => &[][..],
+        (false, Ok(len)) => unsafe { std::slice::from_raw_parts(data, len) },
+        (true, _) => {
+            write_error(err_ptr, SurrealError::invalid_argument("data is null"));
+            return SR_ERROR;
+        }
+        (false, Err(_)) => {
+            write_error(err_ptr, SurrealError::invalid_argument("len is negative"));
+            return SR_ERROR;
+        }
+    };
+    let dump = match std::str::from_utf8(data) {
+        Ok(dump) => dump,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    let mut importer = match Importer::new(db, err_ptr, options) {
+        Ok(importer) => importer,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    for line in dump.split_inclusive('\n') {
+        if let Err(code) = importer.line(line) {
+            return code;
+        }
+    }
+    importer.finish()
+}
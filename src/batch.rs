@@ -0,0 +1,380 @@
+//! Batches of operations sent in a single round-trip
+//!
+//! A `sr_batch_t` collects CRUD operations and compiles them into one multi-statement query.
+//! Contents and arguments are bound as parameters. Resources are read the same way as by the direct
+//! calls, so a batched operation affects the same records: the resources of create and relate
+//! operations are interpolated into the query text like `sr_create` and `sr_relate` do, and the others
+//! are bound. With `atomic` set the statements are wrapped in a transaction, so either every operation
+//! is applied or none are.
+
+use std::ffi::{c_char, c_int, CStr};
+
+use surrealdb::{
+    opt::Resource,
+    types::{Object as sdbObject, SurrealValue, Table, Value as sdbValue},
+};
+
+use crate::{
+    array::{Array, ArrayGen, MakeArray},
+    object::Object,
+    ops, parse_resource,
+    result::{write_error, ArrayResult, SurrealError},
+    string::string_t,
+    value::Value,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// A list of operations to execute in one query
+///
+/// Operations are executed in the order they were added. A batch can be executed any number of
+/// times and must be freed with `sr_batch_free`.
+///
+/// # Examples
+///
+/// ```c
+/// sr_batch_t *batch = sr_batch_new();
+/// sr_batch_create(batch, &err, "person:tobie", &tobie);
+/// sr_batch_create(batch, &err, "person:jaime", &jaime);
+/// sr_batch_relate(batch, &err, "person:tobie", "knows", "person:jaime", NULL);
+///
+/// sr_arr_res_t *res;
+/// int len = sr_batch_execute(db, &err, &res, batch, true);
+/// if (len < 0) {
+///     printf("%s", err);
+///     return 1;
+/// }
+/// for (int i = 0; i < len; i++) {
+///     if (res[i].err.code != 0) {
+///         printf("operation %d failed: %s\n", i, res[i].err.msg);
+///     }
+/// }
+/// sr_free_arr_res_arr(res, len);
+/// sr_batch_free(batch);
+/// ```
+#[derive(Default)]
+pub struct Batch {
+    statements: Vec<String>,
+    vars: sdbObject,
+}
+
+/// Read a required string argument
+fn arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, SurrealError> {
+    if ptr.is_null() {
+        return Err(SurrealError::invalid_argument(format!("{name} is null")));
+    }
+    Ok(unsafe { CStr::from_ptr(ptr) }.to_str()?)
+}
+
+/// Read a required object argument
+fn object_arg(ptr: *const Object, name: &str) -> Result<sdbObject, SurrealError> {
+    match unsafe { ptr.as_ref() } {
+        Some(obj) => Ok(obj.clone().into()),
+        None => Err(SurrealError::invalid_argument(format!("{name} is null"))),
+    }
+}
+
+/// Read a required value argument
+fn value_arg(ptr: *const Value, name: &str) -> Result<sdbValue, SurrealError> {
+    match unsafe { ptr.as_ref() } {
+        Some(val) => Ok(val.clone().into()),
+        None => Err(SurrealError::invalid_argument(format!("{name} is null"))),
+    }
+}
+
+/// Convert a resource to a value which can be bound, following the rules of the CRUD functions
+fn resource(s: &str) -> sdbValue {
+    match parse_resource(s) {
+        Resource::RecordId(id) => sdbValue::RecordId(id),
+        _ => sdbValue::Table(Table::new(s)),
+    }
+}
+
+impl Batch {
+    /// Create an empty batch
+    #[export_name = "sr_batch_new"]
+    pub extern "C" fn new() -> *mut Batch {
+        Box::into_raw(Box::default())
+    }
+
+    /// Get the number of operations in a batch
+    #[export_name = "sr_batch_len"]
+    pub extern "C" fn count(&self) -> c_int {
+        self.statements.len() as c_int
+    }
+
+    /// Free a batch
+    ///
+    /// # Safety
+    ///
+    /// - `batch` must be a pointer returned by `sr_batch_new` which was not freed yet, or null
+    #[export_name = "sr_batch_free"]
+    pub unsafe extern "C" fn free(batch: *mut Batch) {
+        if !batch.is_null() {
+            let _ = unsafe { Box::from_raw(batch) };
+        }
+    }
+
+    /// Add an operation, returning its index or SR_ERROR
+    ///
+    /// `build` is given the index of the operation, to name its parameters, and returns the statement.
+    fn push<F>(&mut self, err_ptr: *mut string_t, build: F) -> c_int
+    where
+        F: FnOnce(usize, &mut sdbObject) -> Result<String, SurrealError>,
+    {
+        let index = self.statements.len();
+        let mut vars = sdbObject::new();
+        match build(index, &mut vars) {
+            Ok(statement) => {
+                self.statements.push(statement);
+                self.vars.extend(vars);
+                index as c_int
+            }
+            Err(e) => {
+                write_error(err_ptr, e);
+                SR_ERROR
+            }
+        }
+    }
+
+    /// Add a create operation, like `sr_create`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_create"]
+    pub extern "C" fn create(&mut self, err_ptr: *mut string_t, what: *const c_char, content: *const Object) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            let what = arg(what, "resource")?;
+            vars.insert(format!("c{i}"), object_arg(content, "content")?);
+            Ok(format!("CREATE {what} CONTENT $c{i}"))
+        })
+    }
+
+    /// Add an insert operation, like `sr_insert`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_insert"]
+    pub extern "C" fn insert(&mut self, err_ptr: *mut string_t, what: *const c_char, content: *const Object) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            let mut content = object_arg(content, "content")?;
+            match resource(arg(what, "resource")?) {
+                // INSERT only accepts a table, so a record is created with its key as id, as the SDK does
+                sdbValue::RecordId(id) => {
+                    content.insert("id".to_owned(), id.key.clone().into_value());
+                    vars.insert(format!("r{i}"), sdbValue::RecordId(id));
+                    vars.insert(format!("c{i}"), sdbValue::Object(content));
+                    Ok(format!("CREATE $r{i} CONTENT $c{i}"))
+                }
+                table => {
+                    vars.insert(format!("r{i}"), table);
+                    vars.insert(format!("c{i}"), sdbValue::Object(content));
+                    Ok(format!("INSERT INTO $r{i} $c{i}"))
+                }
+            }
+        })
+    }
+
+    /// Add an update operation replacing the content of records, like `sr_update`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_update"]
+    pub extern "C" fn update(&mut self, err_ptr: *mut string_t, what: *const c_char, content: *const Object) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            vars.insert(format!("r{i}"), resource(arg(what, "resource")?));
+            vars.insert(format!("c{i}"), object_arg(content, "content")?);
+            Ok(format!("UPDATE $r{i} CONTENT $c{i}"))
+        })
+    }
+
+    /// Add a merge operation, like `sr_merge`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_merge"]
+    pub extern "C" fn merge(&mut self, err_ptr: *mut string_t, what: *const c_char, content: *const Object) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            vars.insert(format!("r{i}"), resource(arg(what, "resource")?));
+            vars.insert(format!("c{i}"), object_arg(content, "content")?);
+            Ok(format!("UPDATE $r{i} MERGE $c{i}"))
+        })
+    }
+
+    fn patch(
+        &mut self,
+        err_ptr: *mut string_t,
+        what: *const c_char,
+        op: &str,
+        path: *const c_char,
+        value: Option<*const Value>,
+    ) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            let mut patch = sdbObject::new();
+            patch.insert("op".to_owned(), sdbValue::String(op.to_owned()));
+            patch.insert("path".to_owned(), sdbValue::String(arg(path, "path")?.to_owned()));
+            if let Some(value) = value {
+                patch.insert("value".to_owned(), value_arg(value, "value")?);
+            }
+            vars.insert(format!("r{i}"), resource(arg(what, "resource")?));
+            vars.insert(format!("p{i}"), sdbValue::Array(vec![sdbValue::Object(patch)].into()));
+            Ok(format!("UPDATE $r{i} PATCH $p{i}"))
+        })
+    }
+
+    /// Add a JSON Patch operation adding a value, like `sr_patch_add`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_patch_add"]
+    pub extern "C" fn patch_add(
+        &mut self,
+        err_ptr: *mut string_t,
+        what: *const c_char,
+        path: *const c_char,
+        value: *const Value,
+    ) -> c_int {
+        self.patch(err_ptr, what, "add", path, Some(value))
+    }
+
+    /// Add a JSON Patch operation removing a value, like `sr_patch_remove`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_patch_remove"]
+    pub extern "C" fn patch_remove(
+        &mut self,
+        err_ptr: *mut string_t,
+        what: *const c_char,
+        path: *const c_char,
+    ) -> c_int {
+        self.patch(err_ptr, what, "remove", path, None)
+    }
+
+    /// Add a JSON Patch operation replacing a value, like `sr_patch_replace`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_patch_replace"]
+    pub extern "C" fn patch_replace(
+        &mut self,
+        err_ptr: *mut string_t,
+        what: *const c_char,
+        path: *const c_char,
+        value: *const Value,
+    ) -> c_int {
+        self.patch(err_ptr, what, "replace", path, Some(value))
+    }
+
+    /// Add a delete operation, like `sr_delete`
+    ///
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_delete"]
+    pub extern "C" fn delete(&mut self, err_ptr: *mut string_t, what: *const c_char) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            vars.insert(format!("r{i}"), resource(arg(what, "resource")?));
+            Ok(format!("DELETE $r{i} RETURN BEFORE"))
+        })
+    }
+
+    /// Add a relate operation, like `sr_relate`
+    ///
+    /// `content` may be null.
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    #[export_name = "sr_batch_relate"]
+    pub extern "C" fn relate(
+        &mut self,
+        err_ptr: *mut string_t,
+        from: *const c_char,
+        relation: *const c_char,
+        to: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            let (from, relation, to) = (arg(from, "from")?, arg(relation, "relation")?, arg(to, "to")?);
+            if content.is_null() {
+                return Ok(format!("RELATE {from}->{relation}->{to}"));
+            }
+            vars.insert(format!("c{i}"), object_arg(content, "content")?);
+            Ok(format!("RELATE {from}->{relation}->{to} CONTENT $c{i}"))
+        })
+    }
+
+    /// Add a function call, like `sr_run`
+    ///
+    /// `args` may be null for no arguments.
+    /// Returns the index of the operation in the results, or SR_ERROR.
+    ///
+    /// # Safety
+    ///
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `function_name` must be a valid null-terminated UTF-8 string
+    /// - `args` must be a valid pointer or null
+    #[export_name = "sr_batch_run"]
+    pub unsafe extern "C" fn run(
+        &mut self,
+        err_ptr: *mut string_t,
+        function_name: *const c_char,
+        args: *const Array,
+    ) -> c_int {
+        self.push(err_ptr, |i, vars| {
+            // function names can not be bound, so only plain paths are accepted
+            let name = arg(function_name, "function_name")?;
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':') {
+                return Err(SurrealError::invalid_argument(format!("invalid function name: {name}")));
+            }
+
+            let args = match unsafe { args.as_ref() } {
+                Some(arr) => arr.as_slice(),
+                None => &[],
+            };
+            let mut params = Vec::with_capacity(args.len());
+            for (j, arg) in args.iter().enumerate() {
+                vars.insert(format!("a{i}_{j}"), sdbValue::from(arg.clone()));
+                params.push(format!("$a{i}_{j}"));
+            }
+            Ok(format!("RETURN {name}({})", params.join(", ")))
+        })
+    }
+
+    /// Execute all operations of a batch in a single query
+    ///
+    /// Writes one result per operation to *res_ptr, in the order they were added, and returns the
+    /// number of operations. A failing operation does not fail the call; its result holds the error.
+    /// If `atomic` is true the operations run in a transaction, and when any of them fails none of
+    /// them are applied. Free the results with `sr_free_arr_res_arr`.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `res_ptr` must be a valid pointer to receive the results
+    /// - `batch` must be a valid pointer to a batch
+    #[export_name = "sr_batch_execute"]
+    pub unsafe extern "C" fn execute(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut ArrayResult,
+        batch: &Batch,
+        atomic: bool,
+    ) -> c_int {
+        if res_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+            return SR_ERROR;
+        }
+        if batch.statements.is_empty() {
+            unsafe { res_ptr.write(std::ptr::null_mut()) }
+            return SR_NONE;
+        }
+
+        let mut query = batch.statements.join(";\n");
+        if atomic {
+            query = format!("BEGIN TRANSACTION;\n{query};\nCOMMIT TRANSACTION;");
+        }
+
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let mut results = ops::query(&surreal.db(), &query, batch.vars.clone()).await?;
+            if atomic {
+                // drop the results of BEGIN and COMMIT, the latter is missing if the transaction failed
+                results = results.into_iter().skip(1).take(batch.statements.len()).collect();
+            }
+
+            let ArrayGen { ptr, len } = ops::into_results(results).make_array();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len)
+        })
+    }
+}
@@ -0,0 +1,500 @@
+//! Callback based asynchronous variants of the CRUD and query calls
+//!
+//! Each `_async` function validates its arguments, copies them and spawns the operation on the
+//! connection's runtime, returning immediately. The callback is invoked exactly once from a runtime
+//! worker thread, so it must be thread safe and must not block. Blocking calls such as `sr_query` or
+//! `sr_stream_next` made inside the callback fail with SR_ERROR; use the `_async` functions instead.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    future::Future,
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    sync::atomic::Ordering,
+};
+
+use futures::FutureExt;
+use surrealdb::types::{Object as sdbObject, Value as sdbValue};
+
+use crate::{
+    array::{Array, ArrayGen, MakeArray},
+    cancel, ops,
+    result::{write_error, ArrayResult, SurrealError},
+    string::string_t,
+    value::{Object, Value},
+    Surreal, SR_ERROR, SR_FATAL, SR_NONE,
+};
+
+/// Callback receiving the values returned by a CRUD operation
+///
+/// On success `len` is the number of values written to `values`, which must be freed with `sr_free_arr`.
+/// On failure `len` is negative, `values` is null and `err` points to the error, which is only valid
+/// for the duration of the callback.
+pub type ValuesCallback = Option<extern "C" fn(userdata: *mut c_void, len: c_int, values: *mut Value, err: *const SurrealError)>;
+
+/// Callback receiving the record returned by `sr_create_async`
+///
+/// On success `code` is 1 and `obj` must be freed with `sr_free_object`.
+/// On failure `code` is negative, `obj` is null and `err` points to the error, which is only valid
+/// for the duration of the callback.
+pub type ObjectCallback = Option<extern "C" fn(userdata: *mut c_void, code: c_int, obj: *mut Object, err: *const SurrealError)>;
+
+/// Callback receiving the statement results of `sr_query_async`
+///
+/// On success `len` is the number of results written to `results`, which must be freed with
+/// `sr_free_arr_res_arr`. On failure `len` is negative, `results` is null and `err` points to the
+/// error, which is only valid for the duration of the callback.
+pub type QueryCallback = Option<
+    extern "C" fn(userdata: *mut c_void, len: c_int, results: *mut ArrayResult, err: *const SurrealError),
+>;
+
+/// User data handed back to a callback
+///
+/// The caller is responsible for the pointee being usable from the runtime's worker threads.
+#[derive(Clone, Copy)]
+pub(crate) struct UserData(pub(crate) *mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    pub(crate) fn get(self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Spawn an operation on the connection's runtime and hand its result to `deliver`
+///
+/// Returns SR_FATAL without spawning if the connection is poisoned.
+/// A panicking operation poisons the connection and is reported to `deliver` as SR_FATAL.
+pub(crate) fn spawn_op<T, F, D>(db: &Surreal, err_ptr: *mut string_t, fut: F, deliver: D) -> c_int
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, SurrealError>> + Send + 'static,
+    D: FnOnce(Result<T, SurrealError>) + Send + 'static,
+{
+    if db.ps.load(Ordering::Acquire) {
+        write_error(err_ptr, SurrealError::poisoned());
+        return SR_FATAL;
+    }
+
+    let fut = cancel::deadline(db.default_timeout(), cancel::guard(&db.interrupt, None, fut));
+    let ps = db.ps.clone();
+    db.rt.spawn(async move {
+        let res = match AssertUnwindSafe(fut).catch_unwind().await {
+            Ok(r) => r,
+            Err(e) => {
+                ps.store(true, Ordering::Release);
+                Err(SurrealError::panicked(&*e))
+            }
+        };
+        catch_unwind(AssertUnwindSafe(|| deliver(res))).ok();
+    });
+
+    SR_NONE
+}
+
+fn deliver_values(
+    callback: extern "C" fn(*mut c_void, c_int, *mut Value, *const SurrealError),
+    userdata: UserData,
+) -> impl FnOnce(Result<sdbValue, SurrealError>) + Send + 'static {
+    move |res| match res {
+        Ok(val) => {
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
+            callback(userdata.get(), len, ptr, ptr::null())
+        }
+        Err(e) => callback(userdata.get(), e.code, ptr::null_mut(), &e),
+    }
+}
+
+/// Copy a required C string argument, writing an error if it is null or not UTF-8
+pub(crate) fn read_str(err_ptr: *mut string_t, ptr: *const c_char, name: &str) -> Option<String> {
+    if ptr.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument(format!("{name} is null")));
+        return None;
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => Some(s.to_owned()),
+        Err(e) => {
+            write_error(err_ptr, SurrealError::from(e));
+            None
+        }
+    }
+}
+
+/// Copy a required object argument, writing an error if it is null
+pub(crate) fn read_object(err_ptr: *mut string_t, ptr: *const Object, name: &str) -> Option<sdbObject> {
+    if ptr.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument(format!("{name} is null")));
+        return None;
+    }
+    Some(sdbObject::from(unsafe { &*ptr }.clone()))
+}
+
+macro_rules! check_callback {
+    ($callback:expr, $err_ptr:expr) => {
+        match $callback {
+            Some(cb) => cb,
+            None => {
+                write_error($err_ptr, SurrealError::invalid_argument("callback is null"));
+                return SR_ERROR;
+            }
+        }
+    };
+}
+
+impl Surreal {
+    /// Select a resource without blocking
+    ///
+    /// Returns SR_NONE once the operation has been started, or SR_ERROR if the arguments are invalid,
+    /// in which case the callback is not invoked.
+    /// The callback receives the same values as `sr_select`. It runs on a runtime worker thread, where
+    /// blocking calls on any connection fail with SR_ERROR, so chain further operations with `_async` calls.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `resource` must be a valid null-terminated UTF-8 string
+    /// - `callback` must not be null
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// void on_select(void *userdata, int len, sr_value_t *values, const sr_error_t *err) {
+    ///     if (len < 0) {
+    ///         printf("%s", err->msg);
+    ///         return;
+    ///     }
+    ///     for (int i = 0; i < len; i++) {
+    ///         sr_value_print(&values[i]);
+    ///     }
+    ///     sr_free_arr(values, len);
+    /// }
+    ///
+    /// if (sr_select_async(db, &err, "foo", on_select, NULL) < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// ```
+    #[export_name = "sr_select_async"]
+    pub extern "C" fn select_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::select(&surreal, &resource).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Create a record without blocking
+    ///
+    /// The callback receives the created record, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_create_async"]
+    pub extern "C" fn create_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        content: *const Object,
+        callback: ObjectCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        let userdata = UserData(userdata);
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::create(&surreal, &resource, content).await },
+            move |res| match res {
+                Ok(obj) => {
+                    let boxed = Box::new(Object::from(obj));
+                    callback(userdata.get(), 1, Box::leak(boxed), ptr::null())
+                }
+                Err(e) => callback(userdata.get(), e.code, ptr::null_mut(), &e),
+            },
+        )
+    }
+
+    /// Insert one or more records without blocking
+    ///
+    /// The callback receives the same values as `sr_insert`, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_insert_async"]
+    pub extern "C" fn insert_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        content: *const Object,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::insert(&surreal, &resource, content).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Update records with new content without blocking
+    ///
+    /// The callback receives the same values as `sr_update`, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_update_async"]
+    pub extern "C" fn update_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        content: *const Object,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::update(&surreal, &resource, content).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Upsert (insert or update) records without blocking
+    ///
+    /// The callback receives the same values as `sr_upsert`, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_upsert_async"]
+    pub extern "C" fn upsert_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        content: *const Object,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::upsert(&surreal, &resource, content).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Merge data into existing records without blocking
+    ///
+    /// The callback receives the same values as `sr_merge`, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_merge_async"]
+    pub extern "C" fn merge_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        content: *const Object,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::merge(&surreal, &resource, content).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Delete a record or records without blocking
+    ///
+    /// The callback receives the same values as `sr_delete`, see `sr_select_async` for the calling convention.
+    #[export_name = "sr_delete_async"]
+    pub extern "C" fn delete_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        resource: *const c_char,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::delete(&surreal, &resource).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Create a graph relation between two records without blocking
+    ///
+    /// `content` may be null.
+    /// The callback receives the same values as `sr_relate`, see `sr_select_async` for the calling convention.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `from`, `relation` and `to` must be valid null-terminated UTF-8 strings
+    /// - `content` must be a valid pointer or null
+    /// - `callback` must not be null
+    #[export_name = "sr_relate_async"]
+    pub unsafe extern "C" fn relate_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        from: *const c_char,
+        relation: *const c_char,
+        to: *const c_char,
+        content: *const Object,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(from) = read_str(err_ptr, from, "from") else {
+            return SR_ERROR;
+        };
+        let Some(relation) = read_str(err_ptr, relation, "relation") else {
+            return SR_ERROR;
+        };
+        let Some(to) = read_str(err_ptr, to, "to") else {
+            return SR_ERROR;
+        };
+        let content = match content.is_null() {
+            true => None,
+            false => Some(sdbObject::from(unsafe { &*content }.clone())),
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::relate(&surreal, &from, &relation, &to, content).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Execute a SurrealDB function without blocking
+    ///
+    /// `args` may be null.
+    /// The callback receives the same values as `sr_run`, see `sr_select_async` for the calling convention.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `function_name` must be a valid null-terminated UTF-8 string
+    /// - `args` must be a valid pointer or null
+    /// - `callback` must not be null
+    #[export_name = "sr_run_async"]
+    pub unsafe extern "C" fn run_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        function_name: *const c_char,
+        args: *const Array,
+        callback: ValuesCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(function_name) = read_str(err_ptr, function_name, "function_name") else {
+            return SR_ERROR;
+        };
+        let args: Vec<sdbValue> = match args.is_null() {
+            true => vec![],
+            false => unsafe { &*args }.as_slice().iter().cloned().map(sdbValue::from).collect(),
+        };
+        let surreal = db.db();
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::run(&surreal, &function_name, args).await },
+            deliver_values(callback, UserData(userdata)),
+        )
+    }
+
+    /// Execute a SurrealQL query without blocking
+    ///
+    /// `vars` may be null.
+    /// The callback receives the same results as `sr_query`, see `sr_select_async` for the calling convention.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the operation
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `query` must be a valid null-terminated UTF-8 string
+    /// - `vars` must be a valid pointer or null
+    /// - `callback` must not be null
+    #[export_name = "sr_query_async"]
+    pub unsafe extern "C" fn query_async(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        query: *const c_char,
+        vars: *const Object,
+        callback: QueryCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        let callback = check_callback!(callback, err_ptr);
+        let Some(query) = read_str(err_ptr, query, "query") else {
+            return SR_ERROR;
+        };
+        let vars: sdbObject = match vars.is_null() {
+            true => sdbObject::default(),
+            false => unsafe { &*vars }.clone().into(),
+        };
+        let surreal = db.db();
+        let userdata = UserData(userdata);
+        spawn_op(
+            db,
+            err_ptr,
+            async move { ops::query(&surreal, &query, vars).await },
+            move |res| match res {
+                Ok(results) => {
+                    let ArrayGen { ptr, len } = ops::into_results(results).make_array();
+                    callback(userdata.get(), len, ptr, ptr::null())
+                }
+                Err(e) => callback(userdata.get(), e.code, ptr::null_mut(), &e),
+            },
+        )
+    }
+}
@@ -0,0 +1,234 @@
+//! Cancellation and deadlines of in-flight operations
+//!
+//! Every operation on a connection is raced against the connection's interrupt generation, which
+//! `sr_surreal_interrupt` advances from any thread. The `_cancellable` siblings of the blocking calls
+//! can additionally be aborted individually through a `sr_cancel_token_t`, and a token bound to a thread
+//! with `sr_cancel_token_bind` guards every blocking call made on that thread.
+//! Cancelled operations fail with SR_CANCELLED and an error of kind SR_ERROR_KIND_CANCELLED.
+//!
+//! Operations are also bounded by the connection's default timeout set with `sr_surreal_set_timeout`,
+//! which a timeout bound to the calling thread with `sr_timeout_bind` overrides for every blocking call,
+//! and the last argument of a `_timeout` sibling overrides for a single one. Expired operations fail with
+//! SR_TIMEOUT and an error of kind SR_ERROR_KIND_TIMEOUT.
+
+use std::{
+    cell::{Cell, RefCell},
+    ffi::c_int,
+    future::{pending, Future},
+    time::Duration,
+};
+
+use tokio::sync::watch;
+
+use crate::result::SurrealError;
+
+/// Token used to cancel blocking calls from another thread
+///
+/// May be shared across threads and passed to several calls at once.
+/// Once cancelled, every call using the token fails until it is reset with `sr_cancel_token_reset`.
+/// Free with `sr_cancel_token_free` once no call is using it.
+pub struct CancelToken {
+    tx: watch::Sender<bool>,
+}
+
+impl CancelToken {
+    /// Create a new cancellation token
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_cancel_token_t *token = sr_cancel_token_new();
+    ///
+    /// // on another thread
+    /// sr_cancel_token_cancel(token);
+    ///
+    /// // on the calling thread
+    /// int len = sr_query_cancellable(db, &err, token, &res, "SLEEP 1h", NULL);
+    /// if (len == sr_SR_CANCELLED) {
+    ///     printf("cancelled: %s", err);
+    /// }
+    /// sr_cancel_token_free(token);
+    /// ```
+    #[export_name = "sr_cancel_token_new"]
+    pub extern "C" fn new() -> *mut CancelToken {
+        let (tx, _) = watch::channel(false);
+        Box::into_raw(Box::new(CancelToken { tx }))
+    }
+
+    /// Cancel all calls using the token, including those started after this call
+    #[export_name = "sr_cancel_token_cancel"]
+    pub extern "C" fn cancel(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Check whether the token has been cancelled
+    #[export_name = "sr_cancel_token_is_cancelled"]
+    pub extern "C" fn is_cancelled(&self) -> bool {
+        *self.tx.borrow()
+    }
+
+    /// Reset a cancelled token so it can be reused
+    #[export_name = "sr_cancel_token_reset"]
+    pub extern "C" fn reset(&self) {
+        self.tx.send_replace(false);
+    }
+
+    /// Bind the token to the calling thread
+    ///
+    /// Every blocking call made on the calling thread is aborted with SR_CANCELLED once the token is
+    /// cancelled, in addition to any token passed to a `_cancellable` sibling, until `sr_cancel_token_unbind`
+    /// is called or another token is bound. Freeing a bound token leaves the calls uncancellable.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_cancel_token_bind(token);
+    /// if (sr_create(db, &err, &res, "person", content) == sr_SR_CANCELLED) {
+    ///     printf("cancelled: %s", err);
+    /// }
+    /// sr_cancel_token_unbind();
+    /// ```
+    #[export_name = "sr_cancel_token_bind"]
+    pub extern "C" fn bind(&self) {
+        let rx = self.tx.subscribe();
+        BOUND.with(|bound| *bound.borrow_mut() = Some(rx));
+    }
+
+    /// Unbind the token bound to the calling thread, if any
+    #[export_name = "sr_cancel_token_unbind"]
+    pub extern "C" fn unbind() {
+        BOUND.with(|bound| *bound.borrow_mut() = None);
+    }
+
+    /// Free a cancellation token
+    ///
+    /// # Safety
+    ///
+    /// - `token` must be a pointer returned by `sr_cancel_token_new` which was not freed yet, or null
+    #[export_name = "sr_cancel_token_free"]
+    pub unsafe extern "C" fn free(token: *mut CancelToken) {
+        if !token.is_null() {
+            let _ = unsafe { Box::from_raw(token) };
+        }
+    }
+}
+
+thread_local! {
+    /// Token bound to the calling thread with `sr_cancel_token_bind`
+    static BOUND: RefCell<Option<watch::Receiver<bool>>> = const { RefCell::new(None) };
+
+    /// Timeout bound to the calling thread with `sr_timeout_bind`
+    static BOUND_TIMEOUT: Cell<Option<Option<Duration>>> = const { Cell::new(None) };
+}
+
+/// Bind a timeout to the calling thread
+///
+/// Every blocking call made on the calling thread fails with SR_TIMEOUT once `timeout_ms` milliseconds
+/// have passed, overriding the connection's default timeout, until `sr_timeout_unbind` is called.
+/// A negative `timeout_ms` waits indefinitely. The `_timeout` siblings still use their own timeout.
+///
+/// # Examples
+///
+/// ```c
+/// sr_timeout_bind(500);
+/// if (sr_create(db, &err, &res, "person", content) == sr_SR_TIMEOUT) {
+///     printf("timed out: %s", err);
+/// }
+/// sr_timeout_unbind();
+/// ```
+#[export_name = "sr_timeout_bind"]
+pub extern "C" fn timeout_bind(timeout_ms: c_int) {
+    BOUND_TIMEOUT.with(|bound| bound.set(Some(timeout_from_ms(timeout_ms))));
+}
+
+/// Unbind the timeout bound to the calling thread, restoring the connection's default timeout
+#[export_name = "sr_timeout_unbind"]
+pub extern "C" fn timeout_unbind() {
+    BOUND_TIMEOUT.with(|bound| bound.set(None));
+}
+
+/// Timeout bound to the calling thread, if any
+pub(crate) fn bound_timeout() -> Option<Option<Duration>> {
+    BOUND_TIMEOUT.with(Cell::get)
+}
+
+/// Interrupt generation of a connection, advanced by `sr_surreal_interrupt`
+pub(crate) struct Interrupt {
+    tx: watch::Sender<u64>,
+}
+
+impl Interrupt {
+    pub(crate) fn new() -> Interrupt {
+        let (tx, _) = watch::channel(0);
+        Interrupt { tx }
+    }
+
+    /// Cancel every operation currently guarded by this interrupt
+    pub(crate) fn trigger(&self) {
+        self.tx.send_modify(|generation| *generation = generation.wrapping_add(1));
+    }
+}
+
+/// Race an operation against an interrupt, an optional token and the token bound to the calling thread
+///
+/// Subscribes immediately, so an interrupt triggered before the returned future is first polled
+/// still cancels it.
+pub(crate) fn guard<T, F>(
+    interrupt: &Interrupt,
+    token: Option<&CancelToken>,
+    fut: F,
+) -> impl Future<Output = Result<T, SurrealError>>
+where
+    F: Future<Output = Result<T, SurrealError>>,
+{
+    let mut interrupted = interrupt.tx.subscribe();
+    let cancelled = token.map(|t| t.tx.subscribe());
+    let bound = BOUND.with(|bound| bound.borrow().clone());
+
+    async move {
+        let interrupted = async {
+            if interrupted.changed().await.is_err() {
+                pending::<()>().await
+            }
+        };
+
+        tokio::select! {
+            res = fut => res,
+            _ = interrupted => Err(SurrealError::cancelled("operation interrupted")),
+            _ = wait_cancelled(cancelled) => Err(SurrealError::cancelled("operation cancelled")),
+            _ = wait_cancelled(bound) => Err(SurrealError::cancelled("operation cancelled")),
+        }
+    }
+}
+
+/// Resolve once a token is cancelled, never if there is no token or it was freed uncancelled
+async fn wait_cancelled(rx: Option<watch::Receiver<bool>>) {
+    let Some(mut rx) = rx else {
+        return pending().await;
+    };
+    if rx.wait_for(|c| *c).await.is_err() {
+        pending::<()>().await
+    }
+}
+
+/// Bound an operation by an optional timeout
+pub(crate) async fn deadline<T, F>(timeout: Option<Duration>, fut: F) -> Result<T, SurrealError>
+where
+    F: Future<Output = Result<T, SurrealError>>,
+{
+    match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, fut).await {
+            Ok(res) => res,
+            Err(_) => Err(SurrealError::timed_out(format!(
+                "operation timed out after {}ms",
+                timeout.as_millis()
+            ))),
+        },
+        None => fut.await,
+    }
+}
+
+/// Convert a timeout in milliseconds from C, where a negative value means no timeout
+pub(crate) fn timeout_from_ms(timeout_ms: c_int) -> Option<Duration> {
+    u64::try_from(timeout_ms).ok().map(Duration::from_millis)
+}
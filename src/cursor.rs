@@ -0,0 +1,214 @@
+//! Cursors over large query results
+//!
+//! `sr_cursor_open` pages through the result of a single query in batches using START and LIMIT, so
+//! at most one batch of rows is held in memory at a time. The SDK does not stream query results, so
+//! every batch is a separate query: a plain SELECT is paginated directly, anything else is wrapped in
+//! a subquery which the engine evaluates again for every batch.
+//!
+//! Rows are read as the query is executed for each batch, so records written while iterating may be
+//! skipped or returned twice. Use an ORDER BY clause for a stable order.
+
+use std::{
+    collections::VecDeque,
+    ffi::{c_char, c_int, CStr},
+};
+
+use surrealdb::types::{Number as sdbNumber, Object as sdbObject, Value as sdbValue};
+
+use crate::{
+    object::Object,
+    ops, parse,
+    result::{write_error, ErrorKind, SurrealError},
+    string::string_t,
+    value::Value,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// Number of rows fetched per batch when no batch size is given
+const DEFAULT_BATCH_SIZE: u64 = 1000;
+
+/// A cursor over the rows returned by a query
+///
+/// Must not be used concurrently, and must be closed with `sr_cursor_close` before its connection
+/// is disconnected.
+pub struct Cursor {
+    db: *const Surreal,
+    query: String,
+    vars: sdbObject,
+    batch_size: u64,
+    start: u64,
+    rows: VecDeque<sdbValue>,
+    done: bool,
+}
+
+impl Cursor {
+    /// Open a cursor over the result of a query
+    ///
+    /// `query` must be a single statement returning an array, usually a SELECT.
+    /// `batch_size` is the number of rows fetched at once, 0 or less for the default of 1000.
+    /// No rows are fetched until the first call to `sr_cursor_next`.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the cursor
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `cursor_ptr` must be a valid pointer to receive the cursor
+    /// - `query` must be a valid null-terminated UTF-8 string
+    /// - `vars` may be null (no variables bound)
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_cursor_t *cursor;
+    /// if (sr_cursor_open(db, &err, &cursor, "SELECT * FROM event ORDER BY time", NULL, 500) < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// sr_value_t *row;
+    /// int res;
+    /// while ((res = sr_cursor_next(cursor, &err, &row)) > 0) {
+    ///     sr_value_print(row);
+    ///     sr_value_free(row);
+    /// }
+    /// sr_cursor_close(cursor);
+    /// ```
+    #[export_name = "sr_cursor_open"]
+    pub extern "C" fn open(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        cursor_ptr: *mut *mut Cursor,
+        query: *const c_char,
+        vars: *const Object,
+        batch_size: c_int,
+    ) -> c_int {
+        if cursor_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("cursor_ptr is null"));
+            return SR_ERROR;
+        }
+        if query.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("query is null"));
+            return SR_ERROR;
+        }
+        let query = match unsafe { CStr::from_ptr(query) }.to_str() {
+            Ok(q) => q.trim_end().trim_end_matches(';'),
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+        if u32::try_from(query.len()).is_err() {
+            write_error(err_ptr, SurrealError::invalid_argument("query is too large"));
+            return SR_ERROR;
+        }
+
+        match parse::parse(query) {
+            Ok(parsed) if parsed.statements == 1 => {}
+            Ok(_) => {
+                write_error(
+                    err_ptr,
+                    SurrealError::invalid_argument("a cursor can only be opened on a single statement"),
+                );
+                return SR_ERROR;
+            }
+            Err(e) => {
+                let msg = format!("Parse error: {}", e.render_on(query));
+                write_error(err_ptr, SurrealError::new(ErrorKind::SR_ERROR_KIND_PARSE, msg));
+                return SR_ERROR;
+            }
+        }
+
+        // START and LIMIT can only be appended to a SELECT which does not already use them,
+        // anything else is paginated as a subquery
+        let paged = format!("{query}\nSTART $cursor_start LIMIT $cursor_limit");
+        let query = match parse::parse(&paged) {
+            Ok(_) => paged,
+            Err(_) => format!("SELECT * FROM (\n{query}\n) START $cursor_start LIMIT $cursor_limit"),
+        };
+
+        let vars = match vars.is_null() {
+            true => sdbObject::default(),
+            false => unsafe { &*vars }.clone().into(),
+        };
+        let batch_size = match u64::try_from(batch_size) {
+            Ok(0) | Err(_) => DEFAULT_BATCH_SIZE,
+            Ok(n) => n,
+        };
+
+        let cursor = Cursor {
+            db,
+            query,
+            vars,
+            batch_size,
+            start: 0,
+            rows: VecDeque::new(),
+            done: false,
+        };
+        unsafe { cursor_ptr.write(Box::into_raw(Box::new(cursor))) }
+        SR_NONE
+    }
+
+    /// Get the next row of a cursor
+    ///
+    /// Fetches the next batch when the current one is used up.
+    /// Returns 1 and writes the row to *val_ptr, SR_NONE once all rows have been returned, or a
+    /// negative error code if fetching a batch failed. Free the row with `sr_value_free`.
+    #[export_name = "sr_cursor_next"]
+    pub extern "C" fn next(&mut self, err_ptr: *mut string_t, val_ptr: *mut *mut Value) -> c_int {
+        if val_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("val_ptr is null"));
+            return SR_ERROR;
+        }
+        if self.rows.is_empty() && !self.done {
+            let res = self.fetch(err_ptr);
+            if res < 0 {
+                return res;
+            }
+        }
+        match self.rows.pop_front() {
+            Some(row) => {
+                unsafe { val_ptr.write(Box::into_raw(Box::new(Value::from(row)))) }
+                1
+            }
+            None => SR_NONE,
+        }
+    }
+
+    fn fetch(&mut self, err_ptr: *mut string_t) -> c_int {
+        let db = unsafe { &*self.db };
+        let mut vars = self.vars.clone();
+        vars.insert("cursor_start".to_owned(), sdbValue::Number(sdbNumber::Int(self.start as i64)));
+        vars.insert("cursor_limit".to_owned(), sdbValue::Number(sdbNumber::Int(self.batch_size as i64)));
+
+        let mut rows = VecDeque::new();
+        let res = with_surreal_async(db, err_ptr, |surreal| async {
+            let mut results = ops::query(&surreal.db(), &self.query, vars).await?;
+            match results.pop() {
+                Some(Ok(sdbValue::Array(arr))) => rows.extend(arr),
+                Some(Ok(other)) => {
+                    return Err(SurrealError::from_msg(format!(
+                        "expected an array of rows from the cursor query, found: {other:?}"
+                    )))
+                }
+                Some(Err(e)) => return Err(e),
+                None => {}
+            }
+            Ok(SR_NONE)
+        });
+        if res < 0 {
+            return res;
+        }
+
+        self.start += rows.len() as u64;
+        self.done = (rows.len() as u64) < self.batch_size;
+        self.rows = rows;
+        SR_NONE
+    }
+
+    /// Close a cursor and free any rows it still holds
+    #[export_name = "sr_cursor_close"]
+    pub extern "C" fn close(cursor: *mut Cursor) {
+        if !cursor.is_null() {
+            let _ = unsafe { Box::from_raw(cursor) };
+        }
+    }
+}
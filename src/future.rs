@@ -0,0 +1,630 @@
+//! Pollable futures for integrating connections into event loops
+//!
+//! Each `_future` function validates its arguments and starts the operation on the connection's runtime,
+//! writing an `sr_future_t` to `fut_ptr` without blocking. Completion can be checked with `sr_future_poll`
+//! or `sr_future_wait`, and the descriptor returned by `sr_surreal_fd` becomes readable whenever any
+//! future of the connection completes, so it can be registered with epoll, kqueue, poll or select.
+
+use std::{
+    ffi::{c_char, c_int, c_void},
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use surrealdb::types::{Object as sdbObject, Value as sdbValue};
+
+use crate::{
+    array::{Array, ArrayGen, MakeArray},
+    callback::{read_object, read_str, spawn_op},
+    ops,
+    result::{write_error, ArrayResult, SurrealError},
+    string::string_t,
+    value::{Object, Value},
+    Surreal, SR_ERROR, SR_NONE, SR_PENDING,
+};
+
+/// Result of a completed operation, kept as SDK values until it is taken
+enum Output {
+    Values(sdbValue),
+    Object(sdbObject),
+    Query(Vec<Result<sdbValue, SurrealError>>),
+}
+
+enum State {
+    Pending,
+    Ready(Result<Output, SurrealError>),
+    Taken,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    ready: Condvar,
+}
+
+/// Handle to an operation running on a connection's runtime
+///
+/// May be sent across threads, but must not be aliased. Unlike the `_async` callbacks, the result is
+/// taken on the caller's thread, so blocking calls may follow it.
+/// Free with `sr_future_free` once it is no longer needed, whether or not it has completed.
+pub struct SurrealFuture {
+    shared: Arc<Shared>,
+}
+
+/// Completion notifier shared by all futures of a connection, or by the streams of a stream set
+pub(crate) struct Completion {
+    #[cfg(unix)]
+    pipe: Mutex<Option<Pipe>>,
+}
+
+impl Completion {
+    pub(crate) fn new() -> Completion {
+        Completion {
+            #[cfg(unix)]
+            pipe: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn notify(&self) {
+        #[cfg(unix)]
+        if let Some(pipe) = &*self.pipe.lock().unwrap_or_else(PoisonError::into_inner) {
+            pipe.signal();
+        }
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn fd(&self) -> Result<c_int, SurrealError> {
+        use std::os::fd::AsRawFd;
+
+        let mut pipe = self.pipe.lock().unwrap_or_else(PoisonError::into_inner);
+        if pipe.is_none() {
+            *pipe = Some(Pipe::new().map_err(|e| format!("error creating completion pipe: {e}"))?);
+        }
+        Ok(pipe.as_ref().unwrap().read.as_raw_fd())
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn fd(&self) -> Result<c_int, SurrealError> {
+        Err(SurrealError::new(
+            crate::result::ErrorKind::SR_ERROR_KIND_CONFIGURATION,
+            "completion descriptors are only supported on unix platforms",
+        ))
+    }
+}
+
+/// Non-blocking, close-on-exec pipe whose read end becomes readable when a byte is written
+#[cfg(unix)]
+struct Pipe {
+    read: std::os::fd::OwnedFd,
+    write: std::os::fd::OwnedFd,
+}
+
+#[cfg(unix)]
+impl Pipe {
+    fn new() -> std::io::Result<Pipe> {
+        use std::os::fd::FromRawFd;
+
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let pipe = unsafe {
+            Pipe {
+                read: std::os::fd::OwnedFd::from_raw_fd(fds[0]),
+                write: std::os::fd::OwnedFd::from_raw_fd(fds[1]),
+            }
+        };
+        for fd in fds {
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                if flags < 0
+                    || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0
+                    || libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) < 0
+                {
+                    return Err(std::io::Error::last_os_error());
+                }
+            }
+        }
+        Ok(pipe)
+    }
+
+    fn signal(&self) {
+        use std::os::fd::AsRawFd;
+
+        // a full pipe is already readable, so a failed write can be ignored
+        unsafe { libc::write(self.write.as_raw_fd(), [1u8].as_ptr().cast(), 1) };
+    }
+}
+
+/// Spawn an operation and write a future tracking it to `fut_ptr`
+fn spawn_future<T, F>(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    fut_ptr: *mut *mut SurrealFuture,
+    fut: F,
+    wrap: fn(T) -> Output,
+) -> c_int
+where
+    T: Send + 'static,
+    F: std::future::Future<Output = Result<T, SurrealError>> + Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State::Pending),
+        ready: Condvar::new(),
+    });
+
+    let completion = db.completion.clone();
+    let delivered = shared.clone();
+    let res = spawn_op(db, err_ptr, fut, move |res| {
+        *delivered.state.lock().unwrap_or_else(PoisonError::into_inner) = State::Ready(res.map(wrap));
+        delivered.ready.notify_all();
+        completion.notify();
+    });
+    if res < 0 {
+        return res;
+    }
+
+    let boxed = Box::new(SurrealFuture { shared });
+    unsafe { fut_ptr.write(Box::into_raw(boxed)) }
+    SR_NONE
+}
+
+macro_rules! check_fut_ptr {
+    ($fut_ptr:expr, $err_ptr:expr) => {
+        if $fut_ptr.is_null() {
+            write_error($err_ptr, SurrealError::invalid_argument("fut_ptr is null"));
+            return SR_ERROR;
+        }
+    };
+}
+
+impl Surreal {
+    /// Get a file descriptor which becomes readable when any future of this connection completes
+    ///
+    /// The descriptor is created on the first call and owned by the connection, so it must not be closed
+    /// and is only valid until `sr_surreal_disconnect`. Completions are only signalled once the descriptor
+    /// exists, so call this before starting the futures it should report.
+    /// After it becomes readable, read and discard the available bytes, then check each pending future with
+    /// `sr_future_poll`. A single readable event may stand for several completed futures.
+    ///
+    /// Returns the descriptor, or SR_ERROR on failure or on platforms without descriptor support.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// int fd = sr_surreal_fd(db, &err);
+    /// struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    /// poll(&pfd, 1, -1);
+    ///
+    /// char buf[64];
+    /// while (read(fd, buf, sizeof(buf)) > 0) {}
+    /// if (sr_future_poll(fut) == 1) {
+    ///     // take the result
+    /// }
+    /// ```
+    #[export_name = "sr_surreal_fd"]
+    pub extern "C" fn surreal_fd(db: &Surreal, err_ptr: *mut string_t) -> c_int {
+        match db.completion.fd() {
+            Ok(fd) => fd,
+            Err(e) => {
+                write_error(err_ptr, e);
+                SR_ERROR
+            }
+        }
+    }
+
+    /// Select a resource, returning a future instead of blocking
+    ///
+    /// Returns SR_NONE and writes the future to `fut_ptr` once the operation has been started,
+    /// or SR_ERROR if the arguments are invalid.
+    /// `sr_future_take_result` yields the same values as `sr_select`, written to a `sr_value_t**`.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the future
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `fut_ptr` must be a valid pointer to receive the future
+    /// - `resource` must be a valid null-terminated UTF-8 string
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_future_t *fut;
+    /// if (sr_select_future(db, &err, &fut, "foo") < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    ///
+    /// sr_future_wait(fut, -1);
+    /// sr_value_t *vals;
+    /// int len = sr_future_take_result(fut, &err, &vals);
+    /// if (len >= 0) {
+    ///     sr_free_arr(vals, len);
+    /// }
+    /// sr_future_free(fut);
+    /// ```
+    #[export_name = "sr_select_future"]
+    pub extern "C" fn select_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::select(&surreal, &resource).await },
+            Output::Values,
+        )
+    }
+
+    /// Create a record, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the created record, written to a `sr_object_t**`.
+    /// See `sr_select_future` for the calling convention.
+    #[export_name = "sr_create_future"]
+    pub extern "C" fn create_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::create(&surreal, &resource, content).await },
+            Output::Object,
+        )
+    }
+
+    /// Insert one or more records, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the same values as `sr_insert`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_insert_future"]
+    pub extern "C" fn insert_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::insert(&surreal, &resource, content).await },
+            Output::Values,
+        )
+    }
+
+    /// Update records with new content, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the same values as `sr_update`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_update_future"]
+    pub extern "C" fn update_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::update(&surreal, &resource, content).await },
+            Output::Values,
+        )
+    }
+
+    /// Upsert (insert or update) records, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the same values as `sr_upsert`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_upsert_future"]
+    pub extern "C" fn upsert_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::upsert(&surreal, &resource, content).await },
+            Output::Values,
+        )
+    }
+
+    /// Merge data into existing records, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the same values as `sr_merge`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_merge_future"]
+    pub extern "C" fn merge_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let Some(content) = read_object(err_ptr, content, "content") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::merge(&surreal, &resource, content).await },
+            Output::Values,
+        )
+    }
+
+    /// Delete a record or records, returning a future instead of blocking
+    ///
+    /// `sr_future_take_result` yields the same values as `sr_delete`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_delete_future"]
+    pub extern "C" fn delete_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        resource: *const c_char,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(resource) = read_str(err_ptr, resource, "resource") else {
+            return SR_ERROR;
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::delete(&surreal, &resource).await },
+            Output::Values,
+        )
+    }
+
+    /// Create a graph relation between two records, returning a future instead of blocking
+    ///
+    /// `content` may be null.
+    /// `sr_future_take_result` yields the same values as `sr_relate`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_relate_future"]
+    pub extern "C" fn relate_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        from: *const c_char,
+        relation: *const c_char,
+        to: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(from) = read_str(err_ptr, from, "from") else {
+            return SR_ERROR;
+        };
+        let Some(relation) = read_str(err_ptr, relation, "relation") else {
+            return SR_ERROR;
+        };
+        let Some(to) = read_str(err_ptr, to, "to") else {
+            return SR_ERROR;
+        };
+        let content = match content.is_null() {
+            true => None,
+            false => Some(sdbObject::from(unsafe { &*content }.clone())),
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::relate(&surreal, &from, &relation, &to, content).await },
+            Output::Values,
+        )
+    }
+
+    /// Execute a SurrealDB function, returning a future instead of blocking
+    ///
+    /// `args` may be null.
+    /// `sr_future_take_result` yields the same values as `sr_run`, see `sr_select_future` for the calling convention.
+    #[export_name = "sr_run_future"]
+    pub extern "C" fn run_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        function_name: *const c_char,
+        args: *const Array,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(function_name) = read_str(err_ptr, function_name, "function_name") else {
+            return SR_ERROR;
+        };
+        let args: Vec<sdbValue> = match args.is_null() {
+            true => vec![],
+            false => unsafe { &*args }.as_slice().iter().cloned().map(sdbValue::from).collect(),
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::run(&surreal, &function_name, args).await },
+            Output::Values,
+        )
+    }
+
+    /// Execute a SurrealQL query, returning a future instead of blocking
+    ///
+    /// `vars` may be null.
+    /// `sr_future_take_result` yields the same results as `sr_query`, written to a `sr_arr_res_t**`.
+    /// See `sr_select_future` for the calling convention.
+    #[export_name = "sr_query_future"]
+    pub extern "C" fn query_future(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        fut_ptr: *mut *mut SurrealFuture,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        check_fut_ptr!(fut_ptr, err_ptr);
+        let Some(query) = read_str(err_ptr, query, "query") else {
+            return SR_ERROR;
+        };
+        let vars = match vars.is_null() {
+            true => sdbObject::new(),
+            false => sdbObject::from(unsafe { &*vars }.clone()),
+        };
+        let surreal = db.db();
+        spawn_future(
+            db,
+            err_ptr,
+            fut_ptr,
+            async move { ops::query(&surreal, &query, vars).await },
+            Output::Query,
+        )
+    }
+}
+
+impl SurrealFuture {
+    /// Check whether a future has completed without blocking
+    ///
+    /// Returns 1 if the result is ready or has already been taken, and SR_NONE if the operation is still running.
+    #[export_name = "sr_future_poll"]
+    pub extern "C" fn poll(&self) -> c_int {
+        match *self.shared.state.lock().unwrap_or_else(PoisonError::into_inner) {
+            State::Pending => SR_NONE,
+            _ => 1,
+        }
+    }
+
+    /// Block until a future completes or the timeout expires
+    ///
+    /// A negative `timeout_ms` waits indefinitely.
+    /// Returns 1 once the future has completed, and SR_NONE if the timeout expired first.
+    #[export_name = "sr_future_wait"]
+    pub extern "C" fn wait(&self, timeout_ms: c_int) -> c_int {
+        let deadline = u64::try_from(timeout_ms)
+            .ok()
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+
+        let mut state = self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        while let State::Pending = *state {
+            state = match deadline {
+                None => self.shared.ready.wait(state).unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                        return SR_NONE;
+                    };
+                    self.shared.ready.wait_timeout(state, left).unwrap_or_else(PoisonError::into_inner).0
+                }
+            };
+        }
+        1
+    }
+
+    /// Take the result of a completed future
+    ///
+    /// `res_ptr` must point to the result type documented by the function which created the future:
+    /// a `sr_value_t**`, `sr_object_t**` or `sr_arr_res_t**`. It is written, and ownership passes to the
+    /// caller, only if the operation succeeded.
+    ///
+    /// Returns the same value as the blocking function would have, SR_ERROR if the operation failed,
+    /// or SR_PENDING if it has not completed yet. The result can only be taken once.
+    #[export_name = "sr_future_take_result"]
+    pub extern "C" fn take_result(&mut self, err_ptr: *mut string_t, res_ptr: *mut c_void) -> c_int {
+        if res_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+            return SR_ERROR;
+        }
+
+        let mut state = self.shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let output = match std::mem::replace(&mut *state, State::Taken) {
+            State::Pending => {
+                *state = State::Pending;
+                return SR_PENDING;
+            }
+            State::Taken => {
+                write_error(err_ptr, SurrealError::invalid_argument("future result has already been taken"));
+                return SR_ERROR;
+            }
+            State::Ready(Err(e)) => {
+                let code = e.code;
+                write_error(err_ptr, e);
+                return code;
+            }
+            State::Ready(Ok(output)) => output,
+        };
+        drop(state);
+
+        match output {
+            Output::Values(val) => {
+                let ArrayGen { ptr, len } = ops::into_array(val).into();
+                unsafe { res_ptr.cast::<*mut Value>().write(ptr) }
+                len
+            }
+            Output::Object(obj) => {
+                let boxed = Box::new(Object::from(obj));
+                unsafe { res_ptr.cast::<*mut Object>().write(Box::into_raw(boxed)) }
+                1
+            }
+            Output::Query(results) => {
+                let ArrayGen { ptr, len } = ops::into_results(results).make_array();
+                unsafe { res_ptr.cast::<*mut ArrayResult>().write(ptr) }
+                len
+            }
+        }
+    }
+
+    /// Free a future
+    ///
+    /// A future that has not completed is detached: the operation keeps running and its result is discarded.
+    #[export_name = "sr_future_free"]
+    pub extern "C" fn free(fut: *mut SurrealFuture) {
+        if !fut.is_null() {
+            let _ = unsafe { Box::from_raw(fut) };
+        }
+    }
+}
@@ -1,20 +1,36 @@
 #![recursion_limit = "256"]
 
+pub mod backup;
+pub mod batch;
+pub mod callback;
+pub mod cancel;
+pub mod cursor;
+pub mod future;
+pub mod load;
+mod ops;
 pub mod opts;
+pub mod parse;
 pub mod rpc;
+pub mod runtime;
+pub mod statement;
+pub mod transaction;
 pub mod types;
 pub mod utils;
 
 use std::{
     ffi::{c_char, c_int, CStr},
-    future::IntoFuture,
     panic::{catch_unwind, AssertUnwindSafe},
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicBool, AtomicI32, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::Duration,
 };
 use stream::Stream;
 use string::string_t;
 use surrealdb::{
     engine::any::{self, Any},
+    opt::Config,
     opt::Resource,
     opt::auth,
     opt::PatchOp,
@@ -34,29 +50,35 @@ fn parse_resource(s: &str) -> Resource {
     }
     Resource::from(s)
 }
+use arc_swap::ArcSwap;
 use tokio::runtime::Runtime;
-use types::result::ArrayResult;
+use types::result::{write_error, ArrayResult, ErrorOut, SurrealError};
 
 use array::{Array, ArrayGen, MakeArray};
 pub use types::*;
 use utils::CStringExt2;
+use response::Response;
 use value::{Object, Value};
 use crate::credentials::{credentials_scope, credentials_access};
+use cancel::{CancelToken, Interrupt};
+use future::Completion;
+use runtime::SurrealRuntime;
+use opts::{opt_str, ConnectOptions, SR_CONNECT_OPTIONS_VERSION};
 
 pub const SR_NONE: c_int = 0;
 pub const SR_CLOSED: c_int = -1;
 pub const SR_ERROR: c_int = -2;
 pub const SR_FATAL: c_int = -3;
+pub const SR_PENDING: c_int = -4;
+pub const SR_CANCELLED: c_int = -5;
+pub const SR_TIMEOUT: c_int = -6;
 
-/// Safely write an error message to an error pointer
-/// 
-/// If `err_ptr` is null, the error is silently ignored.
-#[inline]
-fn write_error(err_ptr: *mut string_t, msg: impl Into<string_t>) {
-    if !err_ptr.is_null() {
-        unsafe { err_ptr.write(msg.into()) };
-    }
-}
+/// `sr_insert_many` flag: skip rows whose record already exists instead of failing
+pub const SR_INSERT_IGNORE: c_int = 1;
+/// `sr_insert_many` flag: update records which already exist with the fields of the row
+pub const SR_INSERT_UPDATE: c_int = 2;
+/// `sr_insert_many` flag: do not return the inserted records
+pub const SR_INSERT_DISCARD: c_int = 4;
 
 /// Macro to validate that a pointer is not null
 /// 
@@ -64,7 +86,7 @@ fn write_error(err_ptr: *mut string_t, msg: impl Into<string_t>) {
 macro_rules! check_null {
     ($ptr:expr, $err_ptr:expr, $msg:expr) => {
         if $ptr.is_null() {
-            write_error($err_ptr, $msg);
+            write_error($err_ptr, SurrealError::invalid_argument($msg));
             return SR_ERROR;
         }
     };
@@ -73,25 +95,32 @@ macro_rules! check_null {
 /// The object representing a Surreal connection
 ///
 /// It is safe to be referenced from multiple threads
-/// If any operation, on any thread returns SR_FATAL then the connection is poisoned and must not be used again.
-/// (use will cause the program to abort)
+/// If any operation, on any thread returns SR_FATAL then the connection is poisoned,
+/// and every further call fails with SR_FATAL until it is rebuilt with sr_surreal_reconnect.
 ///
 /// should be freed with sr_surreal_disconnect
 pub struct Surreal {
-    db: sdbSurreal<Any>,
-    rt: Runtime,
-    ps: AtomicBool,
+    db: ArcSwap<sdbSurreal<Any>>,
+    target: Target,
+    session: Mutex<Session>,
+    rt: Arc<Runtime>,
+    ps: Arc<AtomicBool>,
+    completion: Arc<Completion>,
+    interrupt: Interrupt,
+    /// default timeout of every operation in milliseconds, negative for none
+    timeout_ms: AtomicI32,
 }
 
 impl Surreal {
     /// Connects to a local, remote, or embedded database
     ///
-    /// If any function returns SR_FATAL, the connection is poisoned and must not be used
-    /// (except to drop). Continued use will cause the program to abort.
+    /// If any function returns SR_FATAL, the connection is poisoned: every further call on it fails
+    /// with SR_FATAL without being executed, until the connection is rebuilt with `sr_surreal_reconnect`
+    /// or freed with `sr_surreal_disconnect`.
     ///
     /// # Safety
     ///
-    /// - `err_ptr` must be a valid pointer or null (errors ignored if null)
+    /// - `err_ptr` must be a valid pointer or null (the error is then only available through sr_last_error)
     /// - `surreal_ptr` must be a valid pointer to receive the connection handle
     /// - `endpoint` must be a valid null-terminated UTF-8 string
     ///
@@ -126,59 +155,216 @@ impl Surreal {
         err_ptr: *mut string_t,
         surreal_ptr: *mut *mut Surreal,
         endpoint: *const c_char,
+    ) -> c_int {
+        Self::connect_impl(err_ptr, surreal_ptr, endpoint)
+    }
+
+    /// Connects to a local, remote, or embedded database, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_connect` except for the type of `err_ptr`.
+    #[export_name = "sr_connect_ex"]
+    pub extern "C" fn connect_ex(
+        err_ptr: *mut SurrealError,
+        surreal_ptr: *mut *mut Surreal,
+        endpoint: *const c_char,
+    ) -> c_int {
+        Self::connect_impl(err_ptr, surreal_ptr, endpoint)
+    }
+
+    fn connect_impl<E: ErrorOut>(
+        err_ptr: E,
+        surreal_ptr: *mut *mut Surreal,
+        endpoint: *const c_char,
     ) -> c_int {
         check_null!(surreal_ptr, err_ptr, "surreal_ptr is null");
         check_null!(endpoint, err_ptr, "endpoint is null");
 
-        let res: Result<Result<Surreal, string_t>, _> = catch_unwind(AssertUnwindSafe(|| {
-            let Ok(endpoint) = (unsafe { CStr::from_ptr(endpoint).to_str() }) else {
-                return Err("invalid utf8".into());
-            };
-
-            let Ok(rt) = Runtime::new() else {
-                return Err("error creating runtime".into());
+        connect_with(err_ptr, surreal_ptr, None, || async {
+            let endpoint = unsafe { CStr::from_ptr(endpoint) }.to_str()?;
+            let target = Target {
+                endpoint: endpoint.to_owned(),
+                config: None,
             };
+            Ok((target.connect().await?, target, Session::default()))
+        })
+    }
 
-            let con_fut = any::connect(endpoint);
+    /// Connects to a database and applies connection options
+    ///
+    /// Timeouts and capabilities are applied to the engine, then the connection signs in
+    /// (if `auth_scope` is set), selects the namespace and database, and only then is the
+    /// connection handed back. If `strict` is set the database is defined as STRICT when it
+    /// does not exist yet.
+    ///
+    /// # Safety
+    ///
+    /// - `err_ptr` must be a valid pointer or null (the error is then only available through sr_last_error)
+    /// - `surreal_ptr` must be a valid pointer to receive the connection handle
+    /// - `endpoint` must be a valid null-terminated UTF-8 string
+    /// - `options` must be a valid pointer to connect options with `version` set
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_string_t err;
+    /// sr_surreal_t *db;
+    /// sr_credentials_scope scope = ROOT;
+    /// sr_credentials creds = { "root", "root" };
+    ///
+    /// sr_connect_options_t opts = {0};
+    /// opts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+    /// opts.query_timeout = (sr_duration_t){ .secs = 2, .nanos = 500000000 };
+    /// opts.namespace_ = "test";
+    /// opts.database = "test";
+    /// opts.auth_scope = &scope;
+    /// opts.credentials = &creds;
+    ///
+    /// if (sr_connect_with_options(&err, &db, "ws://localhost:8000", &opts) < 0) {
+    ///     printf("error connecting to db: %s\n", err);
+    ///     return 1;
+    /// }
+    ///
+    /// sr_surreal_disconnect(db);
+    /// ```
+    #[export_name = "sr_connect_with_options"]
+    pub extern "C" fn connect_with_options(
+        err_ptr: *mut string_t,
+        surreal_ptr: *mut *mut Surreal,
+        endpoint: *const c_char,
+        options: *const ConnectOptions,
+    ) -> c_int {
+        check_null!(surreal_ptr, err_ptr, "surreal_ptr is null");
+        check_null!(endpoint, err_ptr, "endpoint is null");
+        check_null!(options, err_ptr, "options is null");
+
+        let options = unsafe { &*options };
+        connect_with(err_ptr, surreal_ptr, options.runtime(), || async {
+            if options.version == 0 || options.version > SR_CONNECT_OPTIONS_VERSION {
+                return Err(SurrealError::invalid_argument(format!(
+                    "unsupported connect options version: {}",
+                    options.version
+                )));
+            }
 
-            let db = match rt.block_on(con_fut.into_future()) {
-                Ok(db) => db,
-                Err(e) => return Err(e.to_string().into()),
+            let endpoint = unsafe { CStr::from_ptr(endpoint) }.to_str()?;
+            let target = Target {
+                endpoint: endpoint.to_owned(),
+                config: Some(options.config()?),
             };
+            let db = target.connect().await?;
+            let session = apply_connect_options(&db, options).await?;
+            Ok((db, target, session))
+        })
+    }
 
-            Ok(Surreal {
-                db,
-                rt,
-                ps: AtomicBool::new(false),
+    /// Rebuild the connection on the same endpoint, clearing poisoning
+    ///
+    /// Opens a new connection with the endpoint and options the connection was created with,
+    /// then replaces the old one. Operations already in flight complete on the old connection.
+    /// The configuration passed to `sr_connect_with_options` is kept, and the session is restored on the new
+    /// connection: it is authenticated again with the token of the last signin, signup or authenticate,
+    /// and the selected namespace and database are selected again. Session variables are not restored.
+    ///
+    /// Embedded in-memory engines (`mem://`) start out empty, so all their data is lost, including the
+    /// users a restored token may refer to.
+    ///
+    /// Returns SR_NONE on success, in which case the connection is no longer poisoned. If the connection
+    /// was rebuilt but the session could not be restored, the connection is no longer poisoned either,
+    /// but SR_ERROR is returned and the session has to be applied again.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// if (sr_query(db, &err, &res, "RETURN 1", NULL) == sr_SR_FATAL) {
+    ///     sr_free_string(err);
+    ///     if (sr_surreal_reconnect(db, &err) < 0) {
+    ///         printf("%s", err);
+    ///         return 1;
+    ///     }
+    /// }
+    /// ```
+    #[export_name = "sr_surreal_reconnect"]
+    pub extern "C" fn reconnect(db: &Surreal, err_ptr: *mut string_t) -> c_int {
+        if on_runtime() {
+            write_error(err_ptr, SurrealError::blocking_on_runtime());
+            return SR_ERROR;
+        }
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            db.rt.block_on(async {
+                let new = Arc::new(db.target.connect().await?);
+                db.db.store(new.clone());
+                db.ps.store(false, Ordering::Release);
+                let session = db.session().clone();
+                session.restore(&new).await
             })
         }));
-
-        let res: Result<Surreal, string_t> = match res {
-            Ok(r) => r,
-            Err(e) => {
-                if let Some(e_str) = e.downcast_ref::<&str>() {
-                    let e_string: string_t = format!("Panicked with: {e_str}").into();
-                    unsafe { err_ptr.write(e_string) }
-                } else {
-                    unsafe { err_ptr.write("Panicked".into()) }
-                }
-                return SR_FATAL;
-            }
-        };
-
         match res {
-            Ok(s) => {
-                let boxed = Box::new(s);
-                unsafe { surreal_ptr.write(Box::leak(boxed)) }
-                1
+            Ok(Ok(())) => SR_NONE,
+            Ok(Err(e)) => {
+                write_error(err_ptr, e);
+                SR_ERROR
             }
             Err(e) => {
-                unsafe { err_ptr.write(e) }
-                SR_ERROR
+                write_error(err_ptr, SurrealError::panicked(&*e));
+                SR_FATAL
             }
         }
     }
 
+    /// The current underlying connection
+    pub(crate) fn db(&self) -> Arc<sdbSurreal<Any>> {
+        self.db.load_full()
+    }
+
+    /// The session state replayed by `sr_surreal_reconnect`
+    fn session(&self) -> MutexGuard<'_, Session> {
+        self.session.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Cancel all in-flight operations on a connection
+    ///
+    /// May be called from any thread. Every blocking, callback and future based call that is running
+    /// when this is called fails with SR_CANCELLED. Calls started afterwards are not affected, and the
+    /// connection stays usable.
+    ///
+    /// Cancelling an operation stops waiting for it, it does not roll back statements the server
+    /// has already executed.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// // on another thread
+    /// sr_surreal_interrupt(db);
+    /// ```
+    #[export_name = "sr_surreal_interrupt"]
+    pub extern "C" fn interrupt(db: &Surreal) {
+        db.interrupt.trigger();
+    }
+
+    /// Set the default timeout of every operation on a connection
+    ///
+    /// Applies to blocking, callback and future based calls started afterwards, which fail with SR_TIMEOUT
+    /// once `timeout_ms` milliseconds have passed. A timeout bound to the calling thread with `sr_timeout_bind`,
+    /// or passed as the last argument of a `_timeout` sibling, overrides it for a single call.
+    /// A negative `timeout_ms`, the default, disables the timeout.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_surreal_set_timeout(db, 5000);
+    /// if (sr_query(db, &err, &res, "SLEEP 10s", NULL) == sr_SR_TIMEOUT) {
+    ///     printf("%s", err);
+    /// }
+    /// ```
+    #[export_name = "sr_surreal_set_timeout"]
+    pub extern "C" fn set_timeout(db: &Surreal, timeout_ms: c_int) {
+        db.timeout_ms.store(timeout_ms, Ordering::Relaxed);
+    }
+
+    pub(crate) fn default_timeout(&self) -> Option<Duration> {
+        cancel::timeout_from_ms(self.timeout_ms.load(Ordering::Relaxed))
+    }
+
     /// Disconnect a database connection
     ///
     /// The Surreal object must not be used after this function has been called.
@@ -195,7 +381,15 @@ impl Surreal {
     /// ```
     #[export_name = "sr_surreal_disconnect"]
     pub extern "C" fn disconnect(db: *mut Surreal) {
-        catch_unwind(AssertUnwindSafe(|| drop(unsafe { Box::from_raw(db) }))).ok();
+        catch_unwind(AssertUnwindSafe(|| {
+            let boxed = unsafe { Box::from_raw(db) };
+            // the runtime may be shared, so drop the connection inside it
+            let rt = boxed.rt.clone();
+            let guard = rt.enter();
+            drop(boxed);
+            drop(guard);
+        }))
+        .ok();
     }
 
     /// Authenticate with a token
@@ -224,11 +418,32 @@ impl Surreal {
         db: &Surreal,
         err_ptr: *mut string_t,
         token: *const c_char,
+    ) -> c_int {
+        Self::authenticate_impl(db, err_ptr, token)
+    }
+
+    /// Authenticate with a token, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_authenticate` except for the type of `err_ptr`.
+    #[export_name = "sr_authenticate_ex"]
+    pub extern "C" fn authenticate_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        token: *const c_char,
+    ) -> c_int {
+        Self::authenticate_impl(db, err_ptr, token)
+    }
+
+    fn authenticate_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        token: *const c_char,
     ) -> c_int {
         check_null!(token, err_ptr, "token is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let token = unsafe { CStr::from_ptr(token) }.to_str()?;
-            surreal.db.authenticate(token).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().authenticate(token).await?;
+            surreal.session().token = Some(token.to_owned());
             Ok(0)
         })
     }
@@ -250,7 +465,7 @@ impl Surreal {
     #[export_name = "sr_begin"]
     pub extern "C" fn begin(db: &Surreal, err_ptr: *mut string_t) -> c_int {
         with_surreal_async(db, err_ptr, |surreal| async {
-            surreal.db.query("BEGIN TRANSACTION").await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().query("BEGIN TRANSACTION").await?;
             Ok(0)
         })
     }
@@ -272,7 +487,7 @@ impl Surreal {
     #[export_name = "sr_cancel"]
     pub extern "C" fn cancel(db: &Surreal, err_ptr: *mut string_t) -> c_int {
         with_surreal_async(db, err_ptr, |surreal| async {
-            surreal.db.query("CANCEL TRANSACTION").await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().query("CANCEL TRANSACTION").await?;
             Ok(0)
         })
     }
@@ -294,7 +509,7 @@ impl Surreal {
     #[export_name = "sr_commit"]
     pub extern "C" fn commit(db: &Surreal, err_ptr: *mut string_t) -> c_int {
         with_surreal_async(db, err_ptr, |surreal| async {
-            surreal.db.query("COMMIT TRANSACTION").await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().query("COMMIT TRANSACTION").await?;
             Ok(0)
         })
     }
@@ -319,6 +534,30 @@ impl Surreal {
         res_ptr: *mut &mut Object,
         resource: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::create_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    /// Create a record, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_create` except for the type of `err_ptr`.
+    #[export_name = "sr_create_ex"]
+    pub extern "C" fn create_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut &mut Object,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::create_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    fn create_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut &mut Object,
+        resource: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(resource, err_ptr, "resource is null");
         check_null!(content, err_ptr, "content is null");
@@ -326,31 +565,7 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let content = sdbObject::from(unsafe { &*content }.clone());
 
-            let query = format!("CREATE {} CONTENT $content", resource);
-            let mut res = surreal.db.query(&query).bind(("content", content)).await
-                .map_err(|e| string_t::from(e.to_string()))?;
-            
-            let val: sdbValue = res.take(0).map_err(|e| string_t::from(e.to_string()))?;
-            let obj = match val {
-                sdbValue::Array(arr) if !arr.is_empty() => {
-                    match arr.into_iter().next().unwrap() {
-                        sdbValue::Object(o) => o,
-                        other => {
-                            return Err(format!(
-                                "Expected object as return type of create, but found: {other:?}"
-                            )
-                            .into())
-                        }
-                    }
-                }
-                sdbValue::Object(o) => o,
-                other => {
-                    return Err(format!(
-                        "Expected object as return type of create, but found: {other:?}"
-                    )
-                    .into())
-                }
-            };
+            let obj = ops::create(&surreal.db(), resource, content).await?;
             if !res_ptr.is_null() {
                 let boxed = Box::new(Object::from(obj));
                 unsafe { res_ptr.write(Box::leak(boxed)) }
@@ -391,24 +606,37 @@ impl Surreal {
         err_ptr: *mut string_t,
         res_ptr: *mut *mut Value,
         resource: *const c_char,
+    ) -> c_int {
+        Self::delete_impl(db, err_ptr, res_ptr, resource)
+    }
+
+    /// Delete a record or records, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_delete` except for the type of `err_ptr`.
+    #[export_name = "sr_delete_ex"]
+    pub extern "C" fn delete_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+    ) -> c_int {
+        Self::delete_impl(db, err_ptr, res_ptr, resource)
+    }
+
+    fn delete_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(resource, err_ptr, "resource is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
 
-            let val: sdbValue = surreal
-                .db
-                .delete(parse_resource(resource))
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::delete(&surreal.db(), resource).await?;
 
-            let ArrayGen { ptr, len } = res.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -444,7 +672,7 @@ impl Surreal {
         check_null!(file_path, err_ptr, "file_path is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let file_path = unsafe { CStr::from_ptr(file_path) }.to_str()?;
-            surreal.db.export(file_path).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().export(file_path).await?;
             Ok(0)
         })
     }
@@ -465,8 +693,21 @@ impl Surreal {
     /// ```
     #[export_name = "sr_health"]
     pub extern "C" fn health(db: &Surreal, err_ptr: *mut string_t) -> c_int {
-        with_surreal_async(db, err_ptr, |surreal| async {
-            surreal.db.health().await.map_err(|e| string_t::from(e.to_string()))?;
+        Self::health_impl(db, err_ptr, None)
+    }
+
+    /// Check if the database is healthy, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+    ///
+    /// Identical to `sr_health` apart from the extra `timeout_ms`, which overrides the connection's default
+    /// timeout. A negative `timeout_ms` waits indefinitely.
+    #[export_name = "sr_health_timeout"]
+    pub extern "C" fn health_timeout(db: &Surreal, err_ptr: *mut string_t, timeout_ms: c_int) -> c_int {
+        Self::health_impl(db, err_ptr, Some(cancel::timeout_from_ms(timeout_ms)))
+    }
+
+    fn health_impl(db: &Surreal, err_ptr: *mut string_t, timeout: Option<Option<Duration>>) -> c_int {
+        with_surreal_guarded(db, err_ptr, None, timeout, |surreal| async {
+            surreal.db().health().await?;
             Ok(0)
         })
     }
@@ -500,7 +741,7 @@ impl Surreal {
         check_null!(file_path, err_ptr, "file_path is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let file_path = unsafe { CStr::from_ptr(file_path) }.to_str()?;
-            surreal.db.import(file_path).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().import(file_path).await?;
             Ok(0)
         })
     }
@@ -538,6 +779,30 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         resource: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::insert_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    /// Insert one or more records, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_insert` except for the type of `err_ptr`.
+    #[export_name = "sr_insert_ex"]
+    pub extern "C" fn insert_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::insert_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    fn insert_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(resource, err_ptr, "resource is null");
@@ -546,19 +811,143 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let content = sdbObject::from(unsafe { &*content }.clone());
 
-            let val: sdbValue = surreal
-                .db
-                .insert(parse_resource(resource))
-                .content(content)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
+            let val = ops::insert(&surreal.db(), resource, content).await?;
 
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len as c_int)
+        })
+    }
+
+    /// Insert many records into a table in a single call
+    ///
+    /// Inserts `count` rows from the `rows` array into `table`. Rows without an `id` field get a
+    /// generated one. By default the call fails if any record already exists; `flags` changes this:
+    ///
+    /// - SR_INSERT_IGNORE skips rows whose record already exists
+    /// - SR_INSERT_UPDATE updates existing records with the fields of the row, fields absent or null
+    ///   in the row keep their current value
+    /// - SR_INSERT_DISCARD does not return the records, `res_ptr` may then be null and 0 is returned
+    ///
+    /// Returns the number of records written to *res_ptr, which must be freed with sr_free_arr.
+    /// Rows skipped by SR_INSERT_IGNORE are not returned.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `res_ptr` must be a valid pointer to receive the result array, or null with SR_INSERT_DISCARD
+    /// - `table` must be a valid null-terminated UTF-8 string
+    /// - `rows` must point to `count` objects, or be null if `count` is 0
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_object_t rows[1000];
+    /// for (int i = 0; i < 1000; i++) {
+    ///     rows[i] = sr_object_new();
+    ///     sr_object_insert_int(&rows[i], "n", i);
+    /// }
+    /// if (sr_insert_many(db, &err, NULL, "reading", rows, 1000, sr_SR_INSERT_DISCARD) < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// ```
+    #[export_name = "sr_insert_many"]
+    pub extern "C" fn insert_many(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        table: *const c_char,
+        rows: *const Object,
+        count: c_int,
+        flags: c_int,
+    ) -> c_int {
+        let rows = match (rows.is_null(), usize::try_from(count)) {
+            (_, Ok(0)) => Vec::new(),
+            (false, Ok(count)) => unsafe { std::slice::from_raw_parts(rows, count) }
+                .iter()
+                .map(|row| sdbObject::from(row.clone()))
+                .collect(),
+            (true, _) => {
+                write_error(err_ptr, SurrealError::invalid_argument("rows is null"));
+                return SR_ERROR;
+            }
+            (false, Err(_)) => {
+                write_error(err_ptr, SurrealError::invalid_argument("count is negative"));
+                return SR_ERROR;
+            }
+        };
+        Self::insert_many_impl(db, err_ptr, res_ptr, table, rows, flags)
+    }
+
+    /// Insert many records into a table from an array of objects
+    ///
+    /// Identical to `sr_insert_many` except that the rows are given as an array, every element of
+    /// which must be an object.
+    #[export_name = "sr_insert_many_arr"]
+    pub extern "C" fn insert_many_arr(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        table: *const c_char,
+        rows: *const Array,
+        flags: c_int,
+    ) -> c_int {
+        check_null!(rows, err_ptr, "rows is null");
+        let mut objects = Vec::new();
+        for (i, row) in unsafe { &*rows }.as_slice().iter().enumerate() {
+            match row {
+                Value::SR_VALUE_OBJECT(obj) => objects.push(sdbObject::from(obj.clone())),
+                _ => {
+                    write_error(err_ptr, SurrealError::invalid_argument(format!("row {i} is not an object")));
+                    return SR_ERROR;
+                }
+            }
+        }
+        Self::insert_many_impl(db, err_ptr, res_ptr, table, objects, flags)
+    }
+
+    fn insert_many_impl(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        table: *const c_char,
+        rows: Vec<sdbObject>,
+        flags: c_int,
+    ) -> c_int {
+        let discard = flags & SR_INSERT_DISCARD != 0;
+        if !discard {
+            check_null!(res_ptr, err_ptr, "res_ptr is null");
+        }
+        check_null!(table, err_ptr, "table is null");
+        let on_duplicate = match (flags & SR_INSERT_IGNORE != 0, flags & SR_INSERT_UPDATE != 0) {
+            (false, false) => ops::OnDuplicate::Fail,
+            (true, false) => ops::OnDuplicate::Ignore,
+            (false, true) => ops::OnDuplicate::Update,
+            (true, true) => {
+                let msg = "SR_INSERT_IGNORE and SR_INSERT_UPDATE can not be combined";
+                write_error(err_ptr, SurrealError::invalid_argument(msg));
+                return SR_ERROR;
+            }
+        };
+        if rows.is_empty() {
+            if !res_ptr.is_null() {
+                unsafe { res_ptr.write(std::ptr::null_mut()) }
+            }
+            return 0;
+        }
+
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let table = unsafe { CStr::from_ptr(table) }.to_str()?;
+
+            let val = ops::insert_many(&surreal.db(), table, rows, on_duplicate, discard).await?;
+            if discard {
+                return Ok(0);
+            }
 
-            let ArrayGen { ptr, len } = res.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -613,11 +1002,10 @@ impl Surreal {
             let content = sdbObject::from(unsafe { &*content }.clone());
 
             let val: sdbValue = surreal
-                .db
+                .db()
                 .insert(Resource::from(table))
                 .relation(content)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
+                .await?;
 
             let result = match val {
                 sdbValue::Array(a) => Array::from(a),
@@ -663,12 +1051,53 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         function_name: *const c_char,
         args: *const Array,
+    ) -> c_int {
+        Self::run_impl(db, err_ptr, res_ptr, function_name, args, None, None)
+    }
+
+    /// Execute a SurrealDB function, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_run` except for the type of `err_ptr`.
+    #[export_name = "sr_run_ex"]
+    pub extern "C" fn run_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        function_name: *const c_char,
+        args: *const Array,
+    ) -> c_int {
+        Self::run_impl(db, err_ptr, res_ptr, function_name, args, None, None)
+    }
+
+    /// Execute a SurrealDB function, aborting with SR_CANCELLED once `token` is cancelled
+    ///
+    /// Identical to `sr_run` apart from the extra `token`, which may be null.
+    #[export_name = "sr_run_cancellable"]
+    pub extern "C" fn run_cancellable(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        token: *const CancelToken,
+        res_ptr: *mut *mut Value,
+        function_name: *const c_char,
+        args: *const Array,
+    ) -> c_int {
+        Self::run_impl(db, err_ptr, res_ptr, function_name, args, unsafe { token.as_ref() }, None)
+    }
+
+    fn run_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        function_name: *const c_char,
+        args: *const Array,
+        token: Option<&CancelToken>,
+        timeout: Option<Option<Duration>>,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(function_name, err_ptr, "function_name is null");
-        with_surreal_async(db, err_ptr, |surreal| async {
+        with_surreal_guarded(db, err_ptr, token, timeout, |surreal| async {
             let function_name = unsafe { CStr::from_ptr(function_name) }.to_str()?;
-            
+
             let args_vec: Vec<sdbValue> = if args.is_null() {
                 vec![]
             } else {
@@ -676,19 +1105,9 @@ impl Surreal {
                 arr.as_slice().iter().cloned().map(|v| sdbValue::from(v)).collect()
             };
 
-            let res: sdbValue = surreal
-                .db
-                .run(function_name)
-                .args(args_vec)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
+            let val = ops::run(&surreal.db(), function_name, args_vec).await?;
 
-            let result_arr = match res {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
-
-            let ArrayGen { ptr, len } = result_arr.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -732,6 +1151,34 @@ impl Surreal {
         relation: *const c_char,
         to: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::relate_impl(db, err_ptr, res_ptr, from, relation, to, content)
+    }
+
+    /// Create a graph relation between two records, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_relate` except for the type of `err_ptr`.
+    #[export_name = "sr_relate_ex"]
+    pub extern "C" fn relate_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        from: *const c_char,
+        relation: *const c_char,
+        to: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::relate_impl(db, err_ptr, res_ptr, from, relation, to, content)
+    }
+
+    fn relate_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        from: *const c_char,
+        relation: *const c_char,
+        to: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(from, err_ptr, "from is null");
@@ -741,29 +1188,14 @@ impl Surreal {
             let from = unsafe { CStr::from_ptr(from) }.to_str()?;
             let relation = unsafe { CStr::from_ptr(relation) }.to_str()?;
             let to = unsafe { CStr::from_ptr(to) }.to_str()?;
-
-            let query = if content.is_null() {
-                format!("RELATE {from}->{relation}->{to}")
-            } else {
-                format!("RELATE {from}->{relation}->{to} CONTENT $content")
+            let content = match content.is_null() {
+                true => None,
+                false => Some(sdbObject::from(unsafe { &*content }.clone())),
             };
 
-            let mut q = surreal.db.query(&query);
-            
-            if !content.is_null() {
-                let content_obj = sdbObject::from(unsafe { &*content }.clone());
-                q = q.bind(("content", content_obj));
-            }
-
-            let mut res = q.await.map_err(|e| string_t::from(e.to_string()))?;
-            
-            let val: sdbValue = res.take(0).map_err(|e| string_t::from(e.to_string()))?;
-            let result = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::relate(&surreal.db(), from, relation, to, content).await?;
 
-            let ArrayGen { ptr, len } = result.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -787,7 +1219,8 @@ impl Surreal {
     #[export_name = "sr_invalidate"]
     pub extern "C" fn invalidate(db: &Surreal, err_ptr: *mut string_t) -> c_int {
         with_surreal_async(db, err_ptr, |surreal| async {
-            surreal.db.invalidate().await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().invalidate().await?;
+            surreal.session().token = None;
             Ok(0)
         })
     }
@@ -819,7 +1252,7 @@ impl Surreal {
         with_surreal_async(db, err_ptr, |surreal| async {
             let uuid_str = unsafe { CStr::from_ptr(query_id) }.to_str()?;
             let query = format!("KILL u'{}'", uuid_str);
-            surreal.db.query(query).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().query(query).await?;
             Ok(0)
         })
     }
@@ -866,8 +1299,111 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
 
             let stream_inner: sdbStreamType<sdbValue> =
-                surreal.db.select(parse_resource(resource)).live().await
-                    .map_err(|e| string_t::from(e.to_string()))?;
+                surreal.db().select(parse_resource(resource)).live().await?;
+
+            let stream_boxed = Box::new(Stream::new(stream_inner, surreal.rt.handle().clone()));
+
+            unsafe { stream_ptr.write(Box::leak(stream_boxed)) };
+
+            Ok(1)
+        })
+    }
+
+    /// Start a live query from a `LIVE SELECT` statement
+    ///
+    /// Unlike `sr_select_live` the statement may filter and project the records with `WHERE`,
+    /// `FETCH` and parameters, which are evaluated by the server. The query must hold exactly
+    /// one `LIVE SELECT` statement, otherwise it fails before anything is sent to the server.
+    /// On success sets *stream_ptr to a Stream, consumed like the one of `sr_select_live`.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `stream_ptr` must be a valid pointer to receive the stream
+    /// - `query` must be a valid null-terminated UTF-8 string
+    /// - `vars` may be null (no variables bound)
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_stream_t *stream;
+    /// if (sr_live_query(db, &err, &stream, "LIVE SELECT * FROM order WHERE status = $s", vars) < 0)
+    /// {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// sr_stream_kill(stream);
+    /// ```
+    #[export_name = "sr_live_query"]
+    pub extern "C" fn live_query(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        stream_ptr: *mut &mut Stream,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        check_null!(stream_ptr, err_ptr, "stream_ptr is null");
+        check_null!(query, err_ptr, "query is null");
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let query = unsafe { CStr::from_ptr(query) }.to_str()?;
+            let vars: sdbObject = match vars.is_null() {
+                true => sdbObject::default(),
+                false => unsafe { &*vars }.clone().into(),
+            };
+
+            let stream_inner = ops::live_query(&surreal.db(), query, vars).await?;
+
+            let stream_boxed = Box::new(Stream::new(stream_inner, surreal.rt.handle().clone()));
+
+            unsafe { stream_ptr.write(Box::leak(stream_boxed)) };
+
+            Ok(1)
+        })
+    }
+
+    /// Make a live selection whose notifications hold the changes of the records
+    ///
+    /// Equivalent to `LIVE SELECT DIFF FROM resource`. Notifications are read with `sr_stream_next_diff`,
+    /// which returns the changes as patch operations to apply with `sr_value_apply_diff`.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `stream_ptr` must be a valid pointer to receive the stream
+    /// - `resource` must be a valid null-terminated UTF-8 string
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_stream_t *stream;
+    /// if (sr_select_live_diff(db, &err, &stream, "foo") < 0)
+    /// {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    ///
+    /// sr_diff_notification_t not;
+    /// if (sr_stream_next_diff(stream, &not) > 0)
+    /// {
+    ///     sr_free_diff_notification(not);
+    /// }
+    /// sr_stream_kill(stream);
+    /// ```
+    #[export_name = "sr_select_live_diff"]
+    pub extern "C" fn select_live_diff(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        stream_ptr: *mut &mut Stream,
+        resource: *const c_char,
+    ) -> c_int {
+        check_null!(stream_ptr, err_ptr, "stream_ptr is null");
+        check_null!(resource, err_ptr, "resource is null");
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
+
+            let stream_inner = ops::live_diff(&surreal.db(), resource).await?;
 
             let stream_boxed = Box::new(Stream::new(stream_inner, surreal.rt.handle().clone()));
 
@@ -910,6 +1446,30 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         resource: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::merge_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    /// Merge data into existing records, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_merge` except for the type of `err_ptr`.
+    #[export_name = "sr_merge_ex"]
+    pub extern "C" fn merge_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::merge_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    fn merge_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(resource, err_ptr, "resource is null");
@@ -918,19 +1478,9 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let content = sdbObject::from(unsafe { &*content }.clone());
 
-            let val: sdbValue = surreal
-                .db
-                .update(parse_resource(resource))
-                .merge(content)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::merge(&surreal.db(), resource, content).await?;
 
-            let ArrayGen { ptr, len } = res.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -982,19 +1532,9 @@ impl Surreal {
             let path = unsafe { CStr::from_ptr(path) }.to_str()?;
             let value: sdbValue = unsafe { &*value }.clone().into();
 
-            let val: sdbValue = surreal
-                .db
-                .update(parse_resource(resource))
-                .patch(PatchOp::add(path, value))
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let result = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::patch(&surreal.db(), resource, PatchOp::add(path, value)).await?;
 
-            let ArrayGen { ptr, len } = result.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -1039,19 +1579,9 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let path = unsafe { CStr::from_ptr(path) }.to_str()?;
 
-            let val: sdbValue = surreal
-                .db
-                .update(parse_resource(resource))
-                .patch(PatchOp::remove(path))
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let result = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::patch(&surreal.db(), resource, PatchOp::remove(path)).await?;
 
-            let ArrayGen { ptr, len } = result.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -1101,19 +1631,9 @@ impl Surreal {
             let path = unsafe { CStr::from_ptr(path) }.to_str()?;
             let value: sdbValue = unsafe { &*value }.clone().into();
 
-            let val: sdbValue = surreal
-                .db
-                .update(parse_resource(resource))
-                .patch(PatchOp::replace(path, value))
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let result = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+            let val = ops::patch(&surreal.db(), resource, PatchOp::replace(path, value)).await?;
 
-            let ArrayGen { ptr, len } = result.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -1136,44 +1656,122 @@ impl Surreal {
         res_ptr: *mut *mut ArrayResult,
         query: *const c_char,
         vars: *const Object,
+    ) -> c_int {
+        Self::query_impl(db, err_ptr, res_ptr, query, vars, None, None)
+    }
+
+    /// Execute a SurrealQL query, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_query` except for the type of `err_ptr`.
+    #[export_name = "sr_query_ex"]
+    pub extern "C" fn query_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut ArrayResult,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        Self::query_impl(db, err_ptr, res_ptr, query, vars, None, None)
+    }
+
+    /// Execute a SurrealQL query, aborting with SR_CANCELLED once `token` is cancelled
+    ///
+    /// Identical to `sr_query` apart from the extra `token`, which may be null.
+    #[export_name = "sr_query_cancellable"]
+    pub extern "C" fn query_cancellable(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        token: *const CancelToken,
+        res_ptr: *mut *mut ArrayResult,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        Self::query_impl(db, err_ptr, res_ptr, query, vars, unsafe { token.as_ref() }, None)
+    }
+
+    /// Execute a SurrealQL query, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+    ///
+    /// Identical to `sr_query` apart from the extra `timeout_ms`, which overrides the connection's default
+    /// timeout. A negative `timeout_ms` waits indefinitely.
+    #[export_name = "sr_query_timeout"]
+    pub extern "C" fn query_timeout(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut ArrayResult,
+        query: *const c_char,
+        vars: *const Object,
+        timeout_ms: c_int,
+    ) -> c_int {
+        Self::query_impl(db, err_ptr, res_ptr, query, vars, None, Some(cancel::timeout_from_ms(timeout_ms)))
+    }
+
+    fn query_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut ArrayResult,
+        query: *const c_char,
+        vars: *const Object,
+        token: Option<&CancelToken>,
+        timeout: Option<Option<Duration>>,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(query, err_ptr, "query is null");
-        with_surreal_async(db, err_ptr, |surreal| async {
+        with_surreal_guarded(db, err_ptr, token, timeout, |surreal| async {
             let query = unsafe { CStr::from_ptr(query) }.to_str()?;
             let vars: sdbObject = match vars.is_null() {
                 true => sdbObject::default(),
                 false => unsafe { &*vars }.clone().into(),
             };
 
-            let mut res = surreal.db.query(query).bind(vars).await
-                .map_err(|e| string_t::from(e.to_string()))?;
-            let res_len = res.num_statements();
-
-            let mut acc = Vec::with_capacity(res_len);
-            for index in 0..res_len {
-                let api_res: Result<sdbValue, _> = res.take(index);
-                let arr_res = match api_res {
-                    Ok(sdbValue::Array(arr)) => {
-                        let a = arr.into();
-                        ArrayResult::ok(a)
-                    }
-                    Ok(val) => {
-                        let arr: Array = vec![Value::from(val)].into();
-                        ArrayResult::ok(arr)
-                    }
-                    Err(e) => ArrayResult::err(e.to_string()),
-                };
-                acc.push(arr_res);
-            }
+            let results = ops::query(&surreal.db(), query, vars).await?;
 
-            let ArrayGen { ptr, len } = acc.make_array();
+            let ArrayGen { ptr, len } = ops::into_results(results).make_array();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len)
         })
     }
 
+    /// Execute a SurrealQL query, returning a response object
+    ///
+    /// Writes a response to *res_ptr holding each statement's value as returned, its error and its
+    /// execution time, and returns the number of statements. A failing statement does not fail the call.
+    /// The response must be freed with sr_response_free.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `res_ptr` must be a valid pointer to receive the response
+    /// - `query` must be a valid null-terminated UTF-8 string
+    /// - `vars` may be null (no variables bound)
+    #[export_name = "sr_query_response"]
+    pub extern "C" fn query_response(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Response,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        check_null!(res_ptr, err_ptr, "res_ptr is null");
+        check_null!(query, err_ptr, "query is null");
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let query = unsafe { CStr::from_ptr(query) }.to_str()?;
+            let vars: sdbObject = match vars.is_null() {
+                true => sdbObject::default(),
+                false => unsafe { &*vars }.clone().into(),
+            };
+
+            let results = ops::query_with_stats(&surreal.db(), query, vars).await?;
+
+            let resp = Response::new(results);
+            let count = resp.count();
+            unsafe { res_ptr.write(Box::into_raw(Box::new(resp))) }
+
+            Ok(count)
+        })
+    }
+
     /// Select a resource
     ///
     /// Selects records from the specified resource (table or record ID).
@@ -1213,30 +1811,74 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         resource: *const c_char,
     ) -> c_int {
-        check_null!(res_ptr, err_ptr, "res_ptr is null");
-        check_null!(resource, err_ptr, "resource is null");
-        with_surreal_async(db, err_ptr, |surreal| async {
-            let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
-
-            let val: sdbValue = surreal
-                .db
-                .select(parse_resource(resource))
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
-
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
+        Self::select_impl(db, err_ptr, res_ptr, resource, None, None)
+    }
 
-            let ArrayGen { ptr, len } = res.into();
-            unsafe { res_ptr.write(ptr) }
+    /// Select a resource, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_select` except for the type of `err_ptr`.
+    #[export_name = "sr_select_ex"]
+    pub extern "C" fn select_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+    ) -> c_int {
+        Self::select_impl(db, err_ptr, res_ptr, resource, None, None)
+    }
 
-            Ok(len as c_int)
-        })
+    /// Select a resource, aborting with SR_CANCELLED once `token` is cancelled
+    ///
+    /// Identical to `sr_select` apart from the extra `token`, which may be null.
+    #[export_name = "sr_select_cancellable"]
+    pub extern "C" fn select_cancellable(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        token: *const CancelToken,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+    ) -> c_int {
+        Self::select_impl(db, err_ptr, res_ptr, resource, unsafe { token.as_ref() }, None)
     }
 
-    /// Set a variable for the current session
+    /// Select a resource, failing with SR_TIMEOUT once `timeout_ms` milliseconds have passed
+    ///
+    /// Identical to `sr_select` apart from the extra `timeout_ms`, which overrides the connection's default
+    /// timeout. A negative `timeout_ms` waits indefinitely.
+    #[export_name = "sr_select_timeout"]
+    pub extern "C" fn select_timeout(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        timeout_ms: c_int,
+    ) -> c_int {
+        Self::select_impl(db, err_ptr, res_ptr, resource, None, Some(cancel::timeout_from_ms(timeout_ms)))
+    }
+
+    fn select_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        token: Option<&CancelToken>,
+        timeout: Option<Option<Duration>>,
+    ) -> c_int {
+        check_null!(res_ptr, err_ptr, "res_ptr is null");
+        check_null!(resource, err_ptr, "resource is null");
+        with_surreal_guarded(db, err_ptr, token, timeout, |surreal| async {
+            let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
+
+            let val = ops::select(&surreal.db(), resource).await?;
+
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len as c_int)
+        })
+    }
+
+    /// Set a variable for the current session
     ///
     /// Defines a session variable that can be referenced in queries.
     ///
@@ -1271,7 +1913,7 @@ impl Surreal {
             let key = unsafe { CStr::from_ptr(key) }.to_str()?;
             let value: sdbValue = unsafe { &*value }.clone().into();
             
-            surreal.db.set(key, value).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().set(key, value).await?;
             Ok(0)
         })
     }
@@ -1351,112 +1993,38 @@ impl Surreal {
         details: *const credentials_access,
         params: *const Object,
     ) -> c_int {
-        with_surreal_async(db, err_ptr, |surreal| async {
-            let mut user = "";
-            let mut pass = "";
-            
-            if !creds.is_null() {
-                let creds = unsafe { &*creds };
-                if !creds.username.0.is_null() {
-                    user = unsafe { CStr::from_ptr(creds.username.0).to_str()? };
-                }
-                if !creds.password.0.is_null() {
-                    pass = unsafe { CStr::from_ptr(creds.password.0).to_str()? };
-                }
-            }
-
-            let mut ns = "";
-            let mut db_name = "";
-            let mut ac = "";
-
-            if !details.is_null() {
-                let details = unsafe { &*details };
-
-                if !details.namespace.0.is_null() {
-                    ns = unsafe { CStr::from_ptr(details.namespace.0).to_str()? };
-                }
-
-                if !details.database.0.is_null() {
-                    db_name = unsafe { CStr::from_ptr(details.database.0).to_str()? };
-                }
-
-                if !details.access.0.is_null() {
-                    ac = unsafe { CStr::from_ptr(details.access.0).to_str()? };
-                }
-            }
-
-            let token: String = match scope {
-                credentials_scope::ROOT => {
-                    let login = auth::Root {
-                        username: user.to_string(),
-                        password: pass.to_string(),
-                    };
-                    let jwt = surreal.db.signin(login).await.map_err(|e| string_t::from(e.to_string()))?;
-                    jwt.access.into_insecure_token()
-                }
-                credentials_scope::NAMESPACE => {
-                    if ns.is_empty() {
-                        return Err("Namespace must be provided.".into());
-                    }
-
-                    let login = auth::Namespace {
-                        namespace: ns.to_string(),
-                        username: user.to_string(),
-                        password: pass.to_string(),
-                    };
-
-                    let jwt = surreal.db.signin(login).await.map_err(|e| string_t::from(e.to_string()))?;
-                    jwt.access.into_insecure_token()
-                }
-                credentials_scope::DATABASE => {
-                    if ns.is_empty() {
-                        return Err("Namespace must be provided.".into());
-                    }
-                    if db_name.is_empty() {
-                        return Err("Database must be provided.".into());
-                    }
-
-                    let login = auth::Database {
-                        namespace: ns.to_string(),
-                        database: db_name.to_string(),
-                        username: user.to_string(),
-                        password: pass.to_string(),
-                    };
-
-                    let jwt = surreal.db.signin(login).await.map_err(|e| string_t::from(e.to_string()))?;
-                    jwt.access.into_insecure_token()
-                }
-                credentials_scope::RECORD => {
-                    if ns.is_empty() {
-                        return Err("Namespace must be provided.".into());
-                    }
-                    if db_name.is_empty() {
-                        return Err("Database must be provided.".into());
-                    }
-                    if ac.is_empty() {
-                        return Err("Access method must be provided.".into());
-                    }
-
-                    let record_params: sdbObject = if !params.is_null() {
-                        unsafe { &*params }.clone().into()
-                    } else {
-                        let mut obj = sdbObject::default();
-                        obj.insert("username".to_string(), sdbValue::String(user.to_string()));
-                        obj.insert("password".to_string(), sdbValue::String(pass.to_string()));
-                        obj
-                    };
+        Self::signin_impl(db, err_ptr, token_ptr, scope, creds, details, params)
+    }
 
-                    let login = auth::Record {
-                        namespace: ns.to_string(),
-                        database: db_name.to_string(),
-                        access: ac.to_string(),
-                        params: record_params,
-                    };
+    /// Sign in utilizing the surreal authentication types, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_signin` except for the type of `err_ptr`.
+    #[export_name = "sr_signin_ex"]
+    pub extern "C" fn signin_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        token_ptr: *mut string_t,
+        scope: &credentials_scope,
+        creds: *const credentials::credentials,
+        details: *const credentials_access,
+        params: *const Object,
+    ) -> c_int {
+        Self::signin_impl(db, err_ptr, token_ptr, scope, creds, details, params)
+    }
 
-                    let jwt = surreal.db.signin(login).await.map_err(|e| string_t::from(e.to_string()))?;
-                    jwt.access.into_insecure_token()
-                }
-            };
+    fn signin_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        token_ptr: *mut string_t,
+        scope: &credentials_scope,
+        creds: *const credentials::credentials,
+        details: *const credentials_access,
+        params: *const Object,
+    ) -> c_int {
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let (ns, db_name, ac) = read_access(details)?;
+            let token = signin_with(&surreal.db(), scope, creds, ns, db_name, ac, params).await?;
+            surreal.session().token = Some(token.clone());
             
             if !token_ptr.is_null() {
                 unsafe { *token_ptr = token.to_string_t(); }
@@ -1519,6 +2087,34 @@ impl Surreal {
         creds: *const credentials::credentials,
         details: *const credentials_access,
         params: *const Object,
+    ) -> c_int {
+        Self::signup_impl(db, err_ptr, token_ptr, scope, creds, details, params)
+    }
+
+    /// Sign up a new user with credentials, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_signup` except for the type of `err_ptr`.
+    #[export_name = "sr_signup_ex"]
+    pub extern "C" fn signup_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        token_ptr: *mut string_t,
+        scope: &credentials_scope,
+        creds: *const credentials::credentials,
+        details: *const credentials_access,
+        params: *const Object,
+    ) -> c_int {
+        Self::signup_impl(db, err_ptr, token_ptr, scope, creds, details, params)
+    }
+
+    fn signup_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        token_ptr: *mut string_t,
+        scope: &credentials_scope,
+        creds: *const credentials::credentials,
+        details: *const credentials_access,
+        params: *const Object,
     ) -> c_int {
         with_surreal_async(db, err_ptr, |surreal| async {
             let mut user = "";
@@ -1556,23 +2152,23 @@ impl Surreal {
 
             let token: String = match scope {
                 credentials_scope::ROOT => {
-                    return Err("Cannot signup as ROOT user".into());
+                    return Err(SurrealError::invalid_argument("Cannot signup as ROOT user"));
                 }
                 credentials_scope::NAMESPACE => {
-                    return Err("Namespace scope does not support signup. Use RECORD scope instead.".into());
+                    return Err(SurrealError::invalid_argument("Namespace scope does not support signup. Use RECORD scope instead."));
                 }
                 credentials_scope::DATABASE => {
-                    return Err("Database scope does not support signup. Use RECORD scope instead.".into());
+                    return Err(SurrealError::invalid_argument("Database scope does not support signup. Use RECORD scope instead."));
                 }
                 credentials_scope::RECORD => {
                     if ns.is_empty() {
-                        return Err("Namespace must be provided.".into());
+                        return Err(SurrealError::invalid_argument("Namespace must be provided."));
                     }
                     if db_name.is_empty() {
-                        return Err("Database must be provided.".into());
+                        return Err(SurrealError::invalid_argument("Database must be provided."));
                     }
                     if ac.is_empty() {
-                        return Err("Access method must be provided.".into());
+                        return Err(SurrealError::invalid_argument("Access method must be provided."));
                     }
 
                     let record_params: sdbObject = if !params.is_null() {
@@ -1591,10 +2187,11 @@ impl Surreal {
                         params: record_params,
                     };
 
-                    let jwt = surreal.db.signup(signup).await.map_err(|e| string_t::from(e.to_string()))?;
+                    let jwt = surreal.db().signup(signup).await?;
                     jwt.access.into_insecure_token()
                 }
             };
+            surreal.session().token = Some(token.clone());
             
             if !token_ptr.is_null() {
                 unsafe { *token_ptr = token.to_string_t(); }
@@ -1633,7 +2230,7 @@ impl Surreal {
         check_null!(key, err_ptr, "key is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let key = unsafe { CStr::from_ptr(key) }.to_str()?;
-            surreal.db.unset(key).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().unset(key).await?;
             Ok(0)
         })
     }
@@ -1671,6 +2268,30 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         resource: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::update_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    /// Update records with new content, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_update` except for the type of `err_ptr`.
+    #[export_name = "sr_update_ex"]
+    pub extern "C" fn update_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::update_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    fn update_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(resource, err_ptr, "resource is null");
@@ -1679,19 +2300,9 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let content = sdbObject::from(unsafe { &*content }.clone());
 
-            let val: sdbValue = surreal
-                .db
-                .update(parse_resource(resource))
-                .content(content)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
+            let val = ops::update(&surreal.db(), resource, content).await?;
 
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
-
-            let ArrayGen { ptr, len } = res.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -1731,6 +2342,30 @@ impl Surreal {
         res_ptr: *mut *mut Value,
         resource: *const c_char,
         content: *const Object,
+    ) -> c_int {
+        Self::upsert_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    /// Upsert (insert or update) records, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_upsert` except for the type of `err_ptr`.
+    #[export_name = "sr_upsert_ex"]
+    pub extern "C" fn upsert_ex(
+        db: &Surreal,
+        err_ptr: *mut SurrealError,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        Self::upsert_impl(db, err_ptr, res_ptr, resource, content)
+    }
+
+    fn upsert_impl<E: ErrorOut>(
+        db: &Surreal,
+        err_ptr: E,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         check_null!(resource, err_ptr, "resource is null");
@@ -1739,19 +2374,9 @@ impl Surreal {
             let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
             let content = sdbObject::from(unsafe { &*content }.clone());
 
-            let val: sdbValue = surreal
-                .db
-                .upsert(parse_resource(resource))
-                .content(content)
-                .await
-                .map_err(|e| string_t::from(e.to_string()))?;
+            let val = ops::upsert(&surreal.db(), resource, content).await?;
 
-            let res = match val {
-                sdbValue::Array(a) => Array::from(a),
-                v => Array::from(vec![Value::from(v)]),
-            };
-
-            let ArrayGen { ptr, len } = res.into();
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
             unsafe { res_ptr.write(ptr) }
 
             Ok(len as c_int)
@@ -1781,11 +2406,24 @@ impl Surreal {
     /// ```
     #[export_name = "sr_use_db"]
     pub extern "C" fn use_db(db: &Surreal, err_ptr: *mut string_t, db_name: *const c_char) -> c_int {
+        Self::use_db_impl(db, err_ptr, db_name)
+    }
+
+    /// Select database, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_use_db` except for the type of `err_ptr`.
+    #[export_name = "sr_use_db_ex"]
+    pub extern "C" fn use_db_ex(db: &Surreal, err_ptr: *mut SurrealError, db_name: *const c_char) -> c_int {
+        Self::use_db_impl(db, err_ptr, db_name)
+    }
+
+    fn use_db_impl<E: ErrorOut>(db: &Surreal, err_ptr: E, db_name: *const c_char) -> c_int {
         check_null!(db_name, err_ptr, "db_name is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let db_name = unsafe { CStr::from_ptr(db_name) }.to_str()?;
 
-            surreal.db.use_db(db_name).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().use_db(db_name).await?;
+            surreal.session().select_db(db_name);
 
             Ok(0)
         })
@@ -1814,11 +2452,24 @@ impl Surreal {
     /// ```
     #[export_name = "sr_use_ns"]
     pub extern "C" fn use_ns(db: &Surreal, err_ptr: *mut string_t, ns_name: *const c_char) -> c_int {
+        Self::use_ns_impl(db, err_ptr, ns_name)
+    }
+
+    /// Select namespace, reporting errors as a structured `sr_error_t`
+    ///
+    /// Identical to `sr_use_ns` except for the type of `err_ptr`.
+    #[export_name = "sr_use_ns_ex"]
+    pub extern "C" fn use_ns_ex(db: &Surreal, err_ptr: *mut SurrealError, ns_name: *const c_char) -> c_int {
+        Self::use_ns_impl(db, err_ptr, ns_name)
+    }
+
+    fn use_ns_impl<E: ErrorOut>(db: &Surreal, err_ptr: E, ns_name: *const c_char) -> c_int {
         check_null!(ns_name, err_ptr, "ns_name is null");
         with_surreal_async(db, err_ptr, |surreal| async {
             let ns_name = unsafe { CStr::from_ptr(ns_name) }.to_str()?;
 
-            surreal.db.use_ns(ns_name).await.map_err(|e| string_t::from(e.to_string()))?;
+            surreal.db().use_ns(ns_name).await?;
+            surreal.session().select_ns(ns_name);
 
             Ok(0)
         })
@@ -1857,7 +2508,7 @@ impl Surreal {
     ) -> c_int {
         check_null!(res_ptr, err_ptr, "res_ptr is null");
         with_surreal_async(db, err_ptr, |surreal| async {
-            let res = surreal.db.version().await.map_err(|e| string_t::from(e.to_string()))?;
+            let res = surreal.db().version().await?;
             let res_string = res.to_string();
             let len = res_string.bytes().len();
             let res_str: string_t = res_string.to_string_t();
@@ -1869,27 +2520,331 @@ impl Surreal {
     }
 }
 
+/// Sign in and select the namespace and database requested in connect options
+async fn apply_connect_options(db: &sdbSurreal<Any>, options: &ConnectOptions) -> Result<Session, SurrealError> {
+    let namespace = opt_str(options.namespace)?;
+    let database = opt_str(options.database)?;
+
+    if options.strict && (namespace.is_none() || database.is_none()) {
+        return Err(SurrealError::invalid_argument("strict mode requires a namespace and database"));
+    }
+
+    let mut session = Session {
+        namespace: namespace.map(str::to_owned),
+        database: database.map(str::to_owned),
+        strict: options.strict,
+        token: None,
+    };
+
+    if !options.auth_scope.is_null() {
+        let (ns, db_name, ac) = match options.access.is_null() {
+            true => (namespace.unwrap_or(""), database.unwrap_or(""), ""),
+            false => read_access(options.access)?,
+        };
+        let scope = unsafe { &*options.auth_scope };
+        let token = signin_with(db, scope, options.credentials, ns, db_name, ac, options.auth_params).await?;
+        session.token = Some(token);
+    }
+
+    session.select(db).await?;
+    Ok(session)
+}
+
+/// Namespace, database and authentication applied on a connection, replayed by `sr_surreal_reconnect`
+#[derive(Clone, Default)]
+struct Session {
+    namespace: Option<String>,
+    database: Option<String>,
+    /// Define the selected database as STRICT if it does not exist, set by `sr_connect_with_options`
+    strict: bool,
+    /// Token of the last signin, signup or authenticate
+    token: Option<String>,
+}
+
+impl Session {
+    fn select_ns(&mut self, ns: &str) {
+        self.namespace = Some(ns.to_owned());
+        self.strict = false;
+    }
+
+    fn select_db(&mut self, db_name: &str) {
+        self.database = Some(db_name.to_owned());
+        self.strict = false;
+    }
+
+    /// Select the namespace and database, defining the database first in strict mode
+    async fn select(&self, db: &sdbSurreal<Any>) -> Result<(), SurrealError> {
+        if let Some(ns) = &self.namespace {
+            db.use_ns(ns).await?;
+        }
+
+        if let (true, Some(database)) = (self.strict, &self.database) {
+            let define = format!("DEFINE DATABASE IF NOT EXISTS `{}` STRICT", database.replace('`', "\\`"));
+            db.query(define)
+                .await
+                .and_then(|res| res.check())?;
+        }
+
+        if let Some(database) = &self.database {
+            db.use_db(database).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the session to a new connection
+    async fn restore(&self, db: &sdbSurreal<Any>) -> Result<(), SurrealError> {
+        if let Some(token) = &self.token {
+            db.authenticate(token.as_str()).await?;
+        }
+        self.select(db).await
+    }
+}
+
+/// Read the namespace, database and access method of a signin target
+///
+/// Null pointers are read as empty strings.
+fn read_access<'a>(details: *const credentials_access) -> Result<(&'a str, &'a str, &'a str), SurrealError> {
+    let mut ns = "";
+    let mut db_name = "";
+    let mut ac = "";
+
+    if !details.is_null() {
+        let details = unsafe { &*details };
+
+        if !details.namespace.0.is_null() {
+            ns = unsafe { CStr::from_ptr(details.namespace.0).to_str()? };
+        }
+
+        if !details.database.0.is_null() {
+            db_name = unsafe { CStr::from_ptr(details.database.0).to_str()? };
+        }
+
+        if !details.access.0.is_null() {
+            ac = unsafe { CStr::from_ptr(details.access.0).to_str()? };
+        }
+    }
+
+    Ok((ns, db_name, ac))
+}
+
+/// Sign in to a connection, returning the JWT
+///
+/// Shared by `sr_signin` and `sr_connect_with_options`.
+async fn signin_with(
+    db: &sdbSurreal<Any>,
+    scope: &credentials_scope,
+    creds: *const credentials::credentials,
+    ns: &str,
+    db_name: &str,
+    ac: &str,
+    params: *const Object,
+) -> Result<String, SurrealError> {
+    let mut user = "";
+    let mut pass = "";
+
+    if !creds.is_null() {
+        let creds = unsafe { &*creds };
+        if !creds.username.0.is_null() {
+            user = unsafe { CStr::from_ptr(creds.username.0).to_str()? };
+        }
+        if !creds.password.0.is_null() {
+            pass = unsafe { CStr::from_ptr(creds.password.0).to_str()? };
+        }
+    }
+
+    let token: String = match scope {
+        credentials_scope::ROOT => {
+            let login = auth::Root {
+                username: user.to_string(),
+                password: pass.to_string(),
+            };
+            let jwt = db.signin(login).await?;
+            jwt.access.into_insecure_token()
+        }
+        credentials_scope::NAMESPACE => {
+            if ns.is_empty() {
+                return Err(SurrealError::invalid_argument("Namespace must be provided."));
+            }
+
+            let login = auth::Namespace {
+                namespace: ns.to_string(),
+                username: user.to_string(),
+                password: pass.to_string(),
+            };
+
+            let jwt = db.signin(login).await?;
+            jwt.access.into_insecure_token()
+        }
+        credentials_scope::DATABASE => {
+            if ns.is_empty() {
+                return Err(SurrealError::invalid_argument("Namespace must be provided."));
+            }
+            if db_name.is_empty() {
+                return Err(SurrealError::invalid_argument("Database must be provided."));
+            }
+
+            let login = auth::Database {
+                namespace: ns.to_string(),
+                database: db_name.to_string(),
+                username: user.to_string(),
+                password: pass.to_string(),
+            };
+
+            let jwt = db.signin(login).await?;
+            jwt.access.into_insecure_token()
+        }
+        credentials_scope::RECORD => {
+            if ns.is_empty() {
+                return Err(SurrealError::invalid_argument("Namespace must be provided."));
+            }
+            if db_name.is_empty() {
+                return Err(SurrealError::invalid_argument("Database must be provided."));
+            }
+            if ac.is_empty() {
+                return Err(SurrealError::invalid_argument("Access method must be provided."));
+            }
+
+            let record_params: sdbObject = if !params.is_null() {
+                unsafe { &*params }.clone().into()
+            } else {
+                let mut obj = sdbObject::default();
+                obj.insert("username".to_string(), sdbValue::String(user.to_string()));
+                obj.insert("password".to_string(), sdbValue::String(pass.to_string()));
+                obj
+            };
+
+            let login = auth::Record {
+                namespace: ns.to_string(),
+                database: db_name.to_string(),
+                access: ac.to_string(),
+                params: record_params,
+            };
+
+            let jwt = db.signin(login).await?;
+            jwt.access.into_insecure_token()
+        }
+    };
+
+    Ok(token)
+}
+
+/// Endpoint and configuration a connection was opened with, used to rebuild it
+struct Target {
+    endpoint: String,
+    config: Option<Config>,
+}
+
+impl Target {
+    async fn connect(&self) -> Result<sdbSurreal<Any>, SurrealError> {
+        let db = match &self.config {
+            Some(config) => any::connect((self.endpoint.as_str(), config.clone())).await?,
+            None => any::connect(self.endpoint.as_str()).await?,
+        };
+        Ok(db)
+    }
+}
+
+/// Create or share a runtime, run the given connect future on it and write the new connection to `surreal_ptr`
+///
+/// catches panics and writes errors appropriately
+fn connect_with<E, C, F>(
+    err_ptr: E,
+    surreal_ptr: *mut *mut Surreal,
+    shared: Option<&SurrealRuntime>,
+    fun: C,
+) -> c_int
+where
+    E: ErrorOut,
+    C: FnOnce() -> F,
+    F: std::future::Future<Output = Result<(sdbSurreal<Any>, Target, Session), SurrealError>>,
+{
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        let rt = runtime::runtime_or_new(shared)?;
+
+        let (db, target, session) = rt.block_on(fun())?;
+
+        Ok::<_, SurrealError>(Surreal {
+            db: ArcSwap::from_pointee(db),
+            target,
+            session: Mutex::new(session),
+            rt,
+            ps: Arc::new(AtomicBool::new(false)),
+            completion: Arc::new(Completion::new()),
+            interrupt: Interrupt::new(),
+            timeout_ms: AtomicI32::new(-1),
+        })
+    }));
+
+    match res {
+        Ok(Ok(s)) => {
+            let boxed = Box::new(s);
+            unsafe { surreal_ptr.write(Box::leak(boxed)) }
+            1
+        }
+        Ok(Err(e)) => {
+            write_error(err_ptr, e);
+            SR_ERROR
+        }
+        Err(e) => {
+            write_error(err_ptr, SurrealError::panicked(&*e));
+            SR_FATAL
+        }
+    }
+}
+
 /// Execute a given closure in an async context, which returns a result then catches panics and writes errors appropriately
-fn with_surreal_async<'a, 'b, C, F>(db: &'a Surreal, err_ptr: *mut string_t, fun: C) -> c_int
+fn with_surreal_async<'a, 'b, E, C, F>(db: &'a Surreal, err_ptr: E, fun: C) -> c_int
 where
     'a: 'b,
+    E: ErrorOut,
     C: FnOnce(&'a Surreal) -> F + 'b,
-    F: std::future::Future<Output = Result<c_int, string_t>>,
+    F: std::future::Future<Output = Result<c_int, SurrealError>>,
+{
+    with_surreal_guarded(db, err_ptr, None, None, fun)
+}
+
+/// Whether the calling thread is inside a runtime, where blocking on a connection would panic
+///
+/// This is the case inside completion callbacks, which run on the connection's runtime.
+pub(crate) fn on_runtime() -> bool {
+    tokio::runtime::Handle::try_current().is_ok()
+}
+
+/// Run a blocking call, guarded by the connection's interrupt, an optional token and a timeout
+///
+/// `timeout` overrides the timeout bound to the calling thread, which overrides the connection's default.
+fn with_surreal_guarded<'a, 'b, E, C, F>(
+    db: &'a Surreal,
+    err_ptr: E,
+    token: Option<&CancelToken>,
+    timeout: Option<Option<Duration>>,
+    fun: C,
+) -> c_int
+where
+    'a: 'b,
+    E: ErrorOut,
+    C: FnOnce(&'a Surreal) -> F + 'b,
+    F: std::future::Future<Output = Result<c_int, SurrealError>>,
 {
     if db.ps.load(Ordering::Acquire) {
-        std::process::abort()
+        write_error(err_ptr, SurrealError::poisoned());
+        return SR_FATAL;
+    }
+    if on_runtime() {
+        write_error(err_ptr, SurrealError::blocking_on_runtime());
+        return SR_ERROR;
     }
     let _guard = db.rt.enter();
 
-    let res = match catch_unwind(AssertUnwindSafe(|| db.rt.block_on(fun(&db)))) {
+    let timeout = timeout.or_else(cancel::bound_timeout).unwrap_or_else(|| db.default_timeout());
+    let res = match catch_unwind(AssertUnwindSafe(|| {
+        db.rt.block_on(cancel::deadline(timeout, cancel::guard(&db.interrupt, token, fun(&db))))
+    })) {
         Ok(r) => r,
         Err(e) => {
-            if let Some(e_str) = e.downcast_ref::<&str>() {
-                let e_string: string_t = format!("Panicked with: {e_str}").into();
-                unsafe { err_ptr.write(e_string) }
-            } else {
-                unsafe { err_ptr.write("Panicked".into()) }
-            }
+            db.ps.store(true, Ordering::Release);
+            write_error(err_ptr, SurrealError::panicked(&*e));
             return SR_FATAL;
         }
     };
@@ -1897,8 +2852,9 @@ where
     match res {
         Ok(n) => n,
         Err(e) => {
-            unsafe { err_ptr.write(e) }
-            SR_ERROR
+            let code = e.code;
+            write_error(err_ptr, e);
+            code
         }
     }
 }
@@ -0,0 +1,670 @@
+//! Bulk loading of NDJSON and CSV data
+//!
+//! `sr_load_ndjson` and `sr_load_csv` read a file or buffer row by row, convert every row into an
+//! object and insert the rows into a table in batches, so only one batch is held in memory at a time.
+//! Every batch is a separate `sr_insert_many` call with its own timeout, and a failed batch only
+//! affects its own rows: the batches inserted before it are kept.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    mem,
+};
+
+use surrealdb::types::{Number as sdbNumber, Object as sdbObject, Value as sdbValue};
+
+use crate::{
+    array::{ArrayGen, MakeArray},
+    ops::{self, OnDuplicate},
+    result::{write_error, ErrorKind, SurrealError},
+    string::string_t,
+    utils::CStringExt2,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// The current version of `sr_load_options_t`
+///
+/// Callers must set `version` to this value so that fields added in later releases can be detected.
+pub const SR_LOAD_OPTIONS_VERSION: u32 = 1;
+
+/// Error policy: stop at the first row which can not be converted or inserted
+pub const SR_LOAD_STOP: c_int = 0;
+/// Error policy: skip rows which can not be converted or inserted
+pub const SR_LOAD_SKIP: c_int = 1;
+/// Error policy: skip rows which can not be converted or inserted and report their errors
+pub const SR_LOAD_COLLECT: c_int = 2;
+
+/// CSV column type: an integer, float or boolean if the field is one, a string otherwise
+pub const SR_COLUMN_AUTO: c_int = 0;
+/// CSV column type: the field as is
+pub const SR_COLUMN_STRING: c_int = 1;
+/// CSV column type: a 64-bit integer
+pub const SR_COLUMN_INT: c_int = 2;
+/// CSV column type: a 64-bit float
+pub const SR_COLUMN_FLOAT: c_int = 3;
+/// CSV column type: `true`, `false`, `1` or `0`, in any case
+pub const SR_COLUMN_BOOL: c_int = 4;
+/// CSV column type: an ISO 8601 datetime
+pub const SR_COLUMN_DATETIME: c_int = 5;
+/// CSV column type: a JSON value
+pub const SR_COLUMN_JSON: c_int = 6;
+
+/// Number of rows inserted per query when no batch size is given
+const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Callback invoked after every batch with the number of rows loaded and bytes read so far
+pub type LoadProgressCallback = Option<extern "C" fn(userdata: *mut c_void, rows: i64, bytes: i64)>;
+
+/// Options for the `sr_load_*` functions
+///
+/// Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+/// loads with the defaults: batches of 1000 rows, stopping at the first error, and for CSV a comma
+/// delimited file with a header row and every column of type SR_COLUMN_AUTO.
+#[repr(C)]
+pub struct LoadOptions {
+    /// Must be set to SR_LOAD_OPTIONS_VERSION
+    pub version: u32,
+    /// Number of rows inserted per query, zero for 1000
+    pub batch_size: c_int,
+    /// SR_LOAD_STOP, SR_LOAD_SKIP or SR_LOAD_COLLECT
+    pub on_error: c_int,
+    /// Called after every batch, may be null
+    pub progress: LoadProgressCallback,
+    /// Passed to the progress callback
+    pub userdata: *mut c_void,
+    /// CSV field delimiter, zero for a comma
+    pub delimiter: c_char,
+    /// The CSV data has no header row, `columns` must then name the columns
+    pub no_header: bool,
+    /// Names of the CSV columns, overriding the header row, may be null
+    pub columns: *const *const c_char,
+    /// Types of the CSV columns as SR_COLUMN_* constants, may be null
+    pub column_types: *const c_int,
+    /// Number of entries in `columns` and `column_types`
+    pub column_count: c_int,
+}
+
+/// A row which could not be loaded
+#[repr(C)]
+pub struct LoadError {
+    /// Line of the row in the data, starting at 1
+    pub line: i64,
+    /// The category of the error
+    pub kind: ErrorKind,
+    /// Description of the error
+    pub message: string_t,
+}
+
+/// The outcome of a load
+///
+/// Must be freed with `sr_free_load_report`
+#[repr(C)]
+pub struct LoadReport {
+    /// Number of rows inserted
+    pub loaded: i64,
+    /// Number of rows skipped because of an error
+    pub skipped: i64,
+    /// Errors of the skipped rows with SR_LOAD_COLLECT, null otherwise
+    pub errors: *mut LoadError,
+    /// Number of entries in `errors`
+    pub errors_len: c_int,
+}
+
+/// Free the errors held by a load report
+#[export_name = "sr_free_load_report"]
+pub extern "C" fn free_load_report(report: LoadReport) {
+    ArrayGen {
+        ptr: report.errors,
+        len: report.errors_len,
+    }
+    .free()
+}
+
+/// Load newline-delimited JSON from a file into a table
+///
+/// Every non-empty line must hold a JSON object, which is inserted as a record. Objects with an
+/// `id` field keep it as their record id.
+/// Returns SR_NONE once all rows have been handled, or a negative error code if the load stopped.
+/// If `report_ptr` is not null the report is written to it in both cases, and must be freed with
+/// `sr_free_load_report`.
+///
+/// # Safety
+///
+/// - `db` must be a valid pointer to a Surreal connection
+/// - `err_ptr` must be a valid pointer or null
+/// - `report_ptr` must be a valid pointer or null
+/// - `table` and `path` must be valid null-terminated UTF-8 strings
+/// - `options` may be null for the defaults
+///
+/// # Examples
+///
+/// ```c
+/// sr_load_options_t opts = {0};
+/// opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+/// opts.on_error = sr_SR_LOAD_COLLECT;
+///
+/// sr_load_report_t report;
+/// if (sr_load_ndjson(db, &err, &report, "event", "events.ndjson", &opts) < 0) {
+///     printf("%s", err);
+/// }
+/// for (int i = 0; i < report.errors_len; i++) {
+///     printf("line %lld: %s\n", report.errors[i].line, report.errors[i].message);
+/// }
+/// sr_free_load_report(report);
+/// ```
+#[export_name = "sr_load_ndjson"]
+pub extern "C" fn load_ndjson(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    report_ptr: *mut LoadReport,
+    table: *const c_char,
+    path: *const c_char,
+    options: *const LoadOptions,
+) -> c_int {
+    load(db, err_ptr, report_ptr, table, options, Source::Path(path), Format::NdJson)
+}
+
+/// Load newline-delimited JSON from a buffer into a table
+///
+/// Identical to `sr_load_ndjson` except that the data is read from the `len` bytes at `data`.
+#[export_name = "sr_load_ndjson_buf"]
+pub extern "C" fn load_ndjson_buf(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    report_ptr: *mut LoadReport,
+    table: *const c_char,
+    data: *const c_char,
+    len: c_int,
+    options: *const LoadOptions,
+) -> c_int {
+    load(db, err_ptr, report_ptr, table, options, Source::Buffer(data, len), Format::NdJson)
+}
+
+/// Load CSV from a file into a table
+///
+/// Every row is inserted as a record with a field per column, named after the header row or
+/// `columns` of the options. Fields are converted according to `column_types`, empty fields are
+/// NULL unless the column is of type SR_COLUMN_STRING. A column named `id` is used as record id.
+/// Returns and reports like `sr_load_ndjson`.
+///
+/// # Examples
+///
+/// ```c
+/// const char *columns[] = {"id", "name", "price", "listed"};
+/// int types[] = {sr_SR_COLUMN_STRING, sr_SR_COLUMN_STRING, sr_SR_COLUMN_FLOAT, sr_SR_COLUMN_DATETIME};
+///
+/// sr_load_options_t opts = {0};
+/// opts.version = sr_SR_LOAD_OPTIONS_VERSION;
+/// opts.delimiter = ';';
+/// opts.columns = columns;
+/// opts.column_types = types;
+/// opts.column_count = 4;
+///
+/// if (sr_load_csv(db, &err, NULL, "product", "products.csv", &opts) < 0) {
+///     printf("%s", err);
+///     return 1;
+/// }
+/// ```
+#[export_name = "sr_load_csv"]
+pub extern "C" fn load_csv(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    report_ptr: *mut LoadReport,
+    table: *const c_char,
+    path: *const c_char,
+    options: *const LoadOptions,
+) -> c_int {
+    load(db, err_ptr, report_ptr, table, options, Source::Path(path), Format::Csv)
+}
+
+/// Load CSV from a buffer into a table
+///
+/// Identical to `sr_load_csv` except that the data is read from the `len` bytes at `data`.
+#[export_name = "sr_load_csv_buf"]
+pub extern "C" fn load_csv_buf(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    report_ptr: *mut LoadReport,
+    table: *const c_char,
+    data: *const c_char,
+    len: c_int,
+    options: *const LoadOptions,
+) -> c_int {
+    load(db, err_ptr, report_ptr, table, options, Source::Buffer(data, len), Format::Csv)
+}
+
+enum Source {
+    Path(*const c_char),
+    Buffer(*const c_char, c_int),
+}
+
+enum Format {
+    NdJson,
+    Csv,
+}
+
+fn load(
+    db: &Surreal,
+    err_ptr: *mut string_t,
+    report_ptr: *mut LoadReport,
+    table: *const c_char,
+    options: *const LoadOptions,
+    source: Source,
+    format: Format,
+) -> c_int {
+    let opts = match options.is_null() {
+        true => None,
+        false => Some(unsafe { &*options }),
+    };
+    let mut loader = match Loader::new(db, err_ptr, table, opts) {
+        Ok(loader) => loader,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+
+    let reader: Box<dyn BufRead> = match source {
+        Source::Path(path) => {
+            if path.is_null() {
+                write_error(err_ptr, SurrealError::invalid_argument("path is null"));
+                return SR_ERROR;
+            }
+            let file = unsafe { CStr::from_ptr(path) }
+                .to_str()
+                .map_err(SurrealError::from)
+                .and_then(|path| File::open(path).map_err(SurrealError::invalid_argument));
+            match file {
+                Ok(file) => Box::new(BufReader::new(file)),
+                Err(e) => {
+                    write_error(err_ptr, e);
+                    return SR_ERROR;
+                }
+            }
+        }
+        Source::Buffer(data, len) => match (data.is_null(), usize::try_from(len)) {
+            (_, Ok(0)) => Box::new(&[][..]),
+            (false, Ok(len)) => Box::new(unsafe { std::slice::from_raw_parts(data as *const u8, len) }),
+            (true, _) => {
+                write_error(err_ptr, SurrealError::invalid_argument("data is null"));
+                return SR_ERROR;
+            }
+            (false, Err(_)) => {
+                write_error(err_ptr, SurrealError::invalid_argument("len is negative"));
+                return SR_ERROR;
+            }
+        },
+    };
+
+    let res = match format {
+        Format::NdJson => read_ndjson(&mut loader, reader),
+        Format::Csv => match Columns::new(opts) {
+            Ok(columns) => read_csv(&mut loader, reader, columns, opts),
+            Err(e) => {
+                write_error(err_ptr, e);
+                Err(SR_ERROR)
+            }
+        },
+    };
+    let res = res.and_then(|_| loader.flush());
+
+    if !report_ptr.is_null() {
+        let ArrayGen { ptr, len } = match loader.collect {
+            true => mem::take(&mut loader.errors).make_array(),
+            false => ArrayGen {
+                ptr: std::ptr::null_mut(),
+                len: 0,
+            },
+        };
+        let report = LoadReport {
+            loaded: loader.loaded,
+            skipped: loader.skipped,
+            errors: ptr,
+            errors_len: len,
+        };
+        unsafe { report_ptr.write(report) }
+    }
+
+    match res {
+        Ok(()) => SR_NONE,
+        Err(code) => code,
+    }
+}
+
+/// Inserts rows in batches, applying the error policy
+///
+/// Errors which stop the load are written to `err_ptr` and returned as their code.
+struct Loader<'a> {
+    db: &'a Surreal,
+    err_ptr: *mut string_t,
+    table: &'a str,
+    batch_size: usize,
+    stop: bool,
+    collect: bool,
+    progress: LoadProgressCallback,
+    userdata: *mut c_void,
+    batch: Vec<(i64, sdbObject)>,
+    bytes: i64,
+    loaded: i64,
+    skipped: i64,
+    errors: Vec<LoadError>,
+}
+
+impl<'a> Loader<'a> {
+    fn new(
+        db: &'a Surreal,
+        err_ptr: *mut string_t,
+        table: *const c_char,
+        opts: Option<&LoadOptions>,
+    ) -> Result<Self, SurrealError> {
+        if table.is_null() {
+            return Err(SurrealError::invalid_argument("table is null"));
+        }
+        let table = unsafe { CStr::from_ptr(table) }.to_str()?;
+
+        if let Some(opts) = opts {
+            if opts.version == 0 || opts.version > SR_LOAD_OPTIONS_VERSION {
+                return Err(SurrealError::invalid_argument(format!(
+                    "unsupported load options version: {}",
+                    opts.version
+                )));
+            }
+        }
+        let on_error = opts.map_or(SR_LOAD_STOP, |o| o.on_error);
+        if !matches!(on_error, SR_LOAD_STOP | SR_LOAD_SKIP | SR_LOAD_COLLECT) {
+            return Err(SurrealError::invalid_argument(format!("unknown error policy: {on_error}")));
+        }
+        let batch_size = match opts.map(|o| usize::try_from(o.batch_size)) {
+            None | Some(Ok(0) | Err(_)) => DEFAULT_BATCH_SIZE,
+            Some(Ok(n)) => n,
+        };
+
+        Ok(Loader {
+            db,
+            err_ptr,
+            table,
+            batch_size,
+            stop: on_error == SR_LOAD_STOP,
+            collect: on_error == SR_LOAD_COLLECT,
+            progress: opts.and_then(|o| o.progress),
+            userdata: opts.map_or(std::ptr::null_mut(), |o| o.userdata),
+            batch: Vec::new(),
+            bytes: 0,
+            loaded: 0,
+            skipped: 0,
+            errors: Vec::new(),
+        })
+    }
+
+    /// Add a converted row to the current batch, inserting the batch once it is full
+    fn push(&mut self, line: i64, row: Result<sdbObject, SurrealError>) -> Result<(), c_int> {
+        match row {
+            Ok(row) => {
+                self.batch.push((line, row));
+                match self.batch.len() >= self.batch_size {
+                    true => self.flush(),
+                    false => Ok(()),
+                }
+            }
+            Err(e) => self.reject(line, e),
+        }
+    }
+
+    /// Skip a row, or stop the load with its error
+    ///
+    /// When stopping, the rows read before it are inserted first.
+    fn reject(&mut self, line: i64, mut err: SurrealError) -> Result<(), c_int> {
+        let msg = String::from(mem::take(&mut err.msg));
+        if self.stop {
+            self.flush()?;
+            err.msg = format!("line {line}: {msg}").to_string_t();
+            write_error(self.err_ptr, err);
+            return Err(SR_ERROR);
+        }
+        self.skipped += 1;
+        if self.collect {
+            self.errors.push(LoadError {
+                line,
+                kind: err.kind,
+                message: msg.to_string_t(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Insert the current batch
+    ///
+    /// If the batch fails, its rows are inserted one at a time to find the ones at fault, so the
+    /// error policy applies to the failing rows and their own lines are reported.
+    fn flush(&mut self) -> Result<(), c_int> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let batch = mem::take(&mut self.batch);
+        let count = batch.len() as i64;
+
+        match self.insert(batch.iter().map(|(_, row)| row.clone()).collect())? {
+            None => self.loaded += count,
+            Some(_) => {
+                for (line, row) in batch {
+                    match self.insert(vec![row])? {
+                        None => self.loaded += 1,
+                        Some(e) => self.reject(line, e)?,
+                    }
+                }
+            }
+        }
+
+        if let Some(progress) = self.progress {
+            progress(self.userdata, self.loaded, self.bytes);
+        }
+        Ok(())
+    }
+
+    /// Insert rows, returning the error of the insert itself
+    ///
+    /// Fails with the code of the call if it could not be made, for example on a timeout.
+    fn insert(&self, rows: Vec<sdbObject>) -> Result<Option<SurrealError>, c_int> {
+        let mut failed = None;
+        let res = with_surreal_async(self.db, self.err_ptr, |surreal| async {
+            if let Err(e) = ops::insert_many(&surreal.db(), self.table, rows, OnDuplicate::Fail, true).await {
+                failed = Some(e);
+            }
+            Ok(SR_NONE)
+        });
+        match res {
+            SR_NONE => Ok(failed),
+            code => Err(code),
+        }
+    }
+}
+
+fn read_ndjson(loader: &mut Loader, mut reader: impl BufRead) -> Result<(), c_int> {
+    let mut buf = Vec::new();
+    let mut line = 0;
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => loader.bytes += n as i64,
+            Err(e) => {
+                write_error(loader.err_ptr, SurrealError::from_msg(e));
+                return Err(SR_ERROR);
+            }
+        }
+        line += 1;
+
+        let text = match std::str::from_utf8(&buf) {
+            Ok(text) => text.trim(),
+            Err(e) => {
+                loader.reject(line, SurrealError::new(ErrorKind::SR_ERROR_KIND_SERIALIZATION, e))?;
+                continue;
+            }
+        };
+        if text.is_empty() {
+            continue;
+        }
+        let row = match surrealdb_core::syn::json(text) {
+            Ok(sdbValue::Object(obj)) => Ok(obj),
+            Ok(_) => Err(SurrealError::new(ErrorKind::SR_ERROR_KIND_SERIALIZATION, "expected a JSON object")),
+            Err(e) => Err(SurrealError::new(ErrorKind::SR_ERROR_KIND_SERIALIZATION, e)),
+        };
+        loader.push(line, row)?;
+    }
+}
+
+/// Names and types of the columns of a CSV file
+struct Columns {
+    names: Vec<String>,
+    types: Vec<c_int>,
+}
+
+impl Columns {
+    fn new(opts: Option<&LoadOptions>) -> Result<Self, SurrealError> {
+        let mut columns = Columns {
+            names: Vec::new(),
+            types: Vec::new(),
+        };
+        let Some(opts) = opts else {
+            return Ok(columns);
+        };
+        let count = match usize::try_from(opts.column_count) {
+            Ok(count) => count,
+            Err(_) => return Err(SurrealError::invalid_argument("column_count is negative")),
+        };
+        if count > 0 && !opts.columns.is_null() {
+            for name in unsafe { std::slice::from_raw_parts(opts.columns, count) } {
+                if name.is_null() {
+                    return Err(SurrealError::invalid_argument("column name is null"));
+                }
+                columns.names.push(unsafe { CStr::from_ptr(*name) }.to_str()?.to_owned());
+            }
+        }
+        if count > 0 && !opts.column_types.is_null() {
+            columns.types = unsafe { std::slice::from_raw_parts(opts.column_types, count) }.to_vec();
+            if let Some(ty) = columns.types.iter().find(|ty| !(SR_COLUMN_AUTO..=SR_COLUMN_JSON).contains(*ty)) {
+                return Err(SurrealError::invalid_argument(format!("unknown column type: {ty}")));
+            }
+        }
+        if opts.no_header && columns.names.is_empty() {
+            return Err(SurrealError::invalid_argument("columns must be given for CSV data without a header"));
+        }
+        Ok(columns)
+    }
+
+    fn row(&self, record: &csv::ByteRecord) -> Result<sdbObject, SurrealError> {
+        if record.len() != self.names.len() {
+            return Err(SurrealError::new(
+                ErrorKind::SR_ERROR_KIND_SERIALIZATION,
+                format!("expected {} fields, found {}", self.names.len(), record.len()),
+            ));
+        }
+        let mut row = sdbObject::new();
+        for (i, (name, field)) in self.names.iter().zip(record).enumerate() {
+            let ty = self.types.get(i).copied().unwrap_or(SR_COLUMN_AUTO);
+            let value = std::str::from_utf8(field)
+                .map_err(SurrealError::from)
+                .and_then(|field| convert(field, ty))
+                .map_err(|e| SurrealError::new(e.kind, format!("column {name}: {}", String::from(e.msg))))?;
+            row.insert(name.clone(), value);
+        }
+        Ok(row)
+    }
+}
+
+/// Convert a CSV field to a value of a column type
+fn convert(field: &str, ty: c_int) -> Result<sdbValue, SurrealError> {
+    let invalid = |what: &str| {
+        SurrealError::new(ErrorKind::SR_ERROR_KIND_SERIALIZATION, format!("invalid {what}: {field:?}"))
+    };
+    if field.is_empty() && ty != SR_COLUMN_STRING {
+        return Ok(sdbValue::Null);
+    }
+    let value = match ty {
+        SR_COLUMN_STRING => sdbValue::String(field.to_owned()),
+        SR_COLUMN_INT => sdbValue::Number(sdbNumber::Int(field.trim().parse().map_err(|_| invalid("integer"))?)),
+        SR_COLUMN_FLOAT => sdbValue::Number(sdbNumber::Float(field.trim().parse().map_err(|_| invalid("float"))?)),
+        SR_COLUMN_BOOL => match field.trim().to_lowercase().as_str() {
+            "true" | "1" => sdbValue::Bool(true),
+            "false" | "0" => sdbValue::Bool(false),
+            _ => return Err(invalid("boolean")),
+        },
+        SR_COLUMN_DATETIME => {
+            sdbValue::Datetime(surrealdb_core::syn::datetime(field.trim()).map_err(|_| invalid("datetime"))?)
+        }
+        SR_COLUMN_JSON => surrealdb_core::syn::json(field).map_err(|_| invalid("JSON"))?,
+        _ => {
+            let numeric = field.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b));
+            if let (true, Ok(n)) = (numeric, field.parse::<i64>()) {
+                sdbValue::Number(sdbNumber::Int(n))
+            } else if let (true, Ok(n)) = (numeric, field.parse::<f64>()) {
+                sdbValue::Number(sdbNumber::Float(n))
+            } else {
+                match field.trim().to_lowercase().as_str() {
+                    "true" => sdbValue::Bool(true),
+                    "false" => sdbValue::Bool(false),
+                    _ => sdbValue::String(field.to_owned()),
+                }
+            }
+        }
+    };
+    Ok(value)
+}
+
+fn read_csv(
+    loader: &mut Loader,
+    reader: impl Read,
+    mut columns: Columns,
+    opts: Option<&LoadOptions>,
+) -> Result<(), c_int> {
+    let delimiter = match opts.map_or(0, |o| o.delimiter) {
+        0 => b',',
+        d => d as u8,
+    };
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .delimiter(delimiter)
+        .from_reader(reader);
+
+    let mut header = !opts.is_some_and(|o| o.no_header);
+    let mut record = csv::ByteRecord::new();
+    loop {
+        let read = reader.read_byte_record(&mut record);
+        let line = record.position().map_or(0, |p| p.line() as i64);
+        loader.bytes = reader.position().byte() as i64;
+        match read {
+            Ok(false) => return Ok(()),
+            Ok(true) => {}
+            Err(e) => match e.kind() {
+                csv::ErrorKind::Io(_) => {
+                    write_error(loader.err_ptr, SurrealError::from_msg(e));
+                    return Err(SR_ERROR);
+                }
+                _ => {
+                    let line = e.position().map_or(line, |p| p.line() as i64);
+                    loader.reject(line, SurrealError::new(ErrorKind::SR_ERROR_KIND_SERIALIZATION, e))?;
+                    continue;
+                }
+            },
+        }
+
+        if mem::take(&mut header) {
+            // names given in the options take precedence over the header row
+            if columns.names.is_empty() {
+                for field in &record {
+                    match std::str::from_utf8(field) {
+                        Ok(name) => columns.names.push(name.to_owned()),
+                        Err(e) => {
+                            write_error(loader.err_ptr, SurrealError::invalid_argument(format!("invalid header: {e}")));
+                            return Err(SR_ERROR);
+                        }
+                    }
+                }
+            }
+            continue;
+        }
+        loader.push(line, columns.row(&record))?;
+    }
+}
@@ -0,0 +1,331 @@
+//! Core database operations shared by the blocking, callback and future based APIs
+//!
+//! Operations take owned or borrowed Rust values and return SDK values, so they can be spawned
+//! on the runtime. Conversion to C types happens once the result is handed back to the caller.
+
+use std::time::Duration;
+
+use surrealdb::{
+    engine::any::Any,
+    method::{QueryStream, Transaction as sdbTransaction},
+    opt::{PatchOp, Resource},
+    types::{Object as sdbObject, Table, Value as sdbValue},
+    IndexedResults, Surreal as sdbSurreal,
+};
+
+use crate::{
+    array::Array,
+    parse, parse_resource,
+    result::{ArrayResult, ErrorKind, SurrealError},
+    value::Value,
+};
+
+pub(crate) async fn select(db: &sdbSurreal<Any>, resource: &str) -> Result<sdbValue, SurrealError> {
+    Ok(db.select(parse_resource(resource)).await?)
+}
+
+pub(crate) async fn create(
+    db: &sdbSurreal<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbObject, SurrealError> {
+    let query = format!("CREATE {} CONTENT $content", resource);
+    let mut res = db.query(&query).bind(("content", content)).await?;
+    created(res.take(0)?)
+}
+
+/// Extract the record returned by a CREATE statement
+fn created(val: sdbValue) -> Result<sdbObject, SurrealError> {
+    match val {
+        sdbValue::Array(arr) if !arr.is_empty() => match arr.into_iter().next().unwrap() {
+            sdbValue::Object(o) => Ok(o),
+            other => Err(format!("Expected object as return type of create, but found: {other:?}").into()),
+        },
+        sdbValue::Object(o) => Ok(o),
+        other => Err(format!("Expected object as return type of create, but found: {other:?}").into()),
+    }
+}
+
+pub(crate) async fn insert(
+    db: &sdbSurreal<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(db.insert(parse_resource(resource)).content(content).await?)
+}
+
+/// What a bulk insert does with rows whose record already exists
+pub(crate) enum OnDuplicate {
+    Fail,
+    Ignore,
+    Update,
+}
+
+/// Insert many rows into a table with a single statement
+///
+/// Returns NONE instead of the inserted records if `discard` is set.
+pub(crate) async fn insert_many(
+    db: &sdbSurreal<Any>,
+    table: &str,
+    rows: Vec<sdbObject>,
+    on_duplicate: OnDuplicate,
+    discard: bool,
+) -> Result<sdbValue, SurrealError> {
+    let update = match on_duplicate {
+        // update every field present in any row, keeping fields a row does not set
+        OnDuplicate::Update => {
+            let mut fields: Vec<&String> = rows.iter().flat_map(|row| row.keys()).filter(|k| *k != "id").collect();
+            fields.sort();
+            fields.dedup();
+            fields
+                .into_iter()
+                .map(|f| {
+                    let f = format!("`{}`", f.replace('\\', "\\\\").replace('`', "\\`"));
+                    format!("{f} = $input.{f} ?? {f}")
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+        _ => String::new(),
+    };
+    let mut query = match on_duplicate {
+        OnDuplicate::Fail => "INSERT INTO $table $rows".to_owned(),
+        OnDuplicate::Ignore => "INSERT IGNORE INTO $table $rows".to_owned(),
+        // rows holding nothing but an id leave existing records as they are
+        OnDuplicate::Update if update.is_empty() => "INSERT IGNORE INTO $table $rows".to_owned(),
+        OnDuplicate::Update => format!("INSERT INTO $table $rows ON DUPLICATE KEY UPDATE {update}"),
+    };
+    if discard {
+        query += " RETURN NONE";
+    }
+
+    let rows: Vec<sdbValue> = rows.into_iter().map(sdbValue::Object).collect();
+    let mut res = db
+        .query(query)
+        .bind(("table", sdbValue::Table(Table::new(table))))
+        .bind(("rows", sdbValue::Array(rows.into())))
+        .await?;
+    Ok(res.take(0)?)
+}
+
+pub(crate) async fn update(
+    db: &sdbSurreal<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(db.update(parse_resource(resource)).content(content).await?)
+}
+
+pub(crate) async fn upsert(
+    db: &sdbSurreal<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(db.upsert(parse_resource(resource)).content(content).await?)
+}
+
+pub(crate) async fn merge(
+    db: &sdbSurreal<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(db.update(parse_resource(resource)).merge(content).await?)
+}
+
+pub(crate) async fn patch(db: &sdbSurreal<Any>, resource: &str, op: PatchOp) -> Result<sdbValue, SurrealError> {
+    Ok(db.update(parse_resource(resource)).patch(op).await?)
+}
+
+pub(crate) async fn delete(db: &sdbSurreal<Any>, resource: &str) -> Result<sdbValue, SurrealError> {
+    Ok(db.delete(parse_resource(resource)).await?)
+}
+
+pub(crate) async fn relate(
+    db: &sdbSurreal<Any>,
+    from: &str,
+    relation: &str,
+    to: &str,
+    content: Option<sdbObject>,
+) -> Result<sdbValue, SurrealError> {
+    let query = match content {
+        None => format!("RELATE {from}->{relation}->{to}"),
+        Some(_) => format!("RELATE {from}->{relation}->{to} CONTENT $content"),
+    };
+
+    let mut q = db.query(&query);
+    if let Some(content) = content {
+        q = q.bind(("content", content));
+    }
+
+    let mut res = q.await?;
+    Ok(res.take(0)?)
+}
+
+pub(crate) async fn run(
+    db: &sdbSurreal<Any>,
+    function_name: &str,
+    args: Vec<sdbValue>,
+) -> Result<sdbValue, SurrealError> {
+    Ok(db.run(function_name).args(args).await?)
+}
+
+/// Run a query, returning the result of each statement
+pub(crate) async fn query(
+    db: &sdbSurreal<Any>,
+    query: &str,
+    vars: sdbObject,
+) -> Result<Vec<Result<sdbValue, SurrealError>>, SurrealError> {
+    let res = db.query(query).bind(vars).await?;
+    Ok(take_results(res))
+}
+
+fn take_results(mut res: IndexedResults) -> Vec<Result<sdbValue, SurrealError>> {
+    let res_len = res.num_statements();
+
+    let mut acc = Vec::with_capacity(res_len);
+    for index in 0..res_len {
+        acc.push(res.take::<sdbValue>(index).map_err(SurrealError::from));
+    }
+    acc
+}
+
+/// Run a single `LIVE SELECT` statement, returning the stream of its notifications
+///
+/// The query is checked before it is sent, as a live query started by any other statement of it
+/// would keep running without a stream to read or kill it.
+pub(crate) async fn live_query(
+    db: &sdbSurreal<Any>,
+    query: &str,
+    vars: sdbObject,
+) -> Result<QueryStream<sdbValue>, SurrealError> {
+    if u32::try_from(query.len()).is_err() {
+        return Err(SurrealError::invalid_argument("query is too large"));
+    }
+    match parse::parse(query) {
+        Ok(parsed) if parsed.live => {}
+        Ok(_) => return Err(SurrealError::invalid_argument("expected a single LIVE SELECT statement")),
+        Err(e) => {
+            let msg = format!("Parse error: {}", e.render_on(query));
+            return Err(SurrealError::new(ErrorKind::SR_ERROR_KIND_PARSE, msg));
+        }
+    }
+    let mut res = db.query(query).bind(vars).await?;
+    Ok(res.stream::<sdbValue>(0)?)
+}
+
+/// Start a `LIVE SELECT DIFF` of a table or record, whose notifications hold patch operations
+pub(crate) async fn live_diff(db: &sdbSurreal<Any>, resource: &str) -> Result<QueryStream<sdbValue>, SurrealError> {
+    let what = match parse_resource(resource) {
+        Resource::RecordId(id) => sdbValue::RecordId(id),
+        _ => sdbValue::Table(Table::from(resource)),
+    };
+    let mut vars = sdbObject::new();
+    vars.insert("what".to_string(), what);
+    live_query(db, "LIVE SELECT DIFF FROM $what", vars).await
+}
+
+/// Run a query, returning the execution time and result of each statement
+pub(crate) async fn query_with_stats(
+    db: &sdbSurreal<Any>,
+    query: &str,
+    vars: sdbObject,
+) -> Result<Vec<(Option<Duration>, Result<sdbValue, SurrealError>)>, SurrealError> {
+    let mut res = db.query(query).bind(vars).with_stats().await?;
+    let res_len = res.num_statements();
+
+    let mut acc = Vec::with_capacity(res_len);
+    for index in 0..res_len {
+        let (stats, val) = res
+            .take::<sdbValue>(index)
+            .ok_or_else(|| SurrealError::from_msg(format!("missing result of statement {index}")))?;
+        acc.push((stats.execution_time, val.map_err(SurrealError::from)));
+    }
+    Ok(acc)
+}
+
+pub(crate) async fn begin(db: &sdbSurreal<Any>) -> Result<sdbTransaction<Any>, SurrealError> {
+    Ok(db.clone().begin().await?)
+}
+
+pub(crate) async fn commit(txn: sdbTransaction<Any>) -> Result<(), SurrealError> {
+    txn.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn rollback(txn: sdbTransaction<Any>) -> Result<(), SurrealError> {
+    txn.cancel().await?;
+    Ok(())
+}
+
+/// Run a query inside a transaction, returning the result of each statement
+pub(crate) async fn query_in(
+    txn: &sdbTransaction<Any>,
+    query: &str,
+    vars: sdbObject,
+) -> Result<Vec<Result<sdbValue, SurrealError>>, SurrealError> {
+    let res = txn.query(query).bind(vars).await?;
+    Ok(take_results(res))
+}
+
+pub(crate) async fn select_in(txn: &sdbTransaction<Any>, resource: &str) -> Result<sdbValue, SurrealError> {
+    Ok(txn.select(parse_resource(resource)).await?)
+}
+
+pub(crate) async fn create_in(
+    txn: &sdbTransaction<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbObject, SurrealError> {
+    let query = format!("CREATE {} CONTENT $content", resource);
+    let mut res = txn.query(&query).bind(("content", content)).await?;
+    created(res.take(0)?)
+}
+
+pub(crate) async fn insert_in(
+    txn: &sdbTransaction<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(txn.insert(parse_resource(resource)).content(content).await?)
+}
+
+pub(crate) async fn update_in(
+    txn: &sdbTransaction<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(txn.update(parse_resource(resource)).content(content).await?)
+}
+
+pub(crate) async fn upsert_in(
+    txn: &sdbTransaction<Any>,
+    resource: &str,
+    content: sdbObject,
+) -> Result<sdbValue, SurrealError> {
+    Ok(txn.upsert(parse_resource(resource)).content(content).await?)
+}
+
+pub(crate) async fn delete_in(txn: &sdbTransaction<Any>, resource: &str) -> Result<sdbValue, SurrealError> {
+    Ok(txn.delete(parse_resource(resource)).await?)
+}
+
+/// Convert the result of a CRUD operation to an array, wrapping single values
+pub(crate) fn into_array(val: sdbValue) -> Array {
+    match val {
+        sdbValue::Array(a) => Array::from(a),
+        v => Array::from(vec![Value::from(v)]),
+    }
+}
+
+/// Convert the results of each statement of a query
+pub(crate) fn into_results(results: Vec<Result<sdbValue, SurrealError>>) -> Vec<ArrayResult> {
+    results
+        .into_iter()
+        .map(|res| match res {
+            Ok(sdbValue::Array(arr)) => ArrayResult::ok(arr.into()),
+            Ok(val) => ArrayResult::ok(vec![Value::from(val)].into()),
+            Err(e) => ArrayResult::err(e),
+        })
+        .collect()
+}
+
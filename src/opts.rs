@@ -1,3 +1,15 @@
+use std::ffi::{c_char, CStr};
+
+use surrealdb::opt::{capabilities::Capabilities as sdbCapabilities, Config};
+
+use crate::{
+    credentials::{credentials, credentials_access, credentials_scope},
+    duration::Duration,
+    object::Object,
+    result::SurrealError,
+    runtime::SurrealRuntime,
+};
+
 /// Connection options for SurrealDB
 ///
 /// Configures various settings for the database connection.
@@ -10,3 +22,150 @@ pub struct Options {
     /// Transaction timeout in seconds
     pub transaction_timeout: u8,
 }
+
+/// The current version of `sr_connect_options_t`
+///
+/// Callers must set `version` to this value so that fields added in later releases can be detected.
+pub const SR_CONNECT_OPTIONS_VERSION: u32 = 2;
+
+/// Extended connection options for `sr_connect_with_options`
+///
+/// Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+/// behaves like `sr_connect`.
+#[repr(C)]
+pub struct ConnectOptions {
+    /// Must be set to SR_CONNECT_OPTIONS_VERSION
+    pub version: u32,
+    /// Define the selected database as STRICT if it does not exist yet (requires `namespace` and `database`)
+    pub strict: bool,
+    /// Query timeout, zero for no timeout (embedded engines only)
+    pub query_timeout: Duration,
+    /// Transaction timeout, zero for no timeout (embedded engines only)
+    pub transaction_timeout: Duration,
+    /// Namespace to select after connecting, may be null
+    pub namespace: *const c_char,
+    /// Database to select after connecting, may be null
+    pub database: *const c_char,
+    /// Scope to sign in with after connecting, null to skip signing in
+    pub auth_scope: *const credentials_scope,
+    /// Credentials used to sign in, may be null
+    pub credentials: *const credentials,
+    /// Signin target, if null `namespace` and `database` are used
+    pub access: *const credentials_access,
+    /// Record access params used to sign in, may be null
+    pub auth_params: *const Object,
+    /// Capabilities of embedded engines, null for the defaults
+    pub capabilities: *const Capabilities,
+    /// Runtime to run the connection on, null to create one for this connection (since version 2)
+    pub runtime: *const SurrealRuntime,
+}
+
+impl ConnectOptions {
+    /// The shared runtime, if the caller's version of the struct has the field and it is set
+    pub(crate) fn runtime(&self) -> Option<&SurrealRuntime> {
+        match self.version >= 2 {
+            true => unsafe { self.runtime.as_ref() },
+            false => None,
+        }
+    }
+}
+
+/// Capabilities of an embedded database
+///
+/// Function and net target lists are comma-separated, `"*"` matches everything.
+/// A null list keeps the default: all functions allowed, no net targets allowed, nothing denied.
+#[repr(C)]
+pub struct Capabilities {
+    /// Enable the embedded javascript scripting runtime
+    pub scripting: bool,
+    /// Allow unauthenticated users to execute queries
+    pub guest_access: bool,
+    /// Enable live query notifications
+    pub live_query_notifications: bool,
+    /// Functions which may be executed
+    pub allow_functions: *const c_char,
+    /// Functions which may not be executed
+    pub deny_functions: *const c_char,
+    /// Network targets which may be accessed
+    pub allow_net_targets: *const c_char,
+    /// Network targets which may not be accessed
+    pub deny_net_targets: *const c_char,
+}
+
+/// Read an optional C string, null pointers and empty strings are `None`
+pub(crate) fn opt_str<'a>(ptr: *const c_char) -> Result<Option<&'a str>, SurrealError> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    let s = unsafe { CStr::from_ptr(ptr) }.to_str()?;
+    Ok((!s.is_empty()).then_some(s))
+}
+
+impl ConnectOptions {
+    /// Build the SDK configuration used when connecting
+    pub(crate) fn config(&self) -> Result<Config, SurrealError> {
+        let mut config = Config::new();
+        let query_timeout = std::time::Duration::new(self.query_timeout.secs, self.query_timeout.nanos);
+        if !query_timeout.is_zero() {
+            config = config.query_timeout(query_timeout);
+        }
+        let transaction_timeout =
+            std::time::Duration::new(self.transaction_timeout.secs, self.transaction_timeout.nanos);
+        if !transaction_timeout.is_zero() {
+            config = config.transaction_timeout(transaction_timeout);
+        }
+        if !self.capabilities.is_null() {
+            config = config.capabilities(unsafe { &*self.capabilities }.to_sdk()?);
+        }
+        Ok(config)
+    }
+}
+
+impl Capabilities {
+    fn to_sdk(&self) -> Result<sdbCapabilities, SurrealError> {
+        let mut caps = sdbCapabilities::new()
+            .with_scripting(self.scripting)
+            .with_guest_access(self.guest_access)
+            .with_live_query_notifications(self.live_query_notifications);
+
+        if let Some(list) = opt_str(self.allow_functions)? {
+            caps.allow_no_functions();
+            for target in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                if target == "*" {
+                    caps.allow_all_functions();
+                } else {
+                    caps.allow_function(target).map_err(SurrealError::invalid_argument)?;
+                }
+            }
+        }
+        if let Some(list) = opt_str(self.deny_functions)? {
+            for target in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                if target == "*" {
+                    caps.deny_all_functions();
+                } else {
+                    caps.deny_function(target).map_err(SurrealError::invalid_argument)?;
+                }
+            }
+        }
+        if let Some(list) = opt_str(self.allow_net_targets)? {
+            for target in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                if target == "*" {
+                    caps.allow_all_net_targets();
+                } else {
+                    caps.allow_net_target(target).map_err(SurrealError::invalid_argument)?;
+                }
+            }
+        }
+        if let Some(list) = opt_str(self.deny_net_targets)? {
+            for target in list.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+                if target == "*" {
+                    caps.deny_all_net_targets();
+                } else {
+                    caps.deny_net_target(target).map_err(SurrealError::invalid_argument)?;
+                }
+            }
+        }
+
+        Ok(caps)
+    }
+}
@@ -0,0 +1,230 @@
+//! Offline SurrealQL syntax validation
+//!
+//! `sr_parse_check` runs the SurrealQL parser on a query without a connection, so generated queries can
+//! be validated at build time or on startup. Syntax errors are returned as diagnostics pointing into
+//! the query text.
+//!
+//! Statements prepared with `sr_prepare` are validated by the same parser with the same settings, so a
+//! query passes `sr_parse_check` exactly when it can be prepared.
+
+use std::ffi::{c_char, c_int, CStr};
+
+use reblessive::Stack;
+use surrealdb::types::ToSql;
+use surrealdb_core::{
+    dbs::Capabilities,
+    syn::{
+        error::{Location, SyntaxError},
+        parser::Parser,
+        settings_from_capabilities,
+        token::Span,
+    },
+};
+
+use crate::{
+    array::{ArrayGen, MakeArray},
+    result::{write_error, SurrealError},
+    string::string_t,
+    utils::CStringExt2,
+    SR_ERROR,
+};
+
+/// A syntax error found in a query
+///
+/// Lines and columns start at 1, columns are counted in characters.
+/// Offsets and lengths are in bytes, so the span can be sliced out of the query directly.
+/// A diagnostic without a location has a line and column of 0.
+#[repr(C)]
+pub struct Diagnostic {
+    /// Description of the error
+    pub message: string_t,
+    /// Line of the start of the span
+    pub line: c_int,
+    /// Column of the start of the span
+    pub column: c_int,
+    /// Byte offset of the span into the query
+    pub offset: c_int,
+    /// Length of the span in bytes
+    pub length: c_int,
+}
+
+impl Diagnostic {
+    fn new(source: &str, message: &str, span: Option<Span>) -> Diagnostic {
+        match span {
+            Some(span) => {
+                let location = Location::range_of_span(source, span).start;
+                Diagnostic {
+                    message: message.to_string_t(),
+                    line: location.line as c_int,
+                    column: location.column as c_int,
+                    offset: span.offset as c_int,
+                    length: span.len as c_int,
+                }
+            }
+            None => Diagnostic {
+                message: message.to_string_t(),
+                line: 0,
+                column: 0,
+                offset: 0,
+                length: 0,
+            },
+        }
+    }
+}
+
+/// The shape of a parsed query, as far as the syntax tree of the engine can be inspected
+pub(crate) struct Parsed {
+    /// Number of top level statements
+    pub statements: usize,
+    /// Whether the query is a single LIVE statement
+    pub live: bool,
+}
+
+/// Parse a query, shared by every function which inspects a query before sending it
+///
+/// The query must be at most `u32::MAX` bytes long.
+pub(crate) fn parse(query: &str) -> Result<Parsed, SyntaxError> {
+    let settings = settings_from_capabilities(&Capabilities::all());
+    let mut parser = Parser::new_with_settings(query.as_bytes(), settings);
+    let mut stack = Stack::new();
+    let ast = stack.enter(|stk| parser.parse_query(stk)).finish()?;
+
+    let statements = ast.num_statements();
+    // LIVE is only valid as a top level statement, which is always formatted starting with its keyword
+    let live = statements == 1 && ast.to_sql().starts_with("LIVE ");
+    Ok(Parsed { statements, live })
+}
+
+/// Split a syntax error into one diagnostic per location involved
+///
+/// Each location gets the explanation the parser attached to it, or the error itself if it has none.
+fn diagnostics(query: &str, err: SyntaxError) -> Vec<Diagnostic> {
+    let mut spans = Vec::new();
+    let err = err.update_spans(|span| spans.push(*span));
+    let message = err.render_on(query).errors.join(": ");
+    if spans.is_empty() {
+        return vec![Diagnostic::new(query, &message, None)];
+    }
+
+    // labels and spans are both read from the outermost diagnostic inwards
+    let labels = span_labels(&err).filter(|labels| labels.len() == spans.len());
+    let labels = labels.unwrap_or_else(|| vec![None; spans.len()]);
+    spans
+        .into_iter()
+        .zip(labels)
+        .map(|(span, label)| Diagnostic::new(query, label.as_deref().unwrap_or(&message), Some(span)))
+        .collect()
+}
+
+/// Read the label of every span of a syntax error, from the outermost diagnostic inwards
+///
+/// The diagnostics of a syntax error are private, only their derived Debug representation exposes
+/// the label attached to each span, as `label: Some("...")` or `label: None`. Returns None if the
+/// representation cannot be read.
+fn span_labels(err: &SyntaxError) -> Option<Vec<Option<String>>> {
+    let debug = format!("{err:?}");
+    let mut labels = Vec::new();
+    let mut rest = debug.as_str();
+    while let Some(next) = rest.find(['"', 'l']) {
+        rest = &rest[next..];
+        // string literals are skipped as a whole, as they may contain anything
+        if rest.starts_with('"') {
+            (_, rest) = debug_str(rest)?;
+        } else if let Some(tail) = rest.strip_prefix("label: None") {
+            labels.push(None);
+            rest = tail;
+        } else if let Some(tail) = rest.strip_prefix("label: Some(") {
+            let (label, tail) = debug_str(tail)?;
+            labels.push(Some(label));
+            rest = tail;
+        } else {
+            rest = &rest[1..];
+        }
+    }
+    Some(labels)
+}
+
+/// Read a string literal formatted with Debug, returning it unescaped with the text following it
+fn debug_str(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.chars();
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some((out, chars.as_str())),
+            '\\' => match chars.next()? {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                '0' => out.push('\0'),
+                'u' => {
+                    let hex = chars.as_str().strip_prefix('{')?;
+                    let end = hex.find('}')?;
+                    out.push(char::from_u32(u32::from_str_radix(&hex[..end], 16).ok()?)?);
+                    chars = hex[end + 1..].chars();
+                }
+                c => out.push(c),
+            },
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// Check a query for syntax errors without executing it
+///
+/// Returns the number of diagnostics written to `diag_ptr`, 0 if the query is valid SurrealQL.
+/// The parser stops at the first error, so one error may produce several diagnostics pointing at
+/// the different locations involved, each with its own message.
+/// Free the diagnostics with `sr_free_diagnostics`.
+///
+/// # Examples
+///
+/// ```c
+/// sr_diagnostic_t *diags;
+/// int len = sr_parse_check(&err, &diags, "SELECT * FROM user WHERE");
+/// for (int i = 0; i < len; i++) {
+///     printf("%d:%d: %s\n", diags[i].line, diags[i].column, diags[i].message);
+/// }
+/// sr_free_diagnostics(diags, len);
+/// ```
+#[export_name = "sr_parse_check"]
+pub extern "C" fn parse_check(
+    err_ptr: *mut string_t,
+    diag_ptr: *mut *mut Diagnostic,
+    query: *const c_char,
+) -> c_int {
+    if diag_ptr.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("diag_ptr is null"));
+        return SR_ERROR;
+    }
+    if query.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("query is null"));
+        return SR_ERROR;
+    }
+    let query = match unsafe { CStr::from_ptr(query) }.to_str() {
+        Ok(q) => q,
+        Err(e) => {
+            write_error(err_ptr, e);
+            return SR_ERROR;
+        }
+    };
+    if u32::try_from(query.len()).is_err() {
+        write_error(err_ptr, SurrealError::invalid_argument("query is too large"));
+        return SR_ERROR;
+    }
+
+    let diagnostics = match parse(query) {
+        Ok(_) => Vec::new(),
+        Err(e) => diagnostics(query, e),
+    };
+
+    let ArrayGen { ptr, len } = diagnostics.make_array();
+    unsafe { diag_ptr.write(ptr) }
+    len
+}
+
+/// Free diagnostics returned by `sr_parse_check`
+#[export_name = "sr_free_diagnostics"]
+pub extern "C" fn free_diagnostics(ptr: *mut Diagnostic, len: c_int) {
+    ArrayGen { ptr, len }.free()
+}
@@ -13,18 +13,27 @@ use surrealdb_core::rpc::{Method, RpcProtocol, DbResult};
 use surrealdb::types::{Value as sdbValue, HashMap};
 use tokio::{runtime::Runtime, sync::RwLock};
 
-use crate::{array::MakeArray, opts::Options, stream::RpcStream, string::string_t, SR_ERROR, SR_FATAL};
+use crate::{
+    array::MakeArray,
+    opts::Options,
+    result::{write_error, SurrealError},
+    runtime::{runtime_or_new, SurrealRuntime},
+    stream::RpcStream,
+    string::string_t,
+    SR_ERROR,
+    SR_FATAL,
+};
 
 /// The object representing a Surreal RPC connection
 ///
 /// It is safe to be referenced from multiple threads
-/// If any operation, on any thread returns SR_FATAL then the connection is poisoned and must not be used again.
-/// (use will cause the program to abort)
+/// If any operation, on any thread returns SR_FATAL then the connection is poisoned,
+/// and every further call fails with SR_FATAL, so it must be freed and created again.
 ///
 /// should be freed with sr_surreal_rpc_free
 pub struct SurrealRpc {
     inner: RwLock<SurrealRpcInner>,
-    rt: Runtime,
+    rt: Arc<Runtime>,
     ps: AtomicBool,
 }
 /// create new rpc context
@@ -46,14 +55,45 @@ impl SurrealRpc {
         endpoint: *const c_char,
         options: Options,
     ) -> c_int {
-        let res: Result<Result<SurrealRpc, string_t>, _> = catch_unwind(AssertUnwindSafe(|| {
+        Self::new_impl(err_ptr, surreal_ptr, endpoint, options, None)
+    }
+
+    /// Create a new rpc context running on a shared runtime
+    ///
+    /// Identical to `sr_surreal_rpc_new` apart from `runtime`, which may be null to create a runtime
+    /// for this context.
+    #[export_name = "sr_surreal_rpc_new_with_runtime"]
+    pub extern "C" fn new_with_runtime(
+        err_ptr: *mut string_t,
+        surreal_ptr: *mut *mut SurrealRpc,
+        endpoint: *const c_char,
+        options: Options,
+        runtime: *const SurrealRuntime,
+    ) -> c_int {
+        Self::new_impl(err_ptr, surreal_ptr, endpoint, options, unsafe { runtime.as_ref() })
+    }
+
+    fn new_impl(
+        err_ptr: *mut string_t,
+        surreal_ptr: *mut *mut SurrealRpc,
+        endpoint: *const c_char,
+        options: Options,
+        shared: Option<&SurrealRuntime>,
+    ) -> c_int {
+        if surreal_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("surreal_ptr is null"));
+            return SR_ERROR;
+        }
+        if endpoint.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("endpoint is null"));
+            return SR_ERROR;
+        }
+        let res: Result<Result<SurrealRpc, SurrealError>, _> = catch_unwind(AssertUnwindSafe(|| {
             let Ok(endpoint) = (unsafe { CStr::from_ptr(endpoint).to_str() }) else {
-                return Err("Invalid UTF-8".into());
+                return Err(SurrealError::invalid_argument("Invalid UTF-8"));
             };
 
-            let Ok(rt) = Runtime::new() else {
-                return Err("error creating runtime".into());
-            };
+            let rt = runtime_or_new(shared)?;
 
             let con_fut = Datastore::new(endpoint);
 
@@ -90,15 +130,10 @@ impl SurrealRpc {
             })
         }));
 
-        let res: Result<SurrealRpc, string_t> = match res {
+        let res: Result<SurrealRpc, SurrealError> = match res {
             Ok(r) => r,
             Err(e) => {
-                if let Some(e_str) = e.downcast_ref::<&str>() {
-                    let e_string: string_t = format!("Panicked with: {e_str}").into();
-                    unsafe { err_ptr.write(e_string) }
-                } else {
-                    unsafe { err_ptr.write("Panicked".into()) }
-                }
+                write_error(err_ptr, SurrealError::panicked(&*e));
                 return SR_FATAL;
             }
         };
@@ -110,7 +145,7 @@ impl SurrealRpc {
                 1
             }
             Err(e) => {
-                unsafe { err_ptr.write(e) }
+                write_error(err_ptr, e);
                 SR_ERROR
             }
         }
@@ -135,15 +170,11 @@ impl SurrealRpc {
         len: c_int,
     ) -> c_int {
         if res_ptr.is_null() {
-            if !err_ptr.is_null() {
-                unsafe { err_ptr.write("res_ptr is null".into()) };
-            }
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
             return SR_ERROR;
         }
         if ptr.is_null() {
-            if !err_ptr.is_null() {
-                unsafe { err_ptr.write("ptr is null".into()) };
-            }
+            write_error(err_ptr, SurrealError::invalid_argument("ptr is null"));
             return SR_ERROR;
         }
         with_async(self, err_ptr, |ctx| async {
@@ -176,7 +207,7 @@ impl SurrealRpc {
                     Ok(out.len)
                 }
                 _ => {
-                    Err(string_t::from("C SDK: RPC::execute had unimplemented response."))
+                    Err("C SDK: RPC::execute had unimplemented response.".into())
                 }
             }
         })
@@ -197,9 +228,7 @@ impl SurrealRpc {
         stream_ptr: *mut *mut RpcStream,
     ) -> c_int {
         if stream_ptr.is_null() {
-            if !err_ptr.is_null() {
-                unsafe { err_ptr.write("stream_ptr is null".into()) };
-            }
+            write_error(err_ptr, SurrealError::invalid_argument("stream_ptr is null"));
             return SR_ERROR;
         }
         with_async(self, err_ptr, |ctx| async {
@@ -211,7 +240,7 @@ impl SurrealRpc {
                 .notifications()
                 .ok_or(string_t::from("Notifications not enabled"))?;
 
-            let rpc_stream = RpcStream::new(receiver);
+            let rpc_stream = RpcStream::new(receiver, ctx.rt.handle().clone());
             let stream_boxed = Box::new(rpc_stream);
             unsafe { stream_ptr.write(Box::leak(stream_boxed)) };
 
@@ -226,7 +255,11 @@ impl SurrealRpc {
             return;
         }
         let boxed = unsafe { Box::from_raw(ctx) };
-        drop(boxed)
+        // the runtime may be shared, so drop the context inside it
+        let rt = boxed.rt.clone();
+        let guard = rt.enter();
+        drop(boxed);
+        drop(guard);
     }
 }
 
@@ -313,22 +346,19 @@ fn with_async<'a, 'b, C, F>(ctx: &'a SurrealRpc, err_ptr: *mut string_t, fun: C)
 where
     'a: 'b,
     C: FnOnce(&'a SurrealRpc) -> F + 'b,
-    F: std::future::Future<Output = Result<c_int, string_t>>,
+    F: std::future::Future<Output = Result<c_int, SurrealError>>,
 {
     if ctx.ps.load(Ordering::Acquire) {
-        std::process::abort()
+        write_error(err_ptr, SurrealError::poisoned());
+        return SR_FATAL;
     }
     let _guard = ctx.rt.enter();
 
     let res = match catch_unwind(AssertUnwindSafe(|| ctx.rt.block_on(fun(&ctx)))) {
         Ok(r) => r,
         Err(e) => {
-            if let Some(e_str) = e.downcast_ref::<&str>() {
-                let e_string: string_t = format!("Panicked with: {e_str}").into();
-                unsafe { err_ptr.write(e_string) }
-            } else {
-                unsafe { err_ptr.write("Panicked".into()) }
-            }
+            ctx.ps.store(true, Ordering::Release);
+            write_error(err_ptr, SurrealError::panicked(&*e));
             return SR_FATAL;
         }
     };
@@ -336,7 +366,7 @@ where
     match res {
         Ok(n) => n,
         Err(e) => {
-            unsafe { err_ptr.write(e) }
+            write_error(err_ptr, e);
             SR_ERROR
         }
     }
@@ -0,0 +1,179 @@
+//! Runtimes shared by several connections
+//!
+//! By default every `sr_surreal_t` and `sr_surreal_rpc_t` creates its own multi-threaded runtime.
+//! A `sr_runtime_t` can be passed to the connect functions instead, so that many handles share one
+//! thread pool.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{
+    callback::UserData,
+    result::{write_error, SurrealError},
+    string::string_t,
+    SR_ERROR, SR_FATAL, SR_NONE,
+};
+
+/// The current version of `sr_runtime_options_t`
+///
+/// Callers must set `version` to this value so that fields added in later releases can be detected.
+pub const SR_RUNTIME_OPTIONS_VERSION: u32 = 1;
+
+/// Hook invoked on a runtime thread, with the `userdata` of its `sr_runtime_options_t`
+pub type ThreadCallback = Option<extern "C" fn(userdata: *mut c_void)>;
+
+/// Options for `sr_runtime_new`
+///
+/// Zero or null fields are treated as unset, so a zero-initialised struct with only `version` set
+/// creates the same runtime as a connection would.
+#[repr(C)]
+pub struct RuntimeOptions {
+    /// Must be set to SR_RUNTIME_OPTIONS_VERSION
+    pub version: u32,
+    /// Number of worker threads, zero for one per CPU core (ignored in current-thread mode)
+    pub worker_threads: u32,
+    /// Run operations on the threads calling into the library instead of a thread pool
+    ///
+    /// Callback and future based operations then only make progress while a blocking call is running.
+    pub current_thread: bool,
+    /// Name of the runtime's threads, null for the default
+    pub thread_name: *const c_char,
+    /// Called on each runtime thread after it starts, may be null
+    pub on_thread_start: ThreadCallback,
+    /// Called on each runtime thread before it stops, may be null
+    pub on_thread_stop: ThreadCallback,
+    /// Passed to the thread hooks, must be usable from any thread
+    pub userdata: *mut c_void,
+}
+
+impl RuntimeOptions {
+    fn build(&self) -> Result<Runtime, SurrealError> {
+        if self.version == 0 || self.version > SR_RUNTIME_OPTIONS_VERSION {
+            return Err(SurrealError::invalid_argument(format!(
+                "unsupported runtime options version: {}",
+                self.version
+            )));
+        }
+
+        let mut builder = match self.current_thread {
+            true => Builder::new_current_thread(),
+            false => Builder::new_multi_thread(),
+        };
+        builder.enable_all();
+        if self.worker_threads > 0 && !self.current_thread {
+            builder.worker_threads(self.worker_threads as usize);
+        }
+        if !self.thread_name.is_null() {
+            builder.thread_name(unsafe { CStr::from_ptr(self.thread_name) }.to_str()?);
+        }
+
+        let userdata = UserData(self.userdata);
+        if let Some(hook) = self.on_thread_start {
+            builder.on_thread_start(move || hook(userdata.get()));
+        }
+        if let Some(hook) = self.on_thread_stop {
+            builder.on_thread_stop(move || hook(userdata.get()));
+        }
+
+        builder
+            .build()
+            .map_err(|e| SurrealError::from_msg(format!("error creating runtime: {e}")))
+    }
+}
+
+/// A runtime which can be shared by several connections
+///
+/// Connections keep the runtime alive, so it may be freed with `sr_runtime_free` while they are still open.
+pub struct SurrealRuntime {
+    rt: Arc<Runtime>,
+}
+
+impl SurrealRuntime {
+    /// Create a new runtime
+    ///
+    /// `options` may be null for the defaults.
+    /// Returns SR_NONE and writes the runtime to `rt_ptr` on success.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_runtime_options_t opts = {0};
+    /// opts.version = sr_SR_RUNTIME_OPTIONS_VERSION;
+    /// opts.worker_threads = 4;
+    /// opts.thread_name = "surreal-worker";
+    ///
+    /// sr_runtime_t *rt;
+    /// if (sr_runtime_new(&err, &rt, &opts) < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    ///
+    /// sr_connect_options_t copts = {0};
+    /// copts.version = sr_SR_CONNECT_OPTIONS_VERSION;
+    /// copts.runtime = rt;
+    /// sr_connect_with_options(&err, &db1, "mem://", &copts);
+    /// sr_connect_with_options(&err, &db2, "mem://", &copts);
+    /// sr_runtime_free(rt);
+    /// ```
+    #[export_name = "sr_runtime_new"]
+    pub extern "C" fn create(
+        err_ptr: *mut string_t,
+        rt_ptr: *mut *mut SurrealRuntime,
+        options: *const RuntimeOptions,
+    ) -> c_int {
+        if rt_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("rt_ptr is null"));
+            return SR_ERROR;
+        }
+
+        let res = catch_unwind(AssertUnwindSafe(|| match unsafe { options.as_ref() } {
+            Some(options) => options.build(),
+            None => Runtime::new().map_err(|e| SurrealError::from_msg(format!("error creating runtime: {e}"))),
+        }));
+
+        match res {
+            Ok(Ok(rt)) => {
+                let boxed = Box::new(SurrealRuntime { rt: Arc::new(rt) });
+                unsafe { rt_ptr.write(Box::into_raw(boxed)) }
+                SR_NONE
+            }
+            Ok(Err(e)) => {
+                write_error(err_ptr, e);
+                SR_ERROR
+            }
+            Err(e) => {
+                write_error(err_ptr, SurrealError::panicked(&*e));
+                SR_FATAL
+            }
+        }
+    }
+
+    /// Release a runtime
+    ///
+    /// The runtime shuts down once every connection using it has been disconnected.
+    #[export_name = "sr_runtime_free"]
+    pub extern "C" fn free(rt: *mut SurrealRuntime) {
+        if !rt.is_null() {
+            catch_unwind(AssertUnwindSafe(|| drop(unsafe { Box::from_raw(rt) }))).ok();
+        }
+    }
+
+    pub(crate) fn shared(&self) -> Arc<Runtime> {
+        self.rt.clone()
+    }
+}
+
+/// The runtime of a new connection: the shared one if given, otherwise a new one
+pub(crate) fn runtime_or_new(shared: Option<&SurrealRuntime>) -> Result<Arc<Runtime>, SurrealError> {
+    match shared {
+        Some(rt) => Ok(rt.shared()),
+        None => Runtime::new()
+            .map(Arc::new)
+            .map_err(|_| SurrealError::from_msg("error creating runtime")),
+    }
+}
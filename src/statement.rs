@@ -0,0 +1,214 @@
+//! Prepared statements
+//!
+//! `sr_prepare` validates a query with the SurrealQL parser, with the same settings as
+//! `sr_parse_check`, so syntax errors are reported before anything is executed, and keeps its text
+//! together with bound parameters for repeated execution.
+//! No parsed form is kept: the SDK only accepts query text, so each `sr_statement_execute` sends the
+//! text again and the engine parses it again, exactly like `sr_query`.
+
+use std::ffi::{c_char, c_int, CStr};
+
+use surrealdb::types::{Number as sdbNumber, Object as sdbObject, Value as sdbValue};
+
+use crate::{
+    array::{ArrayGen, MakeArray},
+    ops, parse,
+    result::{write_error, ArrayResult, ErrorKind, SurrealError},
+    string::string_t,
+    value::Value,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// A validated query with its bound parameters
+///
+/// Bound parameters are kept between executions until they are rebound or cleared.
+/// Must not be used concurrently, and must be freed with `sr_statement_free` before its
+/// connection is disconnected.
+pub struct Statement {
+    db: *const Surreal,
+    query: String,
+    vars: sdbObject,
+}
+
+impl Statement {
+    /// Validate a query and prepare it for execution on a connection
+    ///
+    /// Returns SR_NONE and writes the statement to `stmt_ptr`, or SR_ERROR with an error of kind
+    /// SR_ERROR_KIND_PARSE if the query is not valid SurrealQL.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the statement
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `stmt_ptr` must be a valid pointer to receive the statement
+    /// - `query` must be a valid null-terminated UTF-8 string
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_statement_t *stmt;
+    /// if (sr_prepare(db, &err, &stmt, "CREATE event SET kind = $kind, value = $value") < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// for (int i = 0; i < 1000; i++) {
+    ///     sr_statement_bind_str(stmt, "kind", "tick");
+    ///     sr_statement_bind_int(stmt, "value", i);
+    ///
+    ///     sr_arr_res_t *res;
+    ///     int len = sr_statement_execute(stmt, &err, &res);
+    ///     if (len >= 0) {
+    ///         sr_free_arr_res_arr(res, len);
+    ///     }
+    /// }
+    /// sr_statement_free(stmt);
+    /// ```
+    #[export_name = "sr_prepare"]
+    pub extern "C" fn prepare(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        stmt_ptr: *mut *mut Statement,
+        query: *const c_char,
+    ) -> c_int {
+        if stmt_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("stmt_ptr is null"));
+            return SR_ERROR;
+        }
+        if query.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("query is null"));
+            return SR_ERROR;
+        }
+        let query = match unsafe { CStr::from_ptr(query) }.to_str() {
+            Ok(q) => q,
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+
+        if u32::try_from(query.len()).is_err() {
+            write_error(err_ptr, SurrealError::invalid_argument("query is too large"));
+            return SR_ERROR;
+        }
+        if let Err(e) = parse::parse(query) {
+            let msg = format!("Parse error: {}", e.render_on(query));
+            write_error(err_ptr, SurrealError::new(ErrorKind::SR_ERROR_KIND_PARSE, msg));
+            return SR_ERROR;
+        }
+
+        let stmt = Statement {
+            db,
+            query: query.to_owned(),
+            vars: sdbObject::new(),
+        };
+        unsafe { stmt_ptr.write(Box::into_raw(Box::new(stmt))) }
+        SR_NONE
+    }
+
+    /// Execute a prepared statement with its bound parameters
+    ///
+    /// The query text is sent and parsed by the engine on every call, as with `sr_query`.
+    /// Writes the results to *res_ptr like `sr_query` and returns the number of statements.
+    /// Free the results with `sr_free_arr_res_arr`.
+    #[export_name = "sr_statement_execute"]
+    pub extern "C" fn execute(&self, err_ptr: *mut string_t, res_ptr: *mut *mut ArrayResult) -> c_int {
+        if res_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+            return SR_ERROR;
+        }
+        let db = unsafe { &*self.db };
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let results = ops::query(&surreal.db(), &self.query, self.vars.clone()).await?;
+
+            let ArrayGen { ptr, len } = ops::into_results(results).make_array();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len)
+        })
+    }
+
+    fn bind(&mut self, err_ptr: *mut string_t, name: *const c_char, value: sdbValue) -> c_int {
+        if name.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("name is null"));
+            return SR_ERROR;
+        }
+        let name = match unsafe { CStr::from_ptr(name) }.to_str() {
+            Ok(n) => n,
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+        let name = name.strip_prefix('$').unwrap_or(name);
+        self.vars.insert(name.to_owned(), value);
+        SR_NONE
+    }
+
+    /// Bind a value to a named parameter
+    ///
+    /// `name` may be given with or without the leading `$`. The value is copied.
+    #[export_name = "sr_statement_bind"]
+    pub extern "C" fn bind_value(&mut self, err_ptr: *mut string_t, name: *const c_char, value: *const Value) -> c_int {
+        if value.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("value is null"));
+            return SR_ERROR;
+        }
+        let value = sdbValue::from(unsafe { &*value }.clone());
+        self.bind(err_ptr, name, value)
+    }
+
+    /// Bind a string to a named parameter
+    #[export_name = "sr_statement_bind_str"]
+    pub extern "C" fn bind_str(&mut self, err_ptr: *mut string_t, name: *const c_char, value: *const c_char) -> c_int {
+        if value.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("value is null"));
+            return SR_ERROR;
+        }
+        let value = match unsafe { CStr::from_ptr(value) }.to_str() {
+            Ok(v) => v.to_owned(),
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+        self.bind(err_ptr, name, sdbValue::String(value))
+    }
+
+    /// Bind an integer to a named parameter
+    #[export_name = "sr_statement_bind_int"]
+    pub extern "C" fn bind_int(&mut self, err_ptr: *mut string_t, name: *const c_char, value: i64) -> c_int {
+        self.bind(err_ptr, name, sdbValue::Number(sdbNumber::Int(value)))
+    }
+
+    /// Bind a float to a named parameter
+    #[export_name = "sr_statement_bind_float"]
+    pub extern "C" fn bind_float(&mut self, err_ptr: *mut string_t, name: *const c_char, value: f64) -> c_int {
+        self.bind(err_ptr, name, sdbValue::Number(sdbNumber::Float(value)))
+    }
+
+    /// Bind a boolean to a named parameter
+    #[export_name = "sr_statement_bind_bool"]
+    pub extern "C" fn bind_bool(&mut self, err_ptr: *mut string_t, name: *const c_char, value: bool) -> c_int {
+        self.bind(err_ptr, name, sdbValue::Bool(value))
+    }
+
+    /// Bind NULL to a named parameter
+    #[export_name = "sr_statement_bind_null"]
+    pub extern "C" fn bind_null(&mut self, err_ptr: *mut string_t, name: *const c_char) -> c_int {
+        self.bind(err_ptr, name, sdbValue::Null)
+    }
+
+    /// Remove all bound parameters
+    #[export_name = "sr_statement_clear_bindings"]
+    pub extern "C" fn clear_bindings(&mut self) {
+        self.vars = sdbObject::new();
+    }
+
+    /// Free a prepared statement
+    #[export_name = "sr_statement_free"]
+    pub extern "C" fn free(stmt: *mut Statement) {
+        if !stmt.is_null() {
+            let _ = unsafe { Box::from_raw(stmt) };
+        }
+    }
+}
@@ -0,0 +1,433 @@
+//! Client-side transactions
+//!
+//! `sr_transaction_begin` starts a transaction on the server and returns a handle; every operation
+//! called with the handle runs inside that transaction until it is committed or rolled back.
+//! Unlike `sr_begin`, which only sends `BEGIN TRANSACTION` as its own query, the transaction spans
+//! any number of calls.
+//!
+//! Engines with optimistic concurrency, such as SurrealKV, fail commits which conflict with another
+//! transaction. `sr_transaction_run` retries a unit of work when that happens.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int, c_void, CStr},
+};
+
+use surrealdb::{
+    engine::any::Any,
+    method::Transaction as sdbTransaction,
+    types::{Object as sdbObject, Value as sdbValue},
+};
+
+use crate::{
+    array::{ArrayGen, MakeArray},
+    callback::UserData,
+    object::Object,
+    ops,
+    result::{get_last_error, set_last_error, write_error, ArrayResult, ErrorKind, ErrorOut, SurrealError},
+    string::string_t,
+    value::Value,
+    with_surreal_async, Surreal, SR_ERROR, SR_NONE,
+};
+
+/// Unit of work run by `sr_transaction_run`
+///
+/// Must only use `txn` for operations and must not commit or roll it back.
+/// Returns a non-negative value on success, or a negative value to roll the transaction back.
+/// A callback failing because of an operation on `txn` should return right after it, so that the
+/// error of the operation is the one reported by `sr_transaction_run`.
+pub type TransactionCallback = Option<extern "C" fn(txn: *mut Transaction, userdata: *mut c_void) -> c_int>;
+
+/// A transaction in progress on a connection
+///
+/// Must not be used concurrently, and must be finished with `sr_transaction_commit` or
+/// `sr_transaction_rollback` before its connection is disconnected.
+pub struct Transaction {
+    db: *const Surreal,
+    txn: sdbTransaction<Any>,
+    /// Error of the last operation, if it failed
+    failure: RefCell<Option<SurrealError>>,
+}
+
+/// Error destination of an operation inside a transaction, which also records the error on it
+#[derive(Clone, Copy)]
+struct Tracked<'a> {
+    txn: &'a Transaction,
+    err_ptr: *mut string_t,
+}
+
+impl ErrorOut for Tracked<'_> {
+    fn write(self, err: SurrealError) {
+        *self.txn.failure.borrow_mut() = Some(err.clone());
+        ErrorOut::write(self.err_ptr, err)
+    }
+}
+
+impl Transaction {
+    /// Begin a transaction
+    ///
+    /// Writes the transaction to *txn_ptr and returns SR_NONE.
+    ///
+    /// # Safety
+    ///
+    /// - `db` must be a valid pointer to a Surreal connection, which outlives the transaction
+    /// - `err_ptr` must be a valid pointer or null
+    /// - `txn_ptr` must be a valid pointer to receive the transaction
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// sr_transaction_t *txn;
+    /// if (sr_transaction_begin(db, &err, &txn) < 0) {
+    ///     printf("%s", err);
+    ///     return 1;
+    /// }
+    /// sr_arr_res_t *res;
+    /// int len = sr_transaction_query(txn, &err, &res, "UPDATE account:a SET balance -= 10", NULL);
+    /// if (len < 0) {
+    ///     sr_transaction_rollback(txn, NULL);
+    ///     return 1;
+    /// }
+    /// sr_free_arr_res_arr(res, len);
+    /// if (sr_transaction_commit(txn, &err) < 0) {
+    ///     printf("%s", err);
+    /// }
+    /// ```
+    #[export_name = "sr_transaction_begin"]
+    pub extern "C" fn begin(db: &Surreal, err_ptr: *mut string_t, txn_ptr: *mut *mut Transaction) -> c_int {
+        if txn_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("txn_ptr is null"));
+            return SR_ERROR;
+        }
+        with_surreal_async(db, err_ptr, |surreal| async {
+            let txn = ops::begin(&surreal.db()).await?;
+            let boxed = Box::new(Transaction {
+                db,
+                txn,
+                failure: RefCell::new(None),
+            });
+            unsafe { txn_ptr.write(Box::into_raw(boxed)) }
+            Ok(SR_NONE)
+        })
+    }
+
+    /// Commit a transaction
+    ///
+    /// The transaction is freed whether or not the commit succeeds, and must not be used again.
+    /// A commit which conflicts with another transaction fails with an error of kind
+    /// SR_ERROR_KIND_CONFLICT, and can be retried by running the transaction again.
+    #[export_name = "sr_transaction_commit"]
+    pub extern "C" fn commit(txn: *mut Transaction, err_ptr: *mut string_t) -> c_int {
+        if txn.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("txn is null"));
+            return SR_ERROR;
+        }
+        let Transaction { db, txn, .. } = *unsafe { Box::from_raw(txn) };
+        with_surreal_async(unsafe { &*db }, err_ptr, |_| async {
+            ops::commit(txn).await?;
+            Ok(SR_NONE)
+        })
+    }
+
+    /// Roll back a transaction, discarding its changes
+    ///
+    /// The transaction is freed whether or not the rollback succeeds, and must not be used again.
+    #[export_name = "sr_transaction_rollback"]
+    pub extern "C" fn rollback(txn: *mut Transaction, err_ptr: *mut string_t) -> c_int {
+        if txn.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("txn is null"));
+            return SR_ERROR;
+        }
+        let Transaction { db, txn, .. } = *unsafe { Box::from_raw(txn) };
+        with_surreal_async(unsafe { &*db }, err_ptr, |_| async {
+            ops::rollback(txn).await?;
+            Ok(SR_NONE)
+        })
+    }
+
+    /// Execute a SurrealQL query inside a transaction
+    ///
+    /// Behaves like `sr_query`.
+    #[export_name = "sr_transaction_query"]
+    pub extern "C" fn query(
+        &self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut ArrayResult,
+        query: *const c_char,
+        vars: *const Object,
+    ) -> c_int {
+        let err_ptr = self.tracked(err_ptr);
+        if res_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+            return SR_ERROR;
+        }
+        if query.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("query is null"));
+            return SR_ERROR;
+        }
+        with_surreal_async(self.db(), err_ptr, |_| async {
+            let query = unsafe { CStr::from_ptr(query) }.to_str()?;
+            let vars: sdbObject = match vars.is_null() {
+                true => sdbObject::default(),
+                false => unsafe { &*vars }.clone().into(),
+            };
+
+            let results = ops::query_in(&self.txn, query, vars).await?;
+
+            let ArrayGen { ptr, len } = ops::into_results(results).make_array();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len)
+        })
+    }
+
+    /// Select records inside a transaction
+    ///
+    /// Behaves like `sr_select`.
+    #[export_name = "sr_transaction_select"]
+    pub extern "C" fn select(&self, err_ptr: *mut string_t, res_ptr: *mut *mut Value, resource: *const c_char) -> c_int {
+        self.records(err_ptr, res_ptr, resource, ops::select_in)
+    }
+
+    /// Create a record inside a transaction
+    ///
+    /// Behaves like `sr_create`.
+    #[export_name = "sr_transaction_create"]
+    pub extern "C" fn create(
+        &self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut &mut Object,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        let err_ptr = self.tracked(err_ptr);
+        if resource.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("resource is null"));
+            return SR_ERROR;
+        }
+        if content.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("content is null"));
+            return SR_ERROR;
+        }
+        with_surreal_async(self.db(), err_ptr, |_| async {
+            let resource = unsafe { CStr::from_ptr(resource) }.to_str()?;
+            let content = sdbObject::from(unsafe { &*content }.clone());
+
+            let obj = ops::create_in(&self.txn, resource, content).await?;
+            if !res_ptr.is_null() {
+                let boxed = Box::new(Object::from(obj));
+                unsafe { res_ptr.write(Box::leak(boxed)) }
+                Ok(1)
+            } else {
+                Ok(0)
+            }
+        })
+    }
+
+    /// Insert records inside a transaction
+    ///
+    /// Behaves like `sr_insert`.
+    #[export_name = "sr_transaction_insert"]
+    pub extern "C" fn insert(
+        &self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        self.records_with(err_ptr, res_ptr, resource, content, |txn, resource, content| {
+            ops::insert_in(txn, resource, content)
+        })
+    }
+
+    /// Replace records inside a transaction
+    ///
+    /// Behaves like `sr_update`.
+    #[export_name = "sr_transaction_update"]
+    pub extern "C" fn update(
+        &self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        self.records_with(err_ptr, res_ptr, resource, content, |txn, resource, content| {
+            ops::update_in(txn, resource, content)
+        })
+    }
+
+    /// Create or replace records inside a transaction
+    ///
+    /// Behaves like `sr_upsert`.
+    #[export_name = "sr_transaction_upsert"]
+    pub extern "C" fn upsert(
+        &self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+    ) -> c_int {
+        self.records_with(err_ptr, res_ptr, resource, content, |txn, resource, content| {
+            ops::upsert_in(txn, resource, content)
+        })
+    }
+
+    /// Delete records inside a transaction
+    ///
+    /// Behaves like `sr_delete`.
+    #[export_name = "sr_transaction_delete"]
+    pub extern "C" fn delete(&self, err_ptr: *mut string_t, res_ptr: *mut *mut Value, resource: *const c_char) -> c_int {
+        self.records(err_ptr, res_ptr, resource, ops::delete_in)
+    }
+
+    /// Run a unit of work in a transaction, retrying it when the commit conflicts
+    ///
+    /// Begins a transaction, passes it to `callback` and commits it if the callback succeeds.
+    /// If the callback or the commit fails with an error of kind SR_ERROR_KIND_CONFLICT, the work is
+    /// run again in a new transaction, up to `max_retries` times. Other failures roll the transaction
+    /// back and are returned without retrying.
+    ///
+    /// Conflicts are detected from the errors the transaction records itself, not from `sr_last_error`:
+    /// the callback failed with a conflict if it returns a negative value and the last operation it ran
+    /// on `txn` failed with SR_ERROR_KIND_CONFLICT. That error is the one written to `err_ptr`, or a
+    /// generic error naming the returned value if the last operation succeeded.
+    ///
+    /// Returns the value returned by the last run of the callback, or a negative error code.
+    /// The last error of the calling thread is left as it was before the call if the run succeeds.
+    ///
+    /// # Examples
+    ///
+    /// ```c
+    /// int transfer(sr_transaction_t *txn, void *userdata) {
+    ///     sr_arr_res_t *res;
+    ///     int len = sr_transaction_query(txn, NULL, &res,
+    ///         "UPDATE account:a SET balance -= 10; UPDATE account:b SET balance += 10", NULL);
+    ///     if (len < 0) return len;
+    ///     sr_free_arr_res_arr(res, len);
+    ///     return 0;
+    /// }
+    ///
+    /// if (sr_transaction_run(db, &err, transfer, NULL, 5) < 0) {
+    ///     printf("%s", err);
+    /// }
+    /// ```
+    #[export_name = "sr_transaction_run"]
+    pub extern "C" fn run(
+        db: &Surreal,
+        err_ptr: *mut string_t,
+        callback: TransactionCallback,
+        userdata: *mut c_void,
+        max_retries: c_int,
+    ) -> c_int {
+        let Some(callback) = callback else {
+            write_error(err_ptr, SurrealError::invalid_argument("callback is null"));
+            return SR_ERROR;
+        };
+        let userdata = UserData(userdata);
+        let last_error = get_last_error();
+
+        let mut attempt = 0;
+        loop {
+            let mut txn = std::ptr::null_mut();
+            let res = Transaction::begin(db, err_ptr, &mut txn);
+            if res < 0 {
+                return res;
+            }
+
+            let res = callback(txn, userdata.get());
+            if res < 0 {
+                let err = unsafe { &*txn }.failure.borrow_mut().take().unwrap_or_else(|| {
+                    SurrealError::from_msg(format!("transaction callback failed with code {res}"))
+                });
+                Transaction::rollback(txn, std::ptr::null_mut());
+                if err.kind == ErrorKind::SR_ERROR_KIND_CONFLICT && attempt < max_retries {
+                    attempt += 1;
+                    continue;
+                }
+                write_error(err_ptr, err);
+                return res;
+            }
+
+            // only the error of the last attempt is handed to the caller
+            let commit = Transaction::commit(txn, std::ptr::null_mut());
+            if commit < 0 {
+                let err = get_last_error();
+                if err.kind == ErrorKind::SR_ERROR_KIND_CONFLICT && attempt < max_retries {
+                    attempt += 1;
+                    continue;
+                }
+                write_error(err_ptr, err);
+                return commit;
+            }
+            set_last_error(last_error);
+            return res;
+        }
+    }
+
+    fn db(&self) -> &Surreal {
+        unsafe { &*self.db }
+    }
+
+    /// Start an operation, recording its error on the transaction if it fails
+    fn tracked(&self, err_ptr: *mut string_t) -> Tracked<'_> {
+        self.failure.borrow_mut().take();
+        Tracked { txn: self, err_ptr }
+    }
+
+    fn records<'a, F, Fut>(
+        &'a self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        fun: F,
+    ) -> c_int
+    where
+        F: FnOnce(&'a sdbTransaction<Any>, &'a str) -> Fut,
+        Fut: std::future::Future<Output = Result<sdbValue, SurrealError>>,
+    {
+        let err_ptr = self.tracked(err_ptr);
+        if res_ptr.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("res_ptr is null"));
+            return SR_ERROR;
+        }
+        if resource.is_null() {
+            write_error(err_ptr, SurrealError::invalid_argument("resource is null"));
+            return SR_ERROR;
+        }
+        let resource = match unsafe { CStr::from_ptr(resource) }.to_str() {
+            Ok(r) => r,
+            Err(e) => {
+                write_error(err_ptr, e);
+                return SR_ERROR;
+            }
+        };
+        with_surreal_async(self.db(), err_ptr, |_| async {
+            let val = fun(&self.txn, resource).await?;
+
+            let ArrayGen { ptr, len } = ops::into_array(val).into();
+            unsafe { res_ptr.write(ptr) }
+
+            Ok(len as c_int)
+        })
+    }
+
+    fn records_with<'a, F, Fut>(
+        &'a self,
+        err_ptr: *mut string_t,
+        res_ptr: *mut *mut Value,
+        resource: *const c_char,
+        content: *const Object,
+        fun: F,
+    ) -> c_int
+    where
+        F: FnOnce(&'a sdbTransaction<Any>, &'a str, sdbObject) -> Fut,
+        Fut: std::future::Future<Output = Result<sdbValue, SurrealError>>,
+    {
+        if content.is_null() {
+            write_error(self.tracked(err_ptr), SurrealError::invalid_argument("content is null"));
+            return SR_ERROR;
+        }
+        let content = sdbObject::from(unsafe { &*content }.clone());
+        self.records(err_ptr, res_ptr, resource, |txn, resource| fun(txn, resource, content))
+    }
+}
@@ -0,0 +1,402 @@
+use std::ffi::{c_int, CStr};
+
+use surrealdb::types::{Object as sdbObject, Value as sdbValue};
+use surrealdb::Notification as sdbNotification;
+
+use crate::array::{ArrayGen, MakeArray};
+use crate::notification::Action;
+use crate::result::{write_error, SurrealError};
+use crate::utils::CStringExt2;
+use crate::{string::string_t, uuid::Uuid, value::Value};
+use crate::{SR_ERROR, SR_NONE};
+
+/// The kind of a patch operation
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffOpKind {
+    /// Insert `value` at `path`, into the array or object holding it
+    SR_DIFF_ADD,
+    /// Remove the value at `path`
+    SR_DIFF_REMOVE,
+    /// Replace the value at `path` with `value`
+    SR_DIFF_REPLACE,
+    /// Apply the text patch in `value`, in diff-match-patch format, to the string at `path`
+    SR_DIFF_CHANGE,
+    /// Copy the value at `from` to `path`
+    SR_DIFF_COPY,
+    /// Move the value at `from` to `path`
+    SR_DIFF_MOVE,
+    /// Fail unless the value at `path` equals `value`
+    SR_DIFF_TEST,
+}
+
+/// A single patch operation of a diff
+///
+/// Paths are JSON pointers such as `/tags/0`, the empty path designates the whole document.
+#[repr(C)]
+#[derive(Debug, Clone)]
+pub struct DiffOp {
+    pub op: DiffOpKind,
+    pub path: string_t,
+    /// Source path of SR_DIFF_COPY and SR_DIFF_MOVE, null otherwise
+    pub from: string_t,
+    /// Operand of the operation, SR_VALUE_NONE if it has none
+    pub value: Value,
+}
+
+/// A live query notification holding the changes of the record as patch operations
+///
+/// Received with `sr_stream_next_diff` from the stream of a `LIVE SELECT DIFF` statement.
+/// Must be freed with `sr_free_diff_notification`
+#[repr(C)]
+pub struct DiffNotification {
+    pub query_id: Uuid,
+    pub action: Action,
+    pub ops: *mut DiffOp,
+    pub ops_len: c_int,
+}
+
+impl TryFrom<sdbObject> for DiffOp {
+    type Error = SurrealError;
+
+    fn try_from(mut obj: sdbObject) -> Result<Self, Self::Error> {
+        let op = match obj.get("op") {
+            Some(sdbValue::String(op)) => match op.as_str() {
+                "add" => DiffOpKind::SR_DIFF_ADD,
+                "remove" => DiffOpKind::SR_DIFF_REMOVE,
+                "replace" => DiffOpKind::SR_DIFF_REPLACE,
+                "change" => DiffOpKind::SR_DIFF_CHANGE,
+                "copy" => DiffOpKind::SR_DIFF_COPY,
+                "move" => DiffOpKind::SR_DIFF_MOVE,
+                "test" => DiffOpKind::SR_DIFF_TEST,
+                other => return Err(SurrealError::invalid_argument(format!("invalid patch operation '{other}'"))),
+            },
+            _ => return Err(SurrealError::invalid_argument("patch operation without an op")),
+        };
+        let path = match obj.remove("path") {
+            Some(sdbValue::String(path)) => path.to_string_t(),
+            _ => return Err(SurrealError::invalid_argument("patch operation without a path")),
+        };
+        let from = match (op, obj.remove("from")) {
+            (DiffOpKind::SR_DIFF_COPY | DiffOpKind::SR_DIFF_MOVE, Some(sdbValue::String(from))) => from.to_string_t(),
+            (DiffOpKind::SR_DIFF_COPY | DiffOpKind::SR_DIFF_MOVE, _) => {
+                return Err(SurrealError::invalid_argument("patch operation without a from path"))
+            }
+            _ => string_t::null(),
+        };
+        let value = obj.remove("value").unwrap_or_default().into();
+        Ok(DiffOp { op, path, from, value })
+    }
+}
+
+/// Converts the data of a `LIVE SELECT DIFF` notification, an array of patch objects
+pub(crate) fn diff_ops(data: sdbValue) -> Result<Vec<DiffOp>, SurrealError> {
+    let sdbValue::Array(arr) = data else {
+        return Err(SurrealError::invalid_argument("notification does not hold a diff"));
+    };
+    arr.into_iter()
+        .map(|op| match op {
+            sdbValue::Object(obj) => DiffOp::try_from(obj),
+            _ => Err(SurrealError::invalid_argument("notification does not hold a diff")),
+        })
+        .collect()
+}
+
+impl TryFrom<sdbNotification<sdbValue>> for DiffNotification {
+    type Error = SurrealError;
+
+    fn try_from(n: sdbNotification<sdbValue>) -> Result<Self, Self::Error> {
+        let ArrayGen { ptr, len } = diff_ops(n.data)?.make_array();
+        Ok(DiffNotification {
+            query_id: Uuid::from(n.query_id),
+            action: Action::from(n.action),
+            ops: ptr,
+            ops_len: len,
+        })
+    }
+}
+
+/// Free the patch operations held by a diff notification
+#[export_name = "sr_free_diff_notification"]
+pub extern "C" fn free_diff_notification(notification: DiffNotification) {
+    ArrayGen {
+        ptr: notification.ops,
+        len: notification.ops_len,
+    }
+    .free()
+}
+
+/// Apply patch operations to a value
+///
+/// Keeps a local copy of a record in sync with the notifications of a `LIVE SELECT DIFF` query,
+/// starting from the empty value for a record which was not known yet.
+/// Either every operation is applied, or `value` is left unchanged and an error is returned.
+/// Returns SR_NONE on success.
+///
+/// Operations are applied in order like JSON Patch (RFC 6902) operations, with one exception made for
+/// the diffs generated by SurrealDB: the elements removed from the end of an array are listed in
+/// ascending order, so consecutive removals from the same array are applied together, each index
+/// referring to the array before the first of them. Patches from other sources which remove several
+/// elements of an array in a row are therefore not applied as RFC 6902 specifies.
+/// Removing or replacing a value which does not exist, or adding past the end of an array, fails.
+///
+/// # Safety
+///
+/// - `err_ptr` must be a valid pointer or null
+/// - `value` must be a valid pointer to a Value
+/// - `ops` must point to `ops_len` operations, it may be null if `ops_len` is 0
+///
+/// # Examples
+///
+/// ```c
+/// sr_value_t *cache = sr_value_none();
+/// sr_diff_notification_t not;
+/// while (sr_stream_next_diff(stream, &not) > 0)
+/// {
+///     if (sr_value_apply_diff(&err, cache, not.ops, not.ops_len) < 0)
+///     {
+///         printf("%s", err);
+///     }
+///     sr_free_diff_notification(not);
+/// }
+/// ```
+#[export_name = "sr_value_apply_diff"]
+pub extern "C" fn value_apply_diff(
+    err_ptr: *mut string_t,
+    value: *mut Value,
+    ops: *const DiffOp,
+    ops_len: c_int,
+) -> c_int {
+    if value.is_null() {
+        write_error(err_ptr, SurrealError::invalid_argument("value is null"));
+        return SR_ERROR;
+    }
+    let ops = match (ops.is_null(), usize::try_from(ops_len)) {
+        (_, Ok(0)) => &[][..],
+        (false, Ok(len)) => unsafe { std::slice::from_raw_parts(ops, len) },
+        (true, _) => {
+            write_error(err_ptr, SurrealError::invalid_argument("ops is null"));
+            return SR_ERROR;
+        }
+        (false, Err(_)) => {
+            write_error(err_ptr, SurrealError::invalid_argument("ops_len is negative"));
+            return SR_ERROR;
+        }
+    };
+
+    let mut doc: sdbValue = unsafe { &*value }.clone().into();
+    match apply(&mut doc, ops) {
+        Ok(()) => {
+            unsafe { *value = doc.into() };
+            SR_NONE
+        }
+        Err(e) => {
+            write_error(err_ptr, e);
+            SR_ERROR
+        }
+    }
+}
+
+fn apply(doc: &mut sdbValue, ops: &[DiffOp]) -> Result<(), SurrealError> {
+    let mut i = 0;
+    while i < ops.len() {
+        let op = &ops[i];
+        let path = parse_path(&op.path)?;
+        i += 1;
+        match op.op {
+            DiffOpKind::SR_DIFF_ADD => add(doc, &path, op.value.clone().into())?,
+            DiffOpKind::SR_DIFF_REMOVE => {
+                // the server lists the elements removed from the end of an array in ascending
+                // order, so a run of removals from one array is applied from the last index
+                let Some((last, parent)) = path.split_last() else {
+                    *doc = sdbValue::None;
+                    continue;
+                };
+                let mut indexes = vec![last.clone()];
+                while i < ops.len() && ops[i].op == DiffOpKind::SR_DIFF_REMOVE {
+                    let next = parse_path(&ops[i].path)?;
+                    match next.split_last() {
+                        Some((last, p)) if p == parent && matches!(pick(doc, parent), Some(sdbValue::Array(_))) => {
+                            indexes.push(last.clone());
+                            i += 1;
+                        }
+                        _ => break,
+                    }
+                }
+                if indexes.len() > 1 {
+                    let mut indexes = indexes.iter().map(|i| index(i)).collect::<Result<Vec<_>, _>>()?;
+                    indexes.sort_unstable_by(|a, b| b.cmp(a));
+                    indexes.dedup();
+                    if let Some(sdbValue::Array(arr)) = pick_mut(doc, parent) {
+                        if let Some(i) = indexes.first().filter(|i| **i >= arr.len()) {
+                            return Err(SurrealError::invalid_argument(format!("index {i} out of bounds")));
+                        }
+                        for i in indexes {
+                            arr.remove(i);
+                        }
+                    }
+                } else {
+                    remove(doc, &path)?;
+                }
+            }
+            DiffOpKind::SR_DIFF_REPLACE => {
+                if pick(doc, &path).is_none() {
+                    return Err(SurrealError::invalid_argument(format!("no value at {}", op_path(op))));
+                }
+                put(doc, &path, op.value.clone().into())?
+            }
+            DiffOpKind::SR_DIFF_CHANGE => {
+                let sdbValue::String(patch) = sdbValue::from(op.value.clone()) else {
+                    return Err(SurrealError::invalid_argument("change operation without a text patch"));
+                };
+                let Some(sdbValue::String(text)) = pick(doc, &path) else {
+                    return Err(SurrealError::invalid_argument(format!("no string at {}", op_path(op))));
+                };
+                let dmp = dmp::new();
+                let patches = dmp
+                    .patch_from_text(patch)
+                    .map_err(|e| SurrealError::invalid_argument(format!("invalid text patch: {e:?}")))?;
+                let (text, _) = dmp
+                    .patch_apply(&patches, text)
+                    .map_err(|e| SurrealError::invalid_argument(format!("invalid text patch: {e:?}")))?;
+                put(doc, &path, sdbValue::String(text.into_iter().collect()))?;
+            }
+            DiffOpKind::SR_DIFF_COPY | DiffOpKind::SR_DIFF_MOVE => {
+                let from = parse_path(&op.from)?;
+                let Some(val) = pick(doc, &from).cloned() else {
+                    let from = String::from(op.from.clone());
+                    return Err(SurrealError::invalid_argument(format!("no value at {from}")));
+                };
+                if op.op == DiffOpKind::SR_DIFF_MOVE {
+                    remove(doc, &from)?;
+                }
+                add(doc, &path, val)?;
+            }
+            DiffOpKind::SR_DIFF_TEST => {
+                let expected: sdbValue = op.value.clone().into();
+                if *pick(doc, &path).unwrap_or(&sdbValue::None) != expected {
+                    return Err(SurrealError::invalid_argument(format!("test of {} failed", op_path(op))));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn op_path(op: &DiffOp) -> String {
+    String::from(op.path.clone())
+}
+
+/// Splits a JSON pointer into its segments, the empty path and `/` both designate the document
+fn parse_path(path: &string_t) -> Result<Vec<String>, SurrealError> {
+    if path.0.is_null() {
+        return Err(SurrealError::invalid_argument("patch operation without a path"));
+    }
+    let path = unsafe { CStr::from_ptr(path.0) }.to_str()?;
+    match path.trim_start_matches('/') {
+        "" => Ok(Vec::new()),
+        path => Ok(path.split('/').map(|s| s.replace("~1", "/").replace("~0", "~")).collect()),
+    }
+}
+
+fn index(segment: &str) -> Result<usize, SurrealError> {
+    segment
+        .parse()
+        .map_err(|_| SurrealError::invalid_argument(format!("invalid array index '{segment}'")))
+}
+
+fn pick<'a>(doc: &'a sdbValue, path: &[String]) -> Option<&'a sdbValue> {
+    path.iter().try_fold(doc, |v, segment| match v {
+        sdbValue::Object(obj) => obj.get(segment),
+        sdbValue::Array(arr) => arr.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+fn pick_mut<'a>(doc: &'a mut sdbValue, path: &[String]) -> Option<&'a mut sdbValue> {
+    path.iter().try_fold(doc, |v, segment| match v {
+        sdbValue::Object(obj) => obj.get_mut(segment),
+        sdbValue::Array(arr) => arr.get_mut(segment.parse::<usize>().ok()?),
+        _ => None,
+    })
+}
+
+/// Returns the container of the value at `path`, creating missing objects on the way
+fn parent_mut<'a>(doc: &'a mut sdbValue, path: &[String]) -> Result<&'a mut sdbValue, SurrealError> {
+    path.iter().try_fold(doc, |v, segment| {
+        if matches!(v, sdbValue::None | sdbValue::Null) {
+            *v = sdbValue::Object(sdbObject::new());
+        }
+        match v {
+            sdbValue::Object(obj) => Ok(obj.entry(segment.clone()).or_insert(sdbValue::None)),
+            sdbValue::Array(arr) => {
+                let i = index(segment)?;
+                arr.get_mut(i).ok_or_else(|| SurrealError::invalid_argument(format!("index {i} out of bounds")))
+            }
+            _ => Err(SurrealError::invalid_argument(format!("cannot descend into '{segment}'"))),
+        }
+    })
+}
+
+fn put(doc: &mut sdbValue, path: &[String], val: sdbValue) -> Result<(), SurrealError> {
+    let Some((last, parent)) = path.split_last() else {
+        *doc = val;
+        return Ok(());
+    };
+    let parent = parent_mut(doc, parent)?;
+    if matches!(parent, sdbValue::None | sdbValue::Null) {
+        *parent = sdbValue::Object(sdbObject::new());
+    }
+    match parent {
+        sdbValue::Object(obj) => {
+            obj.insert(last.clone(), val);
+        }
+        sdbValue::Array(arr) if last == "-" => arr.push(val),
+        sdbValue::Array(arr) => match index(last)? {
+            i if i < arr.len() => arr[i] = val,
+            i if i == arr.len() => arr.push(val),
+            i => return Err(SurrealError::invalid_argument(format!("index {i} out of bounds"))),
+        },
+        _ => return Err(SurrealError::invalid_argument(format!("cannot set '{last}'"))),
+    }
+    Ok(())
+}
+
+fn add(doc: &mut sdbValue, path: &[String], val: sdbValue) -> Result<(), SurrealError> {
+    if let Some((last, parent)) = path.split_last() {
+        if last != "-" {
+            if let Some(sdbValue::Array(arr)) = pick_mut(doc, parent) {
+                let i = index(last)?;
+                if i > arr.len() {
+                    return Err(SurrealError::invalid_argument(format!("index {i} out of bounds")));
+                }
+                arr.insert(i, val);
+                return Ok(());
+            }
+        }
+    }
+    put(doc, path, val)
+}
+
+fn remove(doc: &mut sdbValue, path: &[String]) -> Result<(), SurrealError> {
+    let Some((last, parent)) = path.split_last() else {
+        *doc = sdbValue::None;
+        return Ok(());
+    };
+    let removed = match pick_mut(doc, parent) {
+        Some(sdbValue::Object(obj)) => obj.remove(last).is_some(),
+        Some(sdbValue::Array(arr)) => match index(last)? {
+            i if i < arr.len() => {
+                arr.remove(i);
+                true
+            }
+            _ => false,
+        },
+        _ => false,
+    };
+    match removed {
+        true => Ok(()),
+        false => Err(SurrealError::invalid_argument(format!("no value at /{}", path.join("/")))),
+    }
+}
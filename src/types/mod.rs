@@ -1,11 +1,13 @@
 pub mod array;
 pub mod bytes;
 pub mod credentials;
+pub mod diff;
 pub mod duration;
 pub mod geometry;
 pub mod notification;
 pub mod number;
 pub mod object;
+pub mod response;
 pub mod result;
 pub mod stream;
 pub mod string;
@@ -0,0 +1,125 @@
+use std::ffi::c_int;
+
+use surrealdb::types::Value as sdbValue;
+
+use crate::duration::Duration;
+use crate::result::SurrealError;
+use crate::value::Value;
+
+/// The result of a single statement of a query
+struct Statement {
+    value: Option<Value>,
+    error: Option<SurrealError>,
+    time: Duration,
+}
+
+/// The response to a query, holding the result of each statement as it was returned
+///
+/// Unlike `sr_query`, values are not wrapped into arrays, so `RETURN 1` and `RETURN [1]` can be told apart.
+/// Pointers returned by the accessors are owned by the response and valid until `sr_response_free`.
+///
+/// # Examples
+///
+/// ```c
+/// sr_response_t *resp;
+/// int count = sr_query_response(db, &err, &resp, "RETURN 1; SELECT * FROM ONLY foo:1; THROW 'oops'", NULL);
+/// if (count < 0) {
+///     printf("%s", err);
+///     return 1;
+/// }
+/// for (int i = 0; i < count; i++) {
+///     const sr_error_t *e = sr_response_error(resp, i);
+///     if (e) {
+///         printf("statement %d failed: %s\n", i, e->msg);
+///         continue;
+///     }
+///     sr_value_print(sr_response_value(resp, i));
+/// }
+/// sr_response_free(resp);
+/// ```
+pub struct Response {
+    statements: Vec<Statement>,
+}
+
+impl Response {
+    pub(crate) fn new(results: Vec<(Option<std::time::Duration>, Result<sdbValue, SurrealError>)>) -> Response {
+        let statements = results
+            .into_iter()
+            .map(|(time, res)| {
+                let time = time.map(Duration::from).unwrap_or(Duration { secs: 0, nanos: 0 });
+                match res {
+                    Ok(val) => Statement {
+                        value: Some(Value::from(val)),
+                        error: None,
+                        time,
+                    },
+                    Err(e) => Statement {
+                        value: None,
+                        error: Some(e),
+                        time,
+                    },
+                }
+            })
+            .collect();
+        Response { statements }
+    }
+
+    fn statement(&self, index: c_int) -> Option<&Statement> {
+        usize::try_from(index).ok().and_then(|i| self.statements.get(i))
+    }
+
+    /// Get the number of statements in a response
+    #[export_name = "sr_response_count"]
+    pub extern "C" fn count(&self) -> c_int {
+        self.statements.len() as c_int
+    }
+
+    /// Check whether a statement succeeded
+    ///
+    /// Returns false if the statement failed or `index` is out of range.
+    #[export_name = "sr_response_is_ok"]
+    pub extern "C" fn is_ok(&self, index: c_int) -> bool {
+        self.statement(index).is_some_and(|s| s.error.is_none())
+    }
+
+    /// Get the value returned by a statement
+    ///
+    /// Returns null if the statement failed or `index` is out of range.
+    #[export_name = "sr_response_value"]
+    pub extern "C" fn value(&self, index: c_int) -> *const Value {
+        match self.statement(index).and_then(|s| s.value.as_ref()) {
+            Some(val) => val,
+            None => std::ptr::null(),
+        }
+    }
+
+    /// Get the error of a failed statement
+    ///
+    /// Returns null if the statement succeeded or `index` is out of range.
+    #[export_name = "sr_response_error"]
+    pub extern "C" fn error(&self, index: c_int) -> *const SurrealError {
+        match self.statement(index).and_then(|s| s.error.as_ref()) {
+            Some(err) => err,
+            None => std::ptr::null(),
+        }
+    }
+
+    /// Get the execution time of a statement
+    ///
+    /// Returns a zero duration if the time is unknown or `index` is out of range.
+    #[export_name = "sr_response_time"]
+    pub extern "C" fn time(&self, index: c_int) -> Duration {
+        match self.statement(index) {
+            Some(s) => s.time.clone(),
+            None => Duration { secs: 0, nanos: 0 },
+        }
+    }
+
+    /// Free a response and all values and errors it holds
+    #[export_name = "sr_response_free"]
+    pub extern "C" fn free(resp: *mut Response) {
+        if !resp.is_null() {
+            let _ = unsafe { Box::from_raw(resp) };
+        }
+    }
+}
@@ -1,31 +1,330 @@
-use std::{ffi::c_int, fmt::Display};
+use std::{
+    cell::RefCell,
+    ffi::{c_char, c_int},
+    fmt::Display,
+    str::Utf8Error,
+};
+
+use surrealdb::types::{ErrorDetails, NotAllowedError, QueryError, SurrealValue, ValidationError, Value as sdbValue};
 
 use crate::string::string_t;
-use crate::{utils::CStringExt2, SR_ERROR};
+use crate::{utils::CStringExt2, SR_CANCELLED, SR_ERROR, SR_FATAL, SR_TIMEOUT};
 
 use super::array::{Array, ArrayGen};
+use super::object::Object;
 
+/// The category of an error
+///
+/// Stable across releases, so it can be used to drive retries and error handling
+/// without comparing error messages.
+#[allow(non_camel_case_types)]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// No error
+    SR_ERROR_KIND_NONE,
+    /// Unexpected error inside SurrealDB or the bindings
+    SR_ERROR_KIND_INTERNAL,
+    /// An argument was null, not valid UTF-8 or otherwise invalid
+    SR_ERROR_KIND_INVALID_ARGUMENT,
+    /// A query could not be parsed
+    SR_ERROR_KIND_PARSE,
+    /// A table, record, namespace or database does not exist
+    SR_ERROR_KIND_NOT_FOUND,
+    /// A table, record, namespace or database already exists
+    SR_ERROR_KIND_ALREADY_EXISTS,
+    /// A transaction conflicted with another transaction and can be retried
+    SR_ERROR_KIND_CONFLICT,
+    /// Authentication failed or the session is not permitted to perform the action
+    SR_ERROR_KIND_UNAUTHORIZED,
+    /// The action is disabled by the capabilities of the database
+    SR_ERROR_KIND_NOT_ALLOWED,
+    /// A query or transaction timed out
+    SR_ERROR_KIND_TIMEOUT,
+    /// A query was cancelled
+    SR_ERROR_KIND_CANCELLED,
+    /// A statement was not executed because another statement in its transaction failed
+    SR_ERROR_KIND_NOT_EXECUTED,
+    /// The connection to the database failed
+    SR_ERROR_KIND_CONNECTION,
+    /// A value could not be serialized or deserialized
+    SR_ERROR_KIND_SERIALIZATION,
+    /// The feature is not supported by the connection or its configuration
+    SR_ERROR_KIND_CONFIGURATION,
+    /// An error raised with THROW
+    SR_ERROR_KIND_THROWN,
+}
+
+/// A structured error
+///
 /// when code = 0 there is no error
+///
+/// `details` is null unless SurrealDB provided structured details for the error.
+/// Must be freed with `sr_free_error`
+///
+/// Only the calls with an `_ex` variant write a structured error: `sr_connect_ex`, `sr_use_ns_ex`,
+/// `sr_use_db_ex`, `sr_signin_ex`, `sr_signup_ex`, `sr_authenticate_ex`, `sr_query_ex`, `sr_select_ex`,
+/// `sr_create_ex`, `sr_insert_ex`, `sr_update_ex`, `sr_upsert_ex`, `sr_merge_ex`, `sr_delete_ex`,
+/// `sr_relate_ex` and `sr_run_ex`. Every other call only writes the message of its error, this includes
+/// `sr_begin`, `sr_commit`, `sr_cancel`, `sr_export`, `sr_import`, `sr_health`, `sr_version`,
+/// `sr_insert_relation`, `sr_invalidate`, `sr_kill`, `sr_select_live`, `sr_set`, `sr_unset`, the
+/// `sr_patch_*` functions and the `sr_batch_*`, `sr_transaction_*`, `sr_load_*`, `sr_export_*` and
+/// `sr_import_*` functions. Their code and kind are read with `sr_last_error_code` and
+/// `sr_last_error_kind` right after the failing call, on the same thread; their details are not available.
 #[repr(C)]
+#[derive(Clone)]
 pub struct SurrealError {
-    code: c_int,
-    msg: string_t,
+    /// The code returned by the failed call, SR_ERROR, SR_CANCELLED, SR_TIMEOUT or SR_FATAL
+    pub code: c_int,
+    /// Human readable error message
+    pub msg: string_t,
+    /// The category of the error
+    pub kind: ErrorKind,
+    /// Structured details of the error, may be null
+    pub details: Option<Box<Object>>,
 }
 
+// the message and details are owned by the error and never shared
+unsafe impl Send for SurrealError {}
+
 impl SurrealError {
     pub fn empty() -> Self {
         Self {
             code: 0,
             msg: string_t::null(),
+            kind: ErrorKind::SR_ERROR_KIND_NONE,
+            details: None,
         }
     }
 
     pub fn from_msg(msg: impl Display) -> Self {
-        let out = Self {
+        Self::new(ErrorKind::SR_ERROR_KIND_INTERNAL, msg)
+    }
+
+    pub fn new(kind: ErrorKind, msg: impl Display) -> Self {
+        Self {
             code: SR_ERROR,
             msg: msg.to_string().to_string_t(),
+            kind,
+            details: None,
+        }
+    }
+
+    pub fn invalid_argument(msg: impl Display) -> Self {
+        Self::new(ErrorKind::SR_ERROR_KIND_INVALID_ARGUMENT, msg)
+    }
+
+    /// The error reported when an operation was cancelled or interrupted
+    pub fn cancelled(msg: impl Display) -> Self {
+        let mut err = Self::new(ErrorKind::SR_ERROR_KIND_CANCELLED, msg);
+        err.code = SR_CANCELLED;
+        err
+    }
+
+    /// The error reported when an operation exceeded its deadline
+    pub fn timed_out(msg: impl Display) -> Self {
+        let mut err = Self::new(ErrorKind::SR_ERROR_KIND_TIMEOUT, msg);
+        err.code = SR_TIMEOUT;
+        err
+    }
+
+    /// The error reported when a poisoned connection is used
+    pub fn poisoned() -> Self {
+        let mut err = Self::from_msg("connection is poisoned by an earlier panic and must be reconnected");
+        err.code = SR_FATAL;
+        err
+    }
+
+    /// The error reported when a blocking call is made from a runtime thread, such as inside a completion callback
+    pub fn blocking_on_runtime() -> Self {
+        Self::from_msg("blocking calls can not be made from a completion callback, use the `_async` functions instead")
+    }
+
+    /// The error reported when an operation panicked
+    pub fn panicked(payload: &(dyn std::any::Any + Send)) -> Self {
+        let mut err = match payload.downcast_ref::<&str>() {
+            Some(e_str) => Self::from_msg(format!("Panicked with: {e_str}")),
+            None => Self::from_msg("Panicked"),
+        };
+        err.code = SR_FATAL;
+        err
+    }
+
+    /// Free an error's message and details
+    #[export_name = "sr_free_error"]
+    pub extern "C" fn free(err: SurrealError) {
+        let _ = err;
+    }
+}
+
+impl From<surrealdb::Error> for SurrealError {
+    fn from(e: surrealdb::Error) -> Self {
+        let kind = match e.details() {
+            ErrorDetails::Validation(Some(ValidationError::Parse)) => ErrorKind::SR_ERROR_KIND_PARSE,
+            ErrorDetails::Validation(None) if e.message().starts_with("Parse error") => ErrorKind::SR_ERROR_KIND_PARSE,
+            ErrorDetails::Validation(_) => ErrorKind::SR_ERROR_KIND_INVALID_ARGUMENT,
+            ErrorDetails::Configuration(_) => ErrorKind::SR_ERROR_KIND_CONFIGURATION,
+            ErrorDetails::Query(Some(QueryError::TimedOut { .. })) => ErrorKind::SR_ERROR_KIND_TIMEOUT,
+            ErrorDetails::Query(Some(QueryError::Cancelled)) => ErrorKind::SR_ERROR_KIND_CANCELLED,
+            ErrorDetails::Query(Some(QueryError::NotExecuted)) => ErrorKind::SR_ERROR_KIND_NOT_EXECUTED,
+            ErrorDetails::Serialization(_) => ErrorKind::SR_ERROR_KIND_SERIALIZATION,
+            ErrorDetails::NotAllowed(Some(NotAllowedError::Auth(_))) => ErrorKind::SR_ERROR_KIND_UNAUTHORIZED,
+            ErrorDetails::NotAllowed(_) => ErrorKind::SR_ERROR_KIND_NOT_ALLOWED,
+            ErrorDetails::NotFound(_) => ErrorKind::SR_ERROR_KIND_NOT_FOUND,
+            ErrorDetails::AlreadyExists(_) => ErrorKind::SR_ERROR_KIND_ALREADY_EXISTS,
+            ErrorDetails::Connection(_) => ErrorKind::SR_ERROR_KIND_CONNECTION,
+            ErrorDetails::Thrown => ErrorKind::SR_ERROR_KIND_THROWN,
+            _ => kind_from_msg(e.message()),
+        };
+
+        // details are serialized as { kind, details }, only the inner object is exposed
+        let details = match e.details().clone().into_value() {
+            sdbValue::Object(mut obj) => match obj.remove("details") {
+                Some(sdbValue::Object(inner)) => Some(Box::new(Object::from(inner))),
+                _ => None,
+            },
+            _ => None,
         };
-        out
+
+        Self {
+            details,
+            ..Self::new(kind, e.message())
+        }
+    }
+}
+
+/// Classify errors which SurrealDB reports without structured details
+fn kind_from_msg(msg: &str) -> ErrorKind {
+    let msg = msg.to_lowercase();
+    if msg.contains("conflict") || msg.contains("can be retried") {
+        ErrorKind::SR_ERROR_KIND_CONFLICT
+    } else if msg.contains("already exists") {
+        ErrorKind::SR_ERROR_KIND_ALREADY_EXISTS
+    } else if msg.contains("parse error") {
+        ErrorKind::SR_ERROR_KIND_PARSE
+    } else if msg.contains("does not exist") || msg.contains("not found") {
+        ErrorKind::SR_ERROR_KIND_NOT_FOUND
+    } else if msg.contains("timed out") || msg.contains("timeout") {
+        ErrorKind::SR_ERROR_KIND_TIMEOUT
+    } else if msg.contains("not allowed") || msg.contains("permission") {
+        ErrorKind::SR_ERROR_KIND_UNAUTHORIZED
+    } else {
+        ErrorKind::SR_ERROR_KIND_INTERNAL
+    }
+}
+
+impl From<Utf8Error> for SurrealError {
+    fn from(e: Utf8Error) -> Self {
+        Self::invalid_argument(e)
+    }
+}
+
+impl From<&str> for SurrealError {
+    fn from(msg: &str) -> Self {
+        Self::from_msg(msg)
+    }
+}
+
+impl From<String> for SurrealError {
+    fn from(msg: String) -> Self {
+        Self::from_msg(msg)
+    }
+}
+
+impl From<string_t> for SurrealError {
+    fn from(msg: string_t) -> Self {
+        Self {
+            msg,
+            ..Self::new(ErrorKind::SR_ERROR_KIND_INTERNAL, "")
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<SurrealError> = RefCell::new(SurrealError::empty());
+}
+
+/// Record an error as the last error of the current thread
+pub(crate) fn set_last_error(err: impl Into<SurrealError>) {
+    let err = err.into();
+    LAST_ERROR.with(|last| *last.borrow_mut() = err);
+}
+
+/// The last error raised on the current thread
+pub(crate) fn get_last_error() -> SurrealError {
+    LAST_ERROR.with(|last| last.borrow().clone())
+}
+
+/// Record an error as the last error of the current thread and write it to `err_ptr`
+///
+/// If `err_ptr` is null, the error is only recorded.
+pub(crate) fn write_error<E: ErrorOut>(err_ptr: E, err: impl Into<SurrealError>) {
+    let err = err.into();
+    set_last_error(err.clone());
+    err_ptr.write(err)
+}
+
+/// Returns the message of the last error raised on the calling thread
+///
+/// Every `sr_*` call that fails records its error here, whether or not an `err_ptr` was passed.
+/// Successful calls do not clear it, use `sr_clear_error` before a call to detect new errors.
+///
+/// Returns null if there is no error. The string is owned by the thread and stays valid until
+/// the next failing call or `sr_clear_error` on the same thread, it must not be freed.
+///
+/// # Examples
+///
+/// ```c
+/// sr_surreal_t *db;
+/// if (sr_connect(NULL, &db, "mem://") < 0) {
+///     printf("error connecting to db: %s\n", sr_last_error());
+///     return 1;
+/// }
+/// ```
+#[export_name = "sr_last_error"]
+pub extern "C" fn last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().msg.0)
+}
+
+/// Returns the code of the last error raised on the calling thread
+///
+/// SR_ERROR or SR_FATAL, or 0 if there is no error
+#[export_name = "sr_last_error_code"]
+pub extern "C" fn last_error_code() -> c_int {
+    LAST_ERROR.with(|last| last.borrow().code)
+}
+
+/// Returns the kind of the last error raised on the calling thread
+#[export_name = "sr_last_error_kind"]
+pub extern "C" fn last_error_kind() -> ErrorKind {
+    LAST_ERROR.with(|last| last.borrow().kind)
+}
+
+/// Clear the last error of the calling thread
+#[export_name = "sr_clear_error"]
+pub extern "C" fn clear_error() {
+    LAST_ERROR.with(|last| *last.borrow_mut() = SurrealError::empty());
+}
+
+/// Destination of errors reported by an API call, either a message or a structured error
+pub(crate) trait ErrorOut: Copy {
+    /// Write the error, if the destination is not null
+    fn write(self, err: SurrealError);
+}
+
+impl ErrorOut for *mut string_t {
+    fn write(self, err: SurrealError) {
+        if !self.is_null() {
+            unsafe { self.write(err.msg) }
+        }
+    }
+}
+
+impl ErrorOut for *mut SurrealError {
+    fn write(self, err: SurrealError) {
+        if !self.is_null() {
+            unsafe { self.write(err) }
+        }
     }
 }
 
@@ -57,10 +356,10 @@ pub struct ArrayResult {
 }
 
 impl ArrayResult {
-    pub fn err(msg: impl Display) -> Self {
+    pub fn err(err: impl Into<SurrealError>) -> Self {
         Self {
             ok: Array::empty(),
-            err: SurrealError::from_msg(msg),
+            err: err.into(),
         }
     }
     pub fn ok(ok: Array) -> Self {
@@ -1,12 +1,31 @@
-use std::ffi::c_int;
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    ffi::{c_int, c_void},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
 
-use async_channel::Receiver;
-use futures::StreamExt;
-use surrealdb::method::Stream as sdbStream;
+use async_channel::{Receiver, TryRecvError};
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt};
 use surrealdb::types::{Value as sdbValue, Notification as PublicNotification};
+use surrealdb::Notification as sdbNotification;
 use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
 
-use crate::SR_ERROR;
+use crate::callback::UserData;
+use crate::cancel::timeout_from_ms;
+use crate::future::Completion;
+use crate::result::{set_last_error, write_error, SurrealError};
+use crate::string::string_t;
+use crate::{on_runtime, SR_ERROR, SR_TIMEOUT};
+use crate::diff::DiffNotification;
 use crate::{notification::Notification, SR_CLOSED, SR_NONE};
 
 use super::array::MakeArray;
@@ -16,20 +35,35 @@ use super::array::MakeArray;
 /// May be sent across threads, but must not be aliased.
 /// Use `sr_stream_next` to receive notifications and `sr_stream_kill` to close.
 pub struct Stream {
-    inner: sdbStream<sdbValue>,
+    inner: BoxStream<'static, surrealdb::Result<sdbNotification<sdbValue>>>,
     rt: Handle,
 }
 
 impl Stream {
-    pub fn new(inner: sdbStream<sdbValue>, rt: Handle) -> Stream {
-        Stream { inner, rt }
+    /// Wraps the stream of a live selection or of the `LIVE SELECT` statements of a query
+    pub fn new<S>(inner: S, rt: Handle) -> Stream
+    where
+        S: futures::Stream<Item = surrealdb::Result<sdbNotification<sdbValue>>> + Send + 'static,
+    {
+        Stream { inner: inner.boxed(), rt }
     }
 }
 
+/// How long to wait for a notification
+#[derive(Clone, Copy)]
+enum Wait {
+    /// Only take a notification which was already received
+    Poll,
+    /// Wait until one is received, or the timeout if any has passed
+    Block(Option<Duration>),
+}
+
 impl Stream {
     /// Blocks until next item is received on stream
     /// will return 1 and write notification to notification_ptr if received
-    /// will return SR_NONE if the stream is closed
+    /// will return SR_NONE if the stream is closed, unlike every other function reading a stream which
+    /// returns SR_CLOSED, this is kept for compatibility
+    /// will return SR_ERROR if the stream reported an error, which can be read with sr_last_error
     ///
     /// sr_stream_t *stream;
     /// if (sr_select_live(db, &err, &stream, "foo") < 0)
@@ -46,18 +80,111 @@ impl Stream {
     /// sr_stream_kill(stream);
     #[export_name = "sr_stream_next"]
     pub extern "C" fn next(&mut self, notification_ptr: *mut Notification) -> c_int {
-        match self.rt.block_on(self.inner.next()) {
-            Some(Ok(n)) => {
-                let notif = Notification {
-                    query_id: crate::uuid::Uuid::from(n.query_id),
-                    action: crate::notification::Action::from(n.action),
-                    data: crate::value::Value::from(n.data),
-                };
+        match self.next_impl(notification_ptr, Wait::Block(None)) {
+            SR_CLOSED => SR_NONE,
+            res => res,
+        }
+    }
+
+    /// Blocks until next item is received on stream, or `timeout_ms` milliseconds have passed
+    /// will return SR_TIMEOUT if no notification was received in time, with the stream left usable
+    /// will return SR_CLOSED if the stream is closed
+    /// otherwise identical to sr_stream_next, a negative `timeout_ms` waits indefinitely
+    #[export_name = "sr_stream_next_timeout"]
+    pub extern "C" fn next_timeout(&mut self, notification_ptr: *mut Notification, timeout_ms: c_int) -> c_int {
+        self.next_impl(notification_ptr, Wait::Block(timeout_from_ms(timeout_ms)))
+    }
+
+    /// Blocks until next item is received on the stream of a `LIVE SELECT DIFF` statement
+    /// will return 1 and write the notification, with its changes as patch operations, to notification_ptr
+    /// will return SR_CLOSED if the stream is closed
+    /// will return SR_ERROR if the notification does not hold a diff, otherwise identical to sr_stream_next
+    ///
+    /// The notification must be freed with sr_free_diff_notification
+    #[export_name = "sr_stream_next_diff"]
+    pub extern "C" fn next_diff(&mut self, notification_ptr: *mut DiffNotification) -> c_int {
+        self.next_diff_impl(notification_ptr, Wait::Block(None))
+    }
+
+    /// Blocks until next diff is received on stream, or `timeout_ms` milliseconds have passed
+    /// will return SR_TIMEOUT if no notification was received in time, with the stream left usable
+    /// otherwise identical to sr_stream_next_diff, a negative `timeout_ms` waits indefinitely
+    #[export_name = "sr_stream_next_diff_timeout"]
+    pub extern "C" fn next_diff_timeout(
+        &mut self,
+        notification_ptr: *mut DiffNotification,
+        timeout_ms: c_int,
+    ) -> c_int {
+        self.next_diff_impl(notification_ptr, Wait::Block(timeout_from_ms(timeout_ms)))
+    }
+
+    /// Returns immediately with the next notification if one was already received
+    /// will return 1 and write notification to notification_ptr if one was available
+    /// will return SR_NONE if no notification is pending, and SR_CLOSED if the stream is closed
+    /// will return SR_ERROR if the stream reported an error, which can be read with sr_last_error
+    #[export_name = "sr_stream_try_next"]
+    pub extern "C" fn try_next(&mut self, notification_ptr: *mut Notification) -> c_int {
+        self.next_impl(notification_ptr, Wait::Poll)
+    }
+
+    /// Returns immediately with the next diff if one was already received
+    /// otherwise identical to sr_stream_try_next, see sr_stream_next_diff for the notification
+    #[export_name = "sr_stream_try_next_diff"]
+    pub extern "C" fn try_next_diff(&mut self, notification_ptr: *mut DiffNotification) -> c_int {
+        self.next_diff_impl(notification_ptr, Wait::Poll)
+    }
+
+    fn next_impl(&mut self, notification_ptr: *mut Notification, wait: Wait) -> c_int {
+        match self.recv(wait) {
+            Ok(n) => {
+                unsafe { notification_ptr.write(to_notification(n)) }
+                1
+            }
+            Err(code) => code,
+        }
+    }
+
+    fn next_diff_impl(&mut self, notification_ptr: *mut DiffNotification, wait: Wait) -> c_int {
+        match self.recv(wait).map(DiffNotification::try_from) {
+            Ok(Ok(notif)) => {
                 unsafe { notification_ptr.write(notif) }
                 1
             }
-            Some(Err(_)) => SR_ERROR,
-            None => SR_NONE,
+            Ok(Err(e)) => {
+                set_last_error(e);
+                SR_ERROR
+            }
+            Err(code) => code,
+        }
+    }
+
+    /// Waits for the next notification, or returns the code to report to the caller
+    fn recv(&mut self, wait: Wait) -> Result<sdbNotification<sdbValue>, c_int> {
+        if matches!(wait, Wait::Block(_)) && on_runtime() {
+            set_last_error(SurrealError::blocking_on_runtime());
+            return Err(SR_ERROR);
+        }
+        let next = match wait {
+            Wait::Poll => self.inner.next().now_or_never(),
+            Wait::Block(timeout) => self.rt.block_on(async {
+                match timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, self.inner.next()).await.ok(),
+                    None => Some(self.inner.next().await),
+                }
+            }),
+        };
+        match (next, wait) {
+            (Some(Some(Ok(n))), _) => Ok(n),
+            (Some(Some(Err(e))), _) => {
+                set_last_error(e);
+                Err(SR_ERROR)
+            }
+            (Some(None), _) => Err(SR_CLOSED),
+            (None, Wait::Poll) => Err(SR_NONE),
+            (None, Wait::Block(_)) => {
+                set_last_error(SurrealError::timed_out("timed out waiting for notification"));
+                Err(SR_TIMEOUT)
+            }
         }
     }
 
@@ -68,8 +195,359 @@ impl Stream {
     #[export_name = "sr_stream_kill"]
     pub extern "C" fn kill(stream: *mut Stream) {
         let boxed = unsafe { Box::from_raw(stream) };
-        let handle = boxed.rt.clone();
-        handle.block_on(async { drop(boxed) });
+        // inside a completion callback the runtime is already entered, and blocking on it would panic
+        if on_runtime() {
+            drop(boxed);
+        } else {
+            let handle = boxed.rt.clone();
+            handle.block_on(async { drop(boxed) });
+        }
+    }
+}
+
+fn to_notification(n: sdbNotification<sdbValue>) -> Notification {
+    Notification {
+        query_id: crate::uuid::Uuid::from(n.query_id),
+        action: crate::notification::Action::from(n.action),
+        data: crate::value::Value::from(n.data),
+    }
+}
+
+/// Callback receiving the notifications of a subscribed stream
+///
+/// For each notification `code` is 1 and `notification` points to it, it is only valid for the
+/// duration of the callback. Once the stream ends the callback is invoked a last time, with
+/// `code` set to SR_CLOSED, or to a negative error code with `err` pointing to the error.
+pub type NotificationCallback = Option<
+    extern "C" fn(userdata: *mut c_void, code: c_int, notification: *const Notification, err: *const SurrealError),
+>;
+
+/// A stream whose notifications are dispatched to a callback
+///
+/// Created with `sr_stream_subscribe`, must be freed with `sr_stream_unsubscribe`.
+pub struct Subscription {
+    state: Arc<SubscriptionState>,
+    task: JoinHandle<()>,
+}
+
+struct SubscriptionState {
+    active: AtomicBool,
+    /// held while the callback runs, so unsubscribing can wait for it to return
+    dispatch: Mutex<()>,
+}
+
+thread_local! {
+    /// The subscription whose callback is running on this thread, if any
+    static DISPATCHING: Cell<*const SubscriptionState> = const { Cell::new(ptr::null()) };
+}
+
+impl SubscriptionState {
+    /// Invoke the callback unless the subscription was cancelled, returns false if it was
+    fn dispatch(&self, deliver: impl FnOnce()) -> bool {
+        let _guard = self.dispatch.lock().unwrap_or_else(PoisonError::into_inner);
+        if !self.active.load(Ordering::Acquire) {
+            return false;
+        }
+        DISPATCHING.with(|d| d.set(self));
+        catch_unwind(AssertUnwindSafe(deliver)).ok();
+        DISPATCHING.with(|d| d.set(ptr::null()));
+        true
+    }
+}
+
+impl Stream {
+    /// Dispatch the notifications of a stream to a callback
+    ///
+    /// Takes ownership of the stream, which must not be used afterwards, and invokes `callback`
+    /// from a runtime worker thread for each notification, so it must be thread safe and must not block.
+    /// On success writes the subscription to *sub_ptr and returns SR_NONE, on failure the stream is
+    /// killed and SR_ERROR is returned, with the error available from sr_last_error.
+    ///
+    /// sr_subscription_t *sub;
+    /// if (sr_stream_subscribe(stream, &sub, on_notification, &state) < 0)
+    /// {
+    ///     return 1;
+    /// }
+    /// ...
+    /// sr_stream_unsubscribe(sub);
+    #[export_name = "sr_stream_subscribe"]
+    pub extern "C" fn subscribe(
+        stream: *mut Stream,
+        sub_ptr: *mut *mut Subscription,
+        callback: NotificationCallback,
+        userdata: *mut c_void,
+    ) -> c_int {
+        if stream.is_null() {
+            set_last_error(SurrealError::invalid_argument("stream is null"));
+            return SR_ERROR;
+        }
+        let (Some(callback), false) = (callback, sub_ptr.is_null()) else {
+            Stream::kill(stream);
+            set_last_error(SurrealError::invalid_argument("callback or sub_ptr is null"));
+            return SR_ERROR;
+        };
+        let Stream { mut inner, rt } = *unsafe { Box::from_raw(stream) };
+        let userdata = UserData(userdata);
+
+        let state = Arc::new(SubscriptionState {
+            active: AtomicBool::new(true),
+            dispatch: Mutex::new(()),
+        });
+        let task_state = state.clone();
+        let task = rt.spawn(async move {
+            while let Some(next) = inner.next().await {
+                let delivered = match next {
+                    Ok(n) => task_state.dispatch(|| {
+                        let notification = to_notification(n);
+                        callback(userdata.get(), 1, &notification, ptr::null())
+                    }),
+                    Err(e) => {
+                        let e = SurrealError::from(e);
+                        task_state.dispatch(|| callback(userdata.get(), e.code, ptr::null(), &e));
+                        return;
+                    }
+                };
+                if !delivered {
+                    return;
+                }
+            }
+            task_state.dispatch(|| callback(userdata.get(), SR_CLOSED, ptr::null(), ptr::null()));
+        });
+
+        let sub = Box::new(Subscription { state, task });
+        unsafe { sub_ptr.write(Box::into_raw(sub)) };
+        SR_NONE
+    }
+}
+
+impl Subscription {
+    /// Stop dispatching notifications, kill the live query and free the subscription
+    ///
+    /// May be called from any thread, including from the callback itself. Once it returns the
+    /// callback is not running and will not be invoked again.
+    #[export_name = "sr_stream_unsubscribe"]
+    pub extern "C" fn unsubscribe(sub: *mut Subscription) {
+        if sub.is_null() {
+            return;
+        }
+        let sub = unsafe { Box::from_raw(sub) };
+        sub.state.active.store(false, Ordering::Release);
+        let in_callback = DISPATCHING.with(|d| ptr::eq(d.get(), Arc::as_ptr(&sub.state)));
+        if !in_callback {
+            // wait for a callback which is already running to return
+            drop(sub.state.dispatch.lock());
+        }
+        // the stream is dropped along with the task, on the runtime
+        sub.task.abort();
+    }
+}
+
+/// What a stream of a set reported
+enum SetEvent {
+    Notification(sdbNotification<sdbValue>),
+    Error(SurrealError),
+    Closed,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Member {
+    Open,
+    Closed,
+    Removed,
+}
+
+#[derive(Default)]
+struct SetState {
+    events: VecDeque<(c_int, SetEvent)>,
+    members: Vec<Member>,
+}
+
+struct SetQueue {
+    state: Mutex<SetState>,
+    ready: Condvar,
+    completion: Completion,
+}
+
+impl SetQueue {
+    fn push(&self, index: c_int, event: SetEvent) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let member = &mut state.members[index as usize];
+        if *member == Member::Removed {
+            return;
+        }
+        if let SetEvent::Closed = event {
+            *member = Member::Closed;
+        }
+        state.events.push_back((index, event));
+        drop(state);
+        self.ready.notify_all();
+        self.completion.notify();
+    }
+}
+
+/// A set of streams whose notifications are received together
+///
+/// Each stream added to the set is read on its connection's runtime, and its notifications are
+/// queued until taken with `sr_stream_set_next`, so a single thread can wait on all of them.
+/// May be sent across threads, but must not be aliased. Free with `sr_stream_set_free`.
+pub struct StreamSet {
+    queue: Arc<SetQueue>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl StreamSet {
+    /// Create an empty stream set
+    #[export_name = "sr_stream_set_new"]
+    pub extern "C" fn new() -> *mut StreamSet {
+        let queue = Arc::new(SetQueue {
+            state: Mutex::new(SetState::default()),
+            ready: Condvar::new(),
+            completion: Completion::new(),
+        });
+        Box::into_raw(Box::new(StreamSet { queue, tasks: Vec::new() }))
+    }
+
+    /// Add a stream to the set
+    ///
+    /// Takes ownership of the stream, which must not be used afterwards.
+    /// Returns the index identifying the stream in the set, indexes are not reused.
+    ///
+    /// sr_stream_set_t *set = sr_stream_set_new();
+    /// int orders = sr_stream_set_add(set, orders_stream);
+    /// int users = sr_stream_set_add(set, users_stream);
+    #[export_name = "sr_stream_set_add"]
+    pub extern "C" fn add(&mut self, stream: *mut Stream) -> c_int {
+        if stream.is_null() {
+            set_last_error(SurrealError::invalid_argument("stream is null"));
+            return SR_ERROR;
+        }
+        let Stream { mut inner, rt } = *unsafe { Box::from_raw(stream) };
+        let index = self.tasks.len() as c_int;
+        self.queue.state.lock().unwrap_or_else(PoisonError::into_inner).members.push(Member::Open);
+
+        let queue = self.queue.clone();
+        self.tasks.push(rt.spawn(async move {
+            while let Some(next) = inner.next().await {
+                let event = match next {
+                    Ok(n) => SetEvent::Notification(n),
+                    Err(e) => SetEvent::Error(e.into()),
+                };
+                queue.push(index, event);
+            }
+            queue.push(index, SetEvent::Closed);
+        }));
+        index
+    }
+
+    /// Remove a stream from the set and kill it
+    ///
+    /// Notifications of the stream which were not taken yet are discarded.
+    #[export_name = "sr_stream_set_remove"]
+    pub extern "C" fn remove(&mut self, index: c_int) {
+        let Some(task) = usize::try_from(index).ok().and_then(|i| self.tasks.get(i)) else {
+            return;
+        };
+        let mut state = self.queue.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.members[index as usize] = Member::Removed;
+        state.events.retain(|(i, _)| *i != index);
+        // the stream is dropped along with the task, on its runtime
+        task.abort();
+    }
+
+    /// Waits until one of the streams of the set reports, or `timeout_ms` milliseconds have passed
+    /// will return 1 and write the index of the stream to index_ptr and the notification to notification_ptr
+    /// will return SR_CLOSED and write the index of the stream to index_ptr if that stream closed
+    /// will return SR_ERROR and write the index of the stream to index_ptr if that stream reported an error,
+    /// which can be read with sr_last_error
+    /// will return SR_TIMEOUT if nothing was received in time, and SR_NONE if every stream was closed or removed
+    ///
+    /// A `timeout_ms` of 0 returns immediately and a negative one waits indefinitely.
+    ///
+    /// int index;
+    /// sr_notification_t not;
+    /// while (sr_stream_set_next(set, &index, &not, -1) > 0)
+    /// {
+    ///     printf("stream %d: ", index);
+    ///     sr_print_notification(&not);
+    /// }
+    #[export_name = "sr_stream_set_next"]
+    pub extern "C" fn next(
+        &mut self,
+        index_ptr: *mut c_int,
+        notification_ptr: *mut Notification,
+        timeout_ms: c_int,
+    ) -> c_int {
+        let deadline = timeout_from_ms(timeout_ms).map(|t| Instant::now() + t);
+        let mut state = self.queue.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let (index, event) = loop {
+            if let Some(next) = state.events.pop_front() {
+                break next;
+            }
+            if !state.members.contains(&Member::Open) {
+                return SR_NONE;
+            }
+            state = match deadline {
+                None => self.queue.ready.wait(state).unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let Some(timeout) = deadline.checked_duration_since(Instant::now()) else {
+                        set_last_error(SurrealError::timed_out("timed out waiting for notification"));
+                        return SR_TIMEOUT;
+                    };
+                    self.queue.ready.wait_timeout(state, timeout).unwrap_or_else(PoisonError::into_inner).0
+                }
+            };
+        };
+        drop(state);
+
+        if !index_ptr.is_null() {
+            unsafe { index_ptr.write(index) }
+        }
+        match event {
+            SetEvent::Notification(n) => {
+                unsafe { notification_ptr.write(to_notification(n)) }
+                1
+            }
+            SetEvent::Error(e) => {
+                set_last_error(e);
+                SR_ERROR
+            }
+            SetEvent::Closed => SR_CLOSED,
+        }
+    }
+
+    /// Returns a descriptor which becomes readable when a stream of the set reports
+    ///
+    /// The descriptor is owned by the set, must not be closed and is only supported on unix platforms.
+    /// Drain it before taking the pending notifications with a `timeout_ms` of 0, as with `sr_surreal_fd`.
+    ///
+    /// int fd = sr_stream_set_fd(set, &err);
+    /// struct pollfd pfd = {.fd = fd, .events = POLLIN};
+    /// poll(&pfd, 1, -1);
+    ///
+    /// char buf[64];
+    /// while (read(fd, buf, sizeof(buf)) > 0) {}
+    /// while (sr_stream_set_next(set, &index, &not, 0) > 0) {}
+    #[export_name = "sr_stream_set_fd"]
+    pub extern "C" fn fd(&self, err_ptr: *mut string_t) -> c_int {
+        match self.queue.completion.fd() {
+            Ok(fd) => fd,
+            Err(e) => {
+                write_error(err_ptr, e);
+                SR_ERROR
+            }
+        }
+    }
+
+    /// Kill every stream of the set and free it
+    #[export_name = "sr_stream_set_free"]
+    pub extern "C" fn free(set: *mut StreamSet) {
+        if set.is_null() {
+            return;
+        }
+        let set = unsafe { Box::from_raw(set) };
+        for task in &set.tasks {
+            task.abort();
+        }
     }
 }
 
@@ -79,12 +557,13 @@ impl Stream {
 /// Uses synchronous blocking receives, so no async drop is required.
 pub struct RpcStream {
     rx: Receiver<PublicNotification>,
+    rt: Handle,
 }
 
 impl RpcStream {
-    /// Create a new RpcStream from a notification receiver
-    pub fn new(rx: Receiver<PublicNotification>) -> Self {
-        RpcStream { rx }
+    /// Create a new RpcStream from a notification receiver, timed waits run on `rt`
+    pub fn new(rx: Receiver<PublicNotification>, rt: Handle) -> Self {
+        RpcStream { rx, rt }
     }
 
     /// Get the next notification from the stream
@@ -95,11 +574,46 @@ impl RpcStream {
     /// Free the result with sr_free_byte_arr.
     #[export_name = "sr_rpc_stream_next"]
     pub extern "C" fn next(&mut self, res_ptr: *mut *mut u8) -> c_int {
-        let notification = match self.rx.recv_blocking() {
-            Ok(n) => n,
-            Err(_) => return SR_CLOSED,
+        match self.rx.recv_blocking() {
+            Ok(n) => Self::write_notification(n, res_ptr),
+            Err(_) => SR_CLOSED,
+        }
+    }
+
+    /// Get the next notification from the stream, if one was already received
+    ///
+    /// Returns SR_NONE without waiting if no notification is pending, otherwise identical to
+    /// sr_rpc_stream_next.
+    #[export_name = "sr_rpc_stream_try_next"]
+    pub extern "C" fn try_next(&mut self, res_ptr: *mut *mut u8) -> c_int {
+        match self.rx.try_recv() {
+            Ok(n) => Self::write_notification(n, res_ptr),
+            Err(TryRecvError::Empty) => SR_NONE,
+            Err(TryRecvError::Closed) => SR_CLOSED,
+        }
+    }
+
+    /// Get the next notification from the stream, waiting at most `timeout_ms` milliseconds
+    ///
+    /// Returns SR_TIMEOUT if no notification was received in time, with the stream left usable,
+    /// otherwise identical to sr_rpc_stream_next. A negative `timeout_ms` waits indefinitely.
+    #[export_name = "sr_rpc_stream_next_timeout"]
+    pub extern "C" fn next_timeout(&mut self, res_ptr: *mut *mut u8, timeout_ms: c_int) -> c_int {
+        let Some(timeout) = timeout_from_ms(timeout_ms) else {
+            return self.next(res_ptr);
         };
+        let next = self.rt.block_on(async { tokio::time::timeout(timeout, self.rx.recv()).await });
+        match next {
+            Ok(Ok(n)) => Self::write_notification(n, res_ptr),
+            Ok(Err(_)) => SR_CLOSED,
+            Err(_) => {
+                set_last_error(SurrealError::timed_out("timed out waiting for notification"));
+                SR_TIMEOUT
+            }
+        }
+    }
 
+    fn write_notification(notification: PublicNotification, res_ptr: *mut *mut u8) -> c_int {
         let mut obj = surrealdb::types::Object::new();
         obj.insert(
             "id".to_string(),
@@ -113,7 +627,8 @@ impl RpcStream {
 
         let cbor_val = crate::rpc::value_to_cbor(&sdbValue::Object(obj));
         let mut res = Vec::new();
-        if ciborium::into_writer(&cbor_val, &mut res).is_err() {
+        if let Err(e) = ciborium::into_writer(&cbor_val, &mut res) {
+            set_last_error(format!("CBOR encode error: {e}"));
             return SR_ERROR;
         }
         let out = res.make_array();
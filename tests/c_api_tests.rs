@@ -18,6 +18,8 @@ extern "C" {
     fn test_sr_use_db() -> c_int;
     fn test_sr_version() -> c_int;
     fn test_sr_health() -> c_int;
+    fn test_sr_connect_with_options() -> c_int;
+    fn test_sr_surreal_reconnect() -> c_int;
 
     // Authentication Tests
     fn test_sr_authenticate() -> c_int;
@@ -39,6 +41,14 @@ extern "C" {
     fn test_sr_query() -> c_int;
     fn test_sr_run() -> c_int;
     fn test_sr_relate() -> c_int;
+    fn test_sr_query_response() -> c_int;
+    fn test_sr_prepare() -> c_int;
+    fn test_sr_parse_check() -> c_int;
+    fn test_sr_cursor() -> c_int;
+    fn test_sr_transaction() -> c_int;
+    fn test_sr_transaction_run() -> c_int;
+    fn test_sr_batch() -> c_int;
+    fn test_sr_insert_many() -> c_int;
 
     // Patch Tests
     fn test_sr_patch_add() -> c_int;
@@ -56,6 +66,8 @@ extern "C" {
 
     // Live Query Tests
     fn test_sr_select_live() -> c_int;
+    fn test_sr_live_query() -> c_int;
+    fn test_sr_select_live_diff() -> c_int;
 
     // Import/Export Tests
     fn test_sr_export() -> c_int;
@@ -105,6 +117,10 @@ extern "C" {
     fn test_sr_stream_kill() -> c_int;
     fn test_sr_rpc_stream_next() -> c_int;
     fn test_sr_rpc_stream_free() -> c_int;
+    fn test_sr_stream_try_next() -> c_int;
+    fn test_sr_rpc_stream_try_next() -> c_int;
+    fn test_sr_stream_subscribe() -> c_int;
+    fn test_sr_stream_set() -> c_int;
 
     // Utility Tests
     fn test_sr_free_string() -> c_int;
@@ -128,6 +144,30 @@ extern "C" {
 
     // Kill Live Query Test
     fn test_sr_kill() -> c_int;
+
+    // Error Tests
+    fn test_sr_error_kind() -> c_int;
+    fn test_sr_last_error() -> c_int;
+
+    // Async Tests
+    fn test_sr_async_callbacks() -> c_int;
+    fn test_sr_future() -> c_int;
+    fn test_sr_cancel_token() -> c_int;
+    fn test_sr_timeout() -> c_int;
+    fn test_sr_cancel_token_bind() -> c_int;
+
+    // Runtime Tests
+    fn test_sr_runtime_shared() -> c_int;
+    fn test_sr_runtime_current_thread() -> c_int;
+
+    // Load Tests
+    fn test_sr_load_ndjson() -> c_int;
+    fn test_sr_load_csv() -> c_int;
+
+    // Backup Tests
+    fn test_sr_export_import_buffer() -> c_int;
+    fn test_sr_export_options() -> c_int;
+    fn test_sr_import_export_progress() -> c_int;
 }
 
 const TEST_PASS: c_int = 0;
@@ -162,6 +202,8 @@ c_test!(sr_use_ns, test_sr_use_ns);
 c_test!(sr_use_db, test_sr_use_db);
 c_test!(sr_version, test_sr_version);
 c_test!(sr_health, test_sr_health);
+c_test!(sr_connect_with_options, test_sr_connect_with_options);
+c_test!(sr_surreal_reconnect, test_sr_surreal_reconnect);
 
 // ============================================================================
 // Authentication Tests
@@ -192,6 +234,14 @@ c_test!(sr_merge, test_sr_merge);
 c_test!(sr_query, test_sr_query);
 c_test!(sr_run, test_sr_run);
 c_test!(sr_relate, test_sr_relate);
+c_test!(sr_query_response, test_sr_query_response);
+c_test!(sr_prepare, test_sr_prepare);
+c_test!(sr_parse_check, test_sr_parse_check);
+c_test!(sr_cursor, test_sr_cursor);
+c_test!(sr_transaction, test_sr_transaction);
+c_test!(sr_transaction_run, test_sr_transaction_run);
+c_test!(sr_batch, test_sr_batch);
+c_test!(sr_insert_many, test_sr_insert_many);
 
 // ============================================================================
 // Patch Tests
@@ -221,6 +271,8 @@ c_test!(sr_unset, test_sr_unset);
 // ============================================================================
 
 c_test!(sr_select_live, test_sr_select_live);
+c_test!(sr_live_query, test_sr_live_query);
+c_test!(sr_select_live_diff, test_sr_select_live_diff);
 
 // ============================================================================
 // Import/Export Tests
@@ -288,6 +340,10 @@ c_test!(sr_stream_next, test_sr_stream_next);
 c_test!(sr_stream_kill, test_sr_stream_kill);
 c_test!(sr_rpc_stream_next, test_sr_rpc_stream_next);
 c_test!(sr_rpc_stream_free, test_sr_rpc_stream_free);
+c_test!(sr_stream_try_next, test_sr_stream_try_next);
+c_test!(sr_rpc_stream_try_next, test_sr_rpc_stream_try_next);
+c_test!(sr_stream_subscribe, test_sr_stream_subscribe);
+c_test!(sr_stream_set, test_sr_stream_set);
 
 // ============================================================================
 // Utility Tests
@@ -326,3 +382,42 @@ c_test!(sr_object_keys, test_sr_object_keys);
 // ============================================================================
 
 c_test!(sr_kill, test_sr_kill);
+
+// ============================================================================
+// Error Tests
+// ============================================================================
+
+c_test!(sr_error_kind, test_sr_error_kind);
+c_test!(sr_last_error, test_sr_last_error);
+
+// ============================================================================
+// Async Tests
+// ============================================================================
+
+c_test!(sr_async_callbacks, test_sr_async_callbacks);
+c_test!(sr_future, test_sr_future);
+c_test!(sr_cancel_token, test_sr_cancel_token);
+c_test!(sr_timeout, test_sr_timeout);
+c_test!(sr_cancel_token_bind, test_sr_cancel_token_bind);
+
+// ============================================================================
+// Runtime Tests
+// ============================================================================
+
+c_test!(sr_runtime_shared, test_sr_runtime_shared);
+c_test!(sr_runtime_current_thread, test_sr_runtime_current_thread);
+
+// ============================================================================
+// Load Tests
+// ============================================================================
+
+c_test!(sr_load_ndjson, test_sr_load_ndjson);
+c_test!(sr_load_csv, test_sr_load_csv);
+
+// ============================================================================
+// Backup Tests
+// ============================================================================
+
+c_test!(sr_export_import_buffer, test_sr_export_import_buffer);
+c_test!(sr_export_options, test_sr_export_options);
+c_test!(sr_import_export_progress, test_sr_import_export_progress);